@@ -1,11 +1,45 @@
 pub mod error;
 pub mod models;
 
-use crate::{error::Result, models::YouTubeVideo};
+use crate::{
+    error::Result,
+    models::{parse_upload_date, YouTubeChannel, YouTubeVideo},
+};
 use dotenvy::dotenv;
-use sqlx::{pool::Pool, sqlite::SqlitePoolOptions, Sqlite};
+use sqlx::{pool::Pool, sqlite::SqlitePoolOptions, FromRow, Sqlite};
 use std::path::PathBuf;
 
+/// A row from the videos table, along with the channel it belongs to.
+#[derive(FromRow)]
+struct VideoRow {
+    id: String,
+    title: String,
+    duration: Option<String>,
+    saved_path: Option<String>,
+    upload_date: Option<String>,
+    view_count: Option<i64>,
+    channel_id: String,
+    channel_title: Option<String>,
+}
+
+impl TryFrom<VideoRow> for YouTubeVideo {
+    type Error = crate::error::Error;
+
+    fn try_from(row: VideoRow) -> Result<Self> {
+        let mut video = YouTubeVideo::new(
+            &row.channel_title.unwrap_or_default(),
+            row.duration,
+            &row.id,
+            row.saved_path.map(PathBuf::from),
+            &row.title,
+        )?;
+        video.channel_id = row.channel_id;
+        video.upload_date = row.upload_date.as_deref().and_then(parse_upload_date);
+        video.view_count = row.view_count;
+        Ok(video)
+    }
+}
+
 pub async fn establish_connection() -> Result<Pool<Sqlite>> {
     dotenv().ok();
     let database_url = std::env::var("YOUTUBE_DB_URL")?;
@@ -13,31 +47,133 @@ pub async fn establish_connection() -> Result<Pool<Sqlite>> {
     Ok(pool)
 }
 
+/// The columns selected for a `VideoRow`.
+///
+/// Not every copy of the archive records the upload date and view count of its videos, so these
+/// are selected as NULL when the videos table doesn't have them. That means the queries have to be
+/// built at runtime rather than checked against a particular archive.
+async fn video_columns(pool: &Pool<Sqlite>) -> Result<String> {
+    let columns: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info('videos')")
+        .fetch_all(pool)
+        .await?;
+    let optional = |name: &str| {
+        if columns.iter().any(|c| c == name) {
+            format!("videos.{name} AS {name}")
+        } else {
+            format!("NULL AS {name}")
+        }
+    };
+    Ok(format!(
+        r#"
+            videos.id AS id,
+            videos.title AS title,
+            videos.duration AS duration,
+            videos.saved_path AS saved_path,
+            {},
+            {},
+            channels.id AS channel_id,
+            channels.title AS channel_title
+        "#,
+        optional("upload_date"),
+        optional("view_count")
+    ))
+}
+
 pub async fn get_video(id: &str) -> Result<YouTubeVideo> {
     let pool = establish_connection().await?;
-    let row = sqlx::query!(
+    let sql = format!(
         r#"
-        SELECT 
-            videos.id,
-            videos.title,
-            videos.duration,
-            videos.saved_path,
-            channels.title AS channel_title
+        SELECT {}
         FROM videos
         JOIN channels ON videos.channel_id = channels.id
         WHERE videos.id = $1;
         "#,
-        id
+        video_columns(&pool).await?
+    );
+    let row = sqlx::query_as::<_, VideoRow>(&sql)
+        .bind(id)
+        .fetch_one(&pool)
+        .await?;
+    YouTubeVideo::try_from(row)
+}
+
+/// Gets the channels in the archive, with the number of videos saved from each one.
+pub async fn get_channels() -> Result<Vec<YouTubeChannel>> {
+    let pool = establish_connection().await?;
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            channels.id AS "id!",
+            channels.title,
+            COUNT(videos.id) AS "video_count!: i64"
+        FROM channels
+        LEFT JOIN videos ON videos.channel_id = channels.id
+        GROUP BY channels.id
+        ORDER BY channels.title COLLATE NOCASE;
+        "#
     )
-    .fetch_one(&pool)
+    .fetch_all(&pool)
     .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| YouTubeChannel {
+            id: row.id,
+            title: row.title.unwrap_or_default(),
+            video_count: row.video_count,
+        })
+        .collect())
+}
+
+/// Gets the videos from a channel, newest first.
+///
+/// The channel can be given by its ID or its title.
+pub async fn get_videos_for_channel(channel: &str) -> Result<Vec<YouTubeVideo>> {
+    let pool = establish_connection().await?;
+    let sql = format!(
+        r#"
+        SELECT {}
+        FROM videos
+        JOIN channels ON videos.channel_id = channels.id
+        WHERE channels.id = $1 OR channels.title = $1 COLLATE NOCASE
+        ORDER BY upload_date DESC, title;
+        "#,
+        video_columns(&pool).await?
+    );
+    let rows = sqlx::query_as::<_, VideoRow>(&sql)
+        .bind(channel)
+        .fetch_all(&pool)
+        .await?;
+
+    rows.into_iter().map(YouTubeVideo::try_from).collect()
+}
+
+/// Searches the titles of the videos in the archive, ignoring case.
+///
+/// A video whose ID is the search term is also found. Any `%` or `_` in the term is matched
+/// literally rather than as a wildcard.
+pub async fn search_videos(term: &str) -> Result<Vec<YouTubeVideo>> {
+    let pool = establish_connection().await?;
+    let term = term.trim();
+    let escaped = term
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    let pattern = format!("%{escaped}%");
+    let sql = format!(
+        r#"
+        SELECT {}
+        FROM videos
+        JOIN channels ON videos.channel_id = channels.id
+        WHERE videos.title LIKE $1 ESCAPE '\' OR videos.id = $2
+        ORDER BY upload_date, title;
+        "#,
+        video_columns(&pool).await?
+    );
+    let rows = sqlx::query_as::<_, VideoRow>(&sql)
+        .bind(pattern)
+        .bind(term)
+        .fetch_all(&pool)
+        .await?;
 
-    let video = YouTubeVideo::new(
-        &row.channel_title.unwrap(),
-        row.duration,
-        &row.id.unwrap(),
-        row.saved_path.map(PathBuf::from),
-        &row.title,
-    )?;
-    Ok(video)
+    rows.into_iter().map(YouTubeVideo::try_from).collect()
 }
@@ -1,15 +1,40 @@
 use crate::error::Result;
 
+use chrono::NaiveDate;
 use std::path::PathBuf;
 
+/// Parses an upload date, which yt-dlp writes in `YYYYMMDD` form.
+pub fn parse_upload_date(s: &str) -> Option<NaiveDate> {
+    let s = s.trim();
+    NaiveDate::parse_from_str(s, "%Y%m%d")
+        .or_else(|_| NaiveDate::parse_from_str(s, "%Y-%m-%d"))
+        .ok()
+}
+
+#[derive(Clone)]
+pub struct YouTubeChannel {
+    pub id: String,
+    pub title: String,
+    pub video_count: i64,
+}
+
+impl YouTubeChannel {
+    pub fn print_row(&self) {
+        println!("{}: {} ({} videos)", self.id, self.title, self.video_count);
+    }
+}
+
 #[derive(Clone)]
 pub struct YouTubeVideo {
+    pub channel_id: String,
     pub channel_name: String,
     pub description: Option<String>,
     pub duration: Option<String>,
     pub id: String,
     pub saved_path: Option<PathBuf>,
     pub title: String,
+    pub upload_date: Option<NaiveDate>,
+    pub view_count: Option<i64>,
 }
 
 impl YouTubeVideo {
@@ -41,21 +66,64 @@ impl YouTubeVideo {
         };
 
         Ok(YouTubeVideo {
+            channel_id: String::new(),
             channel_name: channel_name.to_string(),
             description,
             duration,
             id: id.to_string(),
             saved_path,
             title: title.to_string(),
+            upload_date: None,
+            view_count: None,
         })
     }
 
+    pub fn link(&self) -> String {
+        format!("https://www.youtube.com/watch?v={}", self.id)
+    }
+
     pub fn print(&self) {
         println!("ID: {}", self.id);
         println!("Title: {}", self.title);
+        println!("Channel: {} ({})", self.channel_name, self.channel_id);
+        println!(
+            "Uploaded: {}",
+            self.upload_date
+                .map(|d| d.to_string())
+                .unwrap_or_else(|| "-".to_string())
+        );
+        println!(
+            "Views: {}",
+            self.view_count
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "-".to_string())
+        );
+        println!("Duration: {}", self.duration.as_deref().unwrap_or("-"));
+        println!("Link: {}", self.link());
+        if let Some(path) = &self.saved_path {
+            println!("Saved to: {}", path.to_string_lossy());
+        }
         println!(
             "Description: {}",
             self.description.as_ref().unwrap_or(&"-".to_string())
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_upload_date_should_accept_the_yt_dlp_form() {
+        assert_eq!(
+            parse_upload_date("20060911"),
+            NaiveDate::from_ymd_opt(2006, 9, 11)
+        );
+        assert_eq!(
+            parse_upload_date("2006-09-11"),
+            NaiveDate::from_ymd_opt(2006, 9, 11)
+        );
+        assert_eq!(parse_upload_date("NA"), None);
+    }
+}
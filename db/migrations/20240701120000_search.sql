@@ -0,0 +1,65 @@
+-- Full-text search across the catalogue.
+--
+-- Each searchable table gets an immutable function that builds its search vector, with the more
+-- significant fields weighted higher. The same function is used for the index expression and in
+-- the search query, so the planner can use the index.
+CREATE FUNCTION master_video_search_vector(title VARCHAR, description TEXT, nist_notes TEXT)
+RETURNS tsvector AS $$
+    SELECT
+        setweight(to_tsvector('english', coalesce(title, '')), 'A') ||
+        setweight(to_tsvector('english', coalesce(description, '')), 'B') ||
+        setweight(to_tsvector('english', coalesce(nist_notes, '')), 'C')
+$$ LANGUAGE SQL IMMUTABLE;
+
+CREATE INDEX master_videos_search_idx ON master_videos
+USING GIN (master_video_search_vector(title, description, nist_notes));
+
+CREATE FUNCTION event_timestamp_search_vector(description VARCHAR)
+RETURNS tsvector AS $$
+    SELECT setweight(to_tsvector('english', coalesce(description, '')), 'B')
+$$ LANGUAGE SQL IMMUTABLE;
+
+CREATE INDEX event_timestamps_search_idx ON event_timestamps
+USING GIN (event_timestamp_search_vector(description));
+
+CREATE FUNCTION nist_video_search_vector(
+    video_title VARCHAR, notes TEXT, additional_notes TEXT)
+RETURNS tsvector AS $$
+    SELECT
+        setweight(to_tsvector('english', coalesce(video_title, '')), 'A') ||
+        setweight(to_tsvector('english', coalesce(notes, '')), 'B') ||
+        setweight(to_tsvector('english', coalesce(additional_notes, '')), 'C')
+$$ LANGUAGE SQL IMMUTABLE;
+
+CREATE INDEX nist_videos_search_idx ON nist_videos
+USING GIN (nist_video_search_vector(video_title, notes, additional_notes));
+
+CREATE FUNCTION nist_tape_search_vector(tape_name VARCHAR, tape_source VARCHAR)
+RETURNS tsvector AS $$
+    SELECT
+        setweight(to_tsvector('english', coalesce(tape_name, '')), 'A') ||
+        setweight(to_tsvector('english', coalesce(tape_source, '')), 'C')
+$$ LANGUAGE SQL IMMUTABLE;
+
+CREATE INDEX nist_tapes_search_idx ON nist_tapes
+USING GIN (nist_tape_search_vector(tape_name, tape_source));
+
+CREATE FUNCTION news_broadcast_search_vector(description TEXT)
+RETURNS tsvector AS $$
+    SELECT setweight(to_tsvector('english', coalesce(description, '')), 'B')
+$$ LANGUAGE SQL IMMUTABLE;
+
+CREATE INDEX news_broadcasts_search_idx ON news_broadcasts
+USING GIN (news_broadcast_search_vector(description));
+
+CREATE FUNCTION person_search_vector(
+    name VARCHAR, historical_title VARCHAR, description VARCHAR)
+RETURNS tsvector AS $$
+    SELECT
+        setweight(to_tsvector('english', coalesce(name, '')), 'A') ||
+        setweight(to_tsvector('english', coalesce(historical_title, '')), 'B') ||
+        setweight(to_tsvector('english', coalesce(description, '')), 'C')
+$$ LANGUAGE SQL IMMUTABLE;
+
+CREATE INDEX people_search_idx ON people
+USING GIN (person_search_vector(name, historical_title, description));
@@ -0,0 +1,16 @@
+-- A record of every change saved to the catalogue.
+--
+-- Each entry holds a snapshot of the record before and after the change, so the history of a
+-- record can be displayed and an earlier revision restored. The snapshot is empty before a record
+-- is first added.
+CREATE TABLE audit_log (
+    id SERIAL PRIMARY KEY,
+    entity VARCHAR NOT NULL,
+    entity_id INTEGER NOT NULL,
+    before JSONB,
+    after JSONB NOT NULL,
+    changed_by VARCHAR NOT NULL,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX audit_log_entity_idx ON audit_log (entity, entity_id);
@@ -0,0 +1,12 @@
+-- The version of each editable record, which is incremented every time the record is saved.
+--
+-- A record can be open in an editor for a long time. The version is read when the record is
+-- loaded and checked again before it is saved, so changes saved by someone else in the meantime
+-- are not silently overwritten.
+ALTER TABLE master_videos ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE news_affiliates ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE news_broadcasts ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE news_networks ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE nist_tapes ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE nist_videos ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE videos ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,5 @@
+-- Events with a duration, such as a report or an interview, can record the offset at which they
+-- end.
+ALTER TABLE event_timestamps ADD COLUMN end_timestamp INTERVAL;
+ALTER TABLE event_timestamps ADD CONSTRAINT event_timestamps_end_after_start
+    CHECK (end_timestamp IS NULL OR end_timestamp > timestamp);
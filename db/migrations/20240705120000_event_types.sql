@@ -0,0 +1,39 @@
+-- The kinds of event a timestamp can mark.
+--
+-- These were a Postgres enum, which needed a migration and a code change to add a new kind of
+-- event. Events with a canonical time, such as the impacts and collapses, are the key events that
+-- the times of day of a video are checked and synchronised against.
+CREATE TABLE event_types (
+    slug VARCHAR PRIMARY KEY,
+    label VARCHAR NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    canonical_time TIME
+);
+
+INSERT INTO event_types (slug, label, description, canonical_time) VALUES
+    ('camera-source', 'Camera source', 'A change in the source of the footage', NULL),
+    ('jumper', 'Jumper', 'Someone falling or jumping from the towers', NULL),
+    ('key', 'Key', 'An event of particular significance', NULL),
+    ('normal', 'Normal', 'Any other event', NULL),
+    ('person', 'Person', 'A person of interest appears or is mentioned', NULL),
+    ('pentagon-attack', 'Pentagon attack', 'AA77 hits the Pentagon', '09:37:46'),
+    ('report', 'Report', 'A report from a journalist', NULL),
+    ('wtc1-collapse', 'WTC1 collapse', 'The collapse of the North Tower', '10:28:22'),
+    ('wtc1-impact', 'WTC1 impact', 'AA11 hits the North Tower', '08:46:40'),
+    ('wtc2-collapse', 'WTC2 collapse', 'The collapse of the South Tower', '09:58:59'),
+    ('wtc2-impact', 'WTC2 impact', 'UA175 hits the South Tower', '09:03:02');
+
+ALTER TABLE event_timestamps ALTER COLUMN event_type TYPE VARCHAR USING (
+    CASE event_type
+        WHEN 'camerasource' THEN 'camera-source'
+        WHEN 'pentagonattack' THEN 'pentagon-attack'
+        WHEN 'wtc1collapse' THEN 'wtc1-collapse'
+        WHEN 'wtc1impact' THEN 'wtc1-impact'
+        WHEN 'wtc2collapse' THEN 'wtc2-collapse'
+        WHEN 'wtc2impact' THEN 'wtc2-impact'
+        ELSE event_type::VARCHAR
+    END
+);
+ALTER TABLE event_timestamps ADD CONSTRAINT event_timestamps_event_type_fkey
+    FOREIGN KEY (event_type) REFERENCES event_types(slug) ON UPDATE CASCADE;
+DROP TYPE event_type;
@@ -0,0 +1,15 @@
+-- The tables from NIST's Access database other than Videos and Tapes.
+--
+-- They don't have a schema of their own here. Each row is kept with its values as text, in the
+-- same order as the columns, so nothing in the database is lost when it's imported.
+CREATE TABLE nist_tables (
+    name VARCHAR PRIMARY KEY,
+    columns TEXT[] NOT NULL
+);
+
+CREATE TABLE nist_table_rows (
+    table_name VARCHAR NOT NULL REFERENCES nist_tables (name) ON DELETE CASCADE,
+    row_number INTEGER NOT NULL,
+    row_values TEXT[] NOT NULL,
+    PRIMARY KEY (table_name, row_number)
+);
@@ -0,0 +1,15 @@
+-- The NIST videos each master video was made from.
+--
+-- Previously this could only be inferred from the paths of the master's release files. The
+-- links are seeded from the release files a master shares with the tapes of a NIST video.
+CREATE TABLE master_videos_nist_videos (
+    master_video_id INTEGER NOT NULL REFERENCES master_videos(id),
+    nist_video_id INTEGER NOT NULL REFERENCES nist_videos(video_id),
+    PRIMARY KEY (master_video_id, nist_video_id)
+);
+
+INSERT INTO master_videos_nist_videos (master_video_id, nist_video_id)
+SELECT DISTINCT mvrf.master_video_id, t.video_id
+FROM master_videos_release_files mvrf
+JOIN nist_tapes_release_files ntrf ON ntrf.release_file_id = mvrf.release_file_id
+JOIN nist_tapes t ON t.tape_id = ntrf.nist_tape_id;
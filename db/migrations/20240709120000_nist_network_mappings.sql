@@ -0,0 +1,11 @@
+-- Links the network names in NIST's videos table to our news networks and affiliates.
+--
+-- The names are free text from NIST's database, so they are mapped by hand. A name with neither
+-- a network nor an affiliate has been reviewed and has no match, e.g., a name that covers more
+-- than one network.
+CREATE TABLE nist_network_mappings (
+    nist_network TEXT PRIMARY KEY,
+    news_network_id INTEGER REFERENCES news_networks(id),
+    news_affiliate_id INTEGER REFERENCES news_affiliates(id),
+    CHECK (news_network_id IS NULL OR news_affiliate_id IS NULL)
+);
@@ -13,16 +13,24 @@ pub enum Error {
     CumulusAssetNameFieldNotFound,
     #[error("Could not convert from duration to the Postgres interval type")]
     DurationToPgIntervalConversionError,
+    #[error("The '{0}' event type is used by {1} timestamps")]
+    EventTypeInUse(String, i64),
+    #[error("Could not find event type '{0}'")]
+    EventTypeNotFound(String),
     #[error("Could not obtain metadata from file command: {0}")]
     FileCommandError(String),
     #[error("The completed master-video-record template does not match the expected format")]
     InvalidMasterVideoRecordFormat,
+    #[error("'{0}' is not a valid search result type")]
+    InvalidSearchResultType(String),
     #[error("The completed video-record template does not match the expected format")]
     InvalidVideoRecordFormat,
     #[error(transparent)]
     Io(#[from] std::io::Error),
     #[error(transparent)]
     MagickError(#[from] magick_rust::MagickError),
+    #[error("Could not read the Access database: {0}")]
+    MdbToolsError(String),
     #[error("Could not find master video with ID '{0}'")]
     MasterVideoNotFound(u32),
     #[error("A news broadcast cannot have both a network and an affiliate")]
@@ -41,12 +49,31 @@ pub enum Error {
     NistVideoNotFound(i32),
     #[error("Could not obtain path")]
     PathNotObtained,
+    #[error("The {0} with ID {1} has been deleted")]
+    RecordDeleted(String, i32),
     #[error("Could not find release with ID '{0}'")]
     ReleaseNotFound(u32),
     #[error(transparent)]
     RegexError(#[from] regex::Error),
     #[error(transparent)]
     SqlError(#[from] sqlx::Error),
+    #[error("'{0}' is not a kind of record")]
+    UnknownEntity(String),
     #[error(transparent)]
     VarError(#[from] std::env::VarError),
+    #[error("The {0} with ID {1} was saved by someone else")]
+    VersionConflict(String, i32),
+}
+
+impl Error {
+    /// Whether the error is because the record that was asked for doesn't exist.
+    pub fn is_not_found(&self) -> bool {
+        matches!(
+            self,
+            Error::SqlError(sqlx::Error::RowNotFound)
+                | Error::MasterVideoNotFound(_)
+                | Error::NistTapeNotFound(_)
+                | Error::NistVideoNotFound(_)
+        )
+    }
 }
@@ -1,21 +1,24 @@
 pub mod cumulus;
 pub mod error;
 pub mod helpers;
+pub mod mdb;
 pub mod models;
-mod static_data;
 
 use crate::error::{Error, Result};
+use crate::mdb::{Table, TableSource};
 use crate::models::{
-    Category, EventTimestamp, EventType, MasterVideo, NewsAffiliate, NewsBroadcast, NewsNetwork,
-    NistTape, NistVideo, Person, PersonType, Release, ReleaseFile, Video,
+    AuditEntry, Category, EventTimestamp, EventType, MasterVideo, NewsAffiliate, NewsBroadcast,
+    NewsNetwork, NistIdentifier, NistNetworkMapping, NistTape, NistVideo, Person, PersonType,
+    Release, ReleaseFile, SearchResult, SearchResultType, Video,
 };
+use chrono::NaiveTime;
 use csv::ReaderBuilder;
 use dotenvy::dotenv;
 use sqlx::pool::Pool;
 use sqlx::postgres::PgPoolOptions;
-use sqlx::Postgres;
+use sqlx::{Acquire, PgConnection, Postgres};
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     path::{Path, PathBuf},
 };
 
@@ -26,6 +29,13 @@ pub async fn establish_connection() -> Result<Pool<Postgres>> {
     Ok(pool)
 }
 
+/// Begins a transaction, for saving several records so that either all of them are saved or none
+/// of them are.
+pub async fn begin_transaction() -> Result<sqlx::Transaction<'static, Postgres>> {
+    let pool = establish_connection().await?;
+    Ok(pool.begin().await?)
+}
+
 /// ***********************
 /// Read-based queries
 /// ***********************
@@ -150,7 +160,12 @@ pub async fn get_master_video(id: i32, pool: Option<Pool<Postgres>>) -> Result<M
     } else {
         establish_connection().await?
     };
+    let mut conn = pool.acquire().await?;
+    get_master_video_tx(&mut conn, id).await
+}
 
+/// Gets a master video using a connection, which can be one in the middle of a transaction.
+pub async fn get_master_video_tx(conn: &mut PgConnection, id: i32) -> Result<MasterVideo> {
     let row = sqlx::query!(
         r#"
             SELECT id, categories as "categories: Vec<Category>", title, date, description, links,
@@ -159,7 +174,7 @@ pub async fn get_master_video(id: i32, pool: Option<Pool<Postgres>>) -> Result<M
         "#,
         id
     )
-    .fetch_one(&pool)
+    .fetch_one(&mut *conn)
     .await?;
 
     let mut master = MasterVideo {
@@ -176,32 +191,42 @@ pub async fn get_master_video(id: i32, pool: Option<Pool<Postgres>>) -> Result<M
         news_broadcasts: Vec::new(),
         nist_files: Vec::new(),
         nist_notes: row.nist_notes,
+        nist_videos: Vec::new(),
         timestamps: Vec::new(),
         title: row.title,
     };
 
     let rows = sqlx::query!(
         r#"
-            SELECT id, description, timestamp, event_type as "event_type: EventType", time_of_day
-            FROM event_timestamps
-            WHERE master_video_id = $1
+            SELECT ts.id, ts.description, ts.timestamp, ts.end_timestamp, ts.time_of_day,
+                et.slug, et.label, et.description AS event_type_description, et.canonical_time
+            FROM event_timestamps ts
+            JOIN event_types et ON et.slug = ts.event_type
+            WHERE ts.master_video_id = $1
+            ORDER BY ts.timestamp, ts.id
         "#,
         id
     )
-    .fetch_all(&pool)
+    .fetch_all(&mut *conn)
     .await?;
     for row in rows {
         master.timestamps.push(EventTimestamp {
             id: row.id,
             description: row.description,
             timestamp: row.timestamp,
-            event_type: row.event_type,
+            end_timestamp: row.end_timestamp,
+            event_type: EventType {
+                slug: row.slug,
+                label: row.label,
+                description: row.event_type_description,
+                canonical_time: row.canonical_time,
+            },
             time_of_day: row.time_of_day,
         })
     }
 
-    let news_networks = get_news_networks(Some(pool.clone())).await?;
-    let news_affiliates = get_news_affiliates(Some(pool.clone())).await?;
+    let news_networks = get_news_networks_tx(conn).await?;
+    let news_affiliates = get_news_affiliates_tx(conn).await?;
     let rows = sqlx::query!(
         r#"
             SELECT nb.*
@@ -211,7 +236,7 @@ pub async fn get_master_video(id: i32, pool: Option<Pool<Postgres>>) -> Result<M
         "#,
         id
     )
-    .fetch_all(&pool)
+    .fetch_all(&mut *conn)
     .await?;
     for row in rows {
         if let Some(network_id) = row.news_network_id {
@@ -252,7 +277,7 @@ pub async fn get_master_video(id: i32, pool: Option<Pool<Postgres>>) -> Result<M
         "#,
         id
     )
-    .fetch_all(&pool)
+    .fetch_all(&mut *conn)
     .await?;
     for row in rows {
         master.people.push(Person {
@@ -273,7 +298,7 @@ pub async fn get_master_video(id: i32, pool: Option<Pool<Postgres>>) -> Result<M
         "#,
         id
     )
-    .fetch_all(&pool)
+    .fetch_all(&mut *conn)
     .await?;
     for row in rows {
         master
@@ -281,6 +306,23 @@ pub async fn get_master_video(id: i32, pool: Option<Pool<Postgres>>) -> Result<M
             .push((PathBuf::from(row.path), row.size as u64));
     }
 
+    master.nist_videos = sqlx::query_as!(
+        NistVideo,
+        r#"
+            SELECT
+                nv.video_id, nv.video_title, nv.network,
+                nv.broadcast_date, nv.duration_min, nv.subject,
+                nv.notes, nv.is_missing, nv.additional_notes
+            FROM nist_videos nv
+            JOIN master_videos_nist_videos mvnv ON nv.video_id = mvnv.nist_video_id
+            WHERE mvnv.master_video_id = $1
+            ORDER BY nv.video_id
+        "#,
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
     Ok(master)
 }
 
@@ -346,13 +388,28 @@ pub async fn get_videos() -> Result<Vec<Video>> {
 
     Ok(videos)
 }
+/// Gets the links of every video, which is cheaper than getting the videos when only the links
+/// are needed.
+pub async fn get_video_links() -> Result<Vec<String>> {
+    let pool = establish_connection().await?;
+    let links = sqlx::query_scalar!("SELECT link FROM videos ORDER BY id")
+        .fetch_all(&pool)
+        .await?;
+    Ok(links)
+}
+
 pub async fn get_video(id: i32, pool: Option<Pool<Postgres>>) -> Result<Video> {
     let pool = if let Some(p) = pool {
         p
     } else {
         establish_connection().await?
     };
+    let mut conn = pool.acquire().await?;
+    get_video_tx(&mut conn, id).await
+}
 
+/// Gets a video using a connection, which can be one in the middle of a transaction.
+pub async fn get_video_tx(conn: &mut PgConnection, id: i32) -> Result<Video> {
     let row = sqlx::query!(
         r#"
             SELECT id, channel_username, description, duration, is_primary, link, master_id, title
@@ -361,10 +418,10 @@ pub async fn get_video(id: i32, pool: Option<Pool<Postgres>>) -> Result<Video> {
         "#,
         id
     )
-    .fetch_one(&pool)
+    .fetch_one(&mut *conn)
     .await?;
 
-    let master = get_master_video(row.id, Some(pool.clone())).await?;
+    let master = get_master_video_tx(conn, row.id).await?;
     let video = Video {
         channel_username: row.channel_username,
         description: row.description,
@@ -395,6 +452,117 @@ pub async fn get_nist_videos() -> Result<Vec<NistVideo>> {
     Ok(videos)
 }
 
+/// Gets a NIST video using a connection, which can be one in the middle of a transaction.
+pub async fn get_nist_video_tx(conn: &mut PgConnection, video_id: i32) -> Result<NistVideo> {
+    sqlx::query_as!(
+        NistVideo,
+        r#"
+            SELECT
+                video_id, video_title, network,
+                broadcast_date, duration_min, subject,
+                notes, is_missing, additional_notes
+            FROM nist_videos
+            WHERE video_id = $1
+        "#,
+        video_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(Error::NistVideoNotFound(video_id))
+}
+
+/// Gets the NIST videos that no master video has been made from.
+pub async fn get_nist_videos_without_master() -> Result<Vec<NistVideo>> {
+    let pool = establish_connection().await?;
+    let videos = sqlx::query_as!(
+        NistVideo,
+        r#"
+            SELECT
+                video_id, video_title, network,
+                broadcast_date, duration_min, subject,
+                notes, is_missing, additional_notes
+            FROM nist_videos nv
+            WHERE NOT EXISTS (
+                SELECT 1 FROM master_videos_nist_videos mvnv
+                WHERE mvnv.nist_video_id = nv.video_id
+            )
+            ORDER BY video_id
+        "#
+    )
+    .fetch_all(&pool)
+    .await?;
+    Ok(videos)
+}
+
+/// Gets the number of master videos made from each NIST video, along with the number of online
+/// videos of those masters.
+pub async fn get_nist_video_link_counts() -> Result<Vec<(i32, i64, i64)>> {
+    let pool = establish_connection().await?;
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            nv.video_id,
+            COUNT(DISTINCT mvnv.master_video_id) AS "masters!",
+            COUNT(DISTINCT v.id) AS "online_videos!"
+        FROM nist_videos nv
+        LEFT JOIN master_videos_nist_videos mvnv ON mvnv.nist_video_id = nv.video_id
+        LEFT JOIN videos v ON v.master_id = mvnv.master_video_id
+        GROUP BY nv.video_id
+        ORDER BY nv.video_id
+        "#
+    )
+    .fetch_all(&pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|r| (r.video_id, r.masters, r.online_videos))
+        .collect())
+}
+
+/// Gets the distinct network names in NIST's videos table, with the number of videos for each.
+pub async fn get_nist_networks() -> Result<Vec<(String, i64)>> {
+    let pool = establish_connection().await?;
+    let rows = sqlx::query!(
+        r#"
+        SELECT TRIM(network) AS "network!", COUNT(*) AS "count!"
+        FROM nist_videos
+        WHERE network IS NOT NULL AND TRIM(network) <> ''
+        GROUP BY TRIM(network)
+        ORDER BY TRIM(network)
+        "#
+    )
+    .fetch_all(&pool)
+    .await?;
+    Ok(rows.into_iter().map(|r| (r.network, r.count)).collect())
+}
+
+pub async fn get_nist_network_mappings() -> Result<Vec<NistNetworkMapping>> {
+    let pool = establish_connection().await?;
+    let news_networks = get_news_networks(Some(pool.clone())).await?;
+    let news_affiliates = get_news_affiliates(Some(pool.clone())).await?;
+    let rows = sqlx::query!(
+        r#"
+        SELECT nist_network, news_network_id, news_affiliate_id
+        FROM nist_network_mappings
+        ORDER BY nist_network
+        "#
+    )
+    .fetch_all(&pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| NistNetworkMapping {
+            nist_network: row.nist_network,
+            news_affiliate: row
+                .news_affiliate_id
+                .and_then(|id| news_affiliates.iter().find(|a| a.id == id).cloned()),
+            news_network: row
+                .news_network_id
+                .and_then(|id| news_networks.iter().find(|n| n.id == id).cloned()),
+        })
+        .collect())
+}
+
 pub async fn get_nist_tapes() -> Result<Vec<NistTape>> {
     let videos = get_nist_videos().await?;
 
@@ -413,8 +581,11 @@ pub async fn get_nist_tapes() -> Result<Vec<NistTape>> {
                 batch,
                 clips,
                 timecode,
-                document_database_number
+                d.value AS "document_database_number?"
             FROM nist_tapes
+            LEFT JOIN current_nist_identifiers d
+                ON d.entity = 'nist-tape' AND d.entity_id = tape_id
+                AND d.kind = 'document_database_number'
             ORDER BY tape_id
         "#
     )
@@ -465,6 +636,67 @@ pub async fn get_nist_tapes() -> Result<Vec<NistTape>> {
     Ok(tapes)
 }
 
+/// Gets a NIST tape using a connection, which can be one in the middle of a transaction.
+pub async fn get_nist_tape_tx(conn: &mut PgConnection, tape_id: i32) -> Result<NistTape> {
+    let row = sqlx::query!(
+        r#"
+            SELECT
+                tape_id,
+                video_id,
+                tape_name,
+                tape_source,
+                copy,
+                derived_from,
+                format,
+                duration_min,
+                batch,
+                clips,
+                timecode,
+                d.value AS "document_database_number?"
+            FROM nist_tapes
+            LEFT JOIN current_nist_identifiers d
+                ON d.entity = 'nist-tape' AND d.entity_id = tape_id
+                AND d.kind = 'document_database_number'
+            WHERE tape_id = $1
+        "#,
+        tape_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(Error::NistTapeNotFound(tape_id))?;
+    let video = get_nist_video_tx(conn, row.video_id).await?;
+    let release_files = sqlx::query!(
+        r#"
+            SELECT rf.path, rf.size
+            FROM release_files rf
+            JOIN nist_tapes_release_files ntrf ON rf.id = ntrf.release_file_id
+            WHERE ntrf.nist_tape_id = $1
+            ORDER BY rf.path;
+        "#,
+        tape_id
+    )
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|row| (PathBuf::from(row.path), row.size as u64))
+    .collect();
+    Ok(NistTape {
+        tape_id: row.tape_id,
+        tape_name: row.tape_name,
+        tape_source: row.tape_source,
+        copy: row.copy,
+        derived_from: row.derived_from,
+        format: row.format,
+        duration_min: row.duration_min,
+        batch: row.batch,
+        clips: row.clips,
+        timecode: row.timecode,
+        release_files,
+        video,
+        document_database_number: row.document_database_number,
+    })
+}
+
 pub async fn get_nist_tapes_grouped_by_video() -> Result<BTreeMap<NistVideo, Vec<NistTape>>> {
     let videos = get_nist_videos().await?;
     let pool = establish_connection().await?;
@@ -483,8 +715,11 @@ pub async fn get_nist_tapes_grouped_by_video() -> Result<BTreeMap<NistVideo, Vec
                 array_agg(batch ORDER BY tape_id) AS batches,
                 array_agg(clips ORDER BY tape_id) AS clips,
                 array_agg(timecode ORDER BY tape_id) AS timecodes,
-                array_agg(document_database_number ORDER BY tape_id) AS "document_database_numbers: Vec<Option<String>>"
+                array_agg(d.value ORDER BY tape_id) AS "document_database_numbers: Vec<Option<String>>"
             FROM nist_tapes
+            LEFT JOIN current_nist_identifiers d
+                ON d.entity = 'nist-tape' AND d.entity_id = tape_id
+                AND d.kind = 'document_database_number'
             GROUP BY video_id
         "#
     )
@@ -578,11 +813,16 @@ pub async fn get_news_networks(pool: Option<Pool<Postgres>>) -> Result<Vec<NewsN
     } else {
         establish_connection().await?
     };
+    let mut conn = pool.acquire().await?;
+    get_news_networks_tx(&mut conn).await
+}
+
+async fn get_news_networks_tx(conn: &mut PgConnection) -> Result<Vec<NewsNetwork>> {
     let news_networks = sqlx::query_as!(
         NewsNetwork,
         "SELECT id, name, description FROM news_networks"
     )
-    .fetch_all(&pool)
+    .fetch_all(&mut *conn)
     .await?;
     Ok(news_networks)
 }
@@ -593,12 +833,18 @@ pub async fn get_news_network(id: i32, pool: Option<Pool<Postgres>>) -> Result<N
     } else {
         establish_connection().await?
     };
+    let mut conn = pool.acquire().await?;
+    get_news_network_tx(&mut conn, id).await
+}
+
+/// Gets a network using a connection, which can be one in the middle of a transaction.
+pub async fn get_news_network_tx(conn: &mut PgConnection, id: i32) -> Result<NewsNetwork> {
     let news_network = sqlx::query_as!(
         NewsNetwork,
         "SELECT id, name, description FROM news_networks WHERE id = $1",
         id
     )
-    .fetch_one(&pool)
+    .fetch_one(&mut *conn)
     .await?;
     Ok(news_network)
 }
@@ -609,12 +855,16 @@ pub async fn get_news_affiliates(pool: Option<Pool<Postgres>>) -> Result<Vec<New
     } else {
         establish_connection().await?
     };
+    let mut conn = pool.acquire().await?;
+    get_news_affiliates_tx(&mut conn).await
+}
 
-    let news_networks = get_news_networks(Some(pool.clone())).await?;
+async fn get_news_affiliates_tx(conn: &mut PgConnection) -> Result<Vec<NewsAffiliate>> {
+    let news_networks = get_news_networks_tx(conn).await?;
     let mut news_affiliates = Vec::new();
     let rows =
         sqlx::query!("SELECT id, name, description, region, news_network_id FROM news_affiliates")
-            .fetch_all(&pool)
+            .fetch_all(&mut *conn)
             .await?;
     for row in rows {
         let network = news_networks
@@ -638,8 +888,13 @@ pub async fn get_news_affiliate(id: i32, pool: Option<Pool<Postgres>>) -> Result
     } else {
         establish_connection().await?
     };
+    let mut conn = pool.acquire().await?;
+    get_news_affiliate_tx(&mut conn, id).await
+}
 
-    let news_networks = get_news_networks(Some(pool.clone())).await?;
+/// Gets a affiliate using a connection, which can be one in the middle of a transaction.
+pub async fn get_news_affiliate_tx(conn: &mut PgConnection, id: i32) -> Result<NewsAffiliate> {
+    let news_networks = get_news_networks_tx(conn).await?;
     let row = sqlx::query!(
         r#"
             SELECT id, name, description, region, news_network_id FROM news_affiliates
@@ -647,7 +902,7 @@ pub async fn get_news_affiliate(id: i32, pool: Option<Pool<Postgres>>) -> Result
         "#,
         id
     )
-    .fetch_one(&pool)
+    .fetch_one(&mut *conn)
     .await?;
     let network = news_networks
         .iter()
@@ -669,7 +924,12 @@ pub async fn get_news_broadcast(id: i32, pool: Option<Pool<Postgres>>) -> Result
     } else {
         establish_connection().await?
     };
+    let mut conn = pool.acquire().await?;
+    get_news_broadcast_tx(&mut conn, id).await
+}
 
+/// Gets a broadcast using a connection, which can be one in the middle of a transaction.
+pub async fn get_news_broadcast_tx(conn: &mut PgConnection, id: i32) -> Result<NewsBroadcast> {
     let row = sqlx::query!(
         r#"
             SELECT id, date, description, news_network_id, news_affiliate_id FROM news_broadcasts
@@ -677,17 +937,17 @@ pub async fn get_news_broadcast(id: i32, pool: Option<Pool<Postgres>>) -> Result
         "#,
         id
     )
-    .fetch_one(&pool)
+    .fetch_one(&mut *conn)
     .await?;
 
     let network = if let Some(network_id) = row.news_network_id {
-        Some(get_news_network(network_id, Some(pool.clone())).await?)
+        Some(get_news_network_tx(conn, network_id).await?)
     } else {
         None
     };
 
     let affiliate = if let Some(affiliate_id) = row.news_affiliate_id {
-        Some(get_news_affiliate(affiliate_id, Some(pool.clone())).await?)
+        Some(get_news_affiliate_tx(conn, affiliate_id).await?)
     } else {
         None
     };
@@ -781,149 +1041,785 @@ pub async fn get_people() -> Result<Vec<Person>> {
     Ok(people)
 }
 
+pub async fn get_event_types() -> Result<Vec<EventType>> {
+    let pool = establish_connection().await?;
+    let event_types = sqlx::query_as!(
+        EventType,
+        "SELECT slug, label, description, canonical_time FROM event_types ORDER BY slug"
+    )
+    .fetch_all(&pool)
+    .await?;
+    Ok(event_types)
+}
+
+/// Full-text search over master videos, event timestamps, NIST videos and tapes, news broadcasts
+/// and people.
+///
+/// The query uses web search syntax, so quoted phrases, `or` and `-` for exclusion are supported.
+/// Results are ordered by rank, highest first.
+pub async fn search(query: &str, limit: i64) -> Result<Vec<SearchResult>> {
+    let pool = establish_connection().await?;
+    let rows = sqlx::query!(
+        r#"
+        WITH q AS (SELECT websearch_to_tsquery('english', $1) AS query)
+        SELECT kind AS "kind!", id AS "id!", title AS "title!", snippet AS "snippet!",
+               rank AS "rank!"
+        FROM (
+            SELECT 'master' AS kind, mv.id, mv.title,
+                   ts_headline('english',
+                       mv.description || ' ' || coalesce(mv.nist_notes, ''), q.query) AS snippet,
+                   ts_rank(
+                       master_video_search_vector(mv.title, mv.description, mv.nist_notes),
+                       q.query) AS rank
+            FROM master_videos mv, q
+            WHERE master_video_search_vector(mv.title, mv.description, mv.nist_notes) @@ q.query
+            UNION ALL
+            SELECT 'timestamp', mv.id,
+                   mv.title || ' [' || to_char(et.timestamp, 'HH24:MI:SS') || ']',
+                   ts_headline('english', et.description, q.query),
+                   ts_rank(event_timestamp_search_vector(et.description), q.query)
+            FROM event_timestamps et
+            JOIN master_videos mv ON mv.id = et.master_video_id, q
+            WHERE event_timestamp_search_vector(et.description) @@ q.query
+            UNION ALL
+            SELECT 'nist-video', nv.video_id, nv.video_title,
+                   ts_headline('english',
+                       nv.video_title || ' ' || coalesce(nv.notes, '') || ' ' ||
+                       coalesce(nv.additional_notes, ''),
+                       q.query),
+                   ts_rank(
+                       nist_video_search_vector(nv.video_title, nv.notes, nv.additional_notes),
+                       q.query)
+            FROM nist_videos nv, q
+            WHERE nist_video_search_vector(nv.video_title, nv.notes, nv.additional_notes)
+                  @@ q.query
+            UNION ALL
+            SELECT 'nist-tape', nt.tape_id, nt.tape_name,
+                   ts_headline('english', nt.tape_name || ' ' || nt.tape_source, q.query),
+                   ts_rank(nist_tape_search_vector(nt.tape_name, nt.tape_source), q.query)
+            FROM nist_tapes nt, q
+            WHERE nist_tape_search_vector(nt.tape_name, nt.tape_source) @@ q.query
+            UNION ALL
+            SELECT 'broadcast', nb.id,
+                   coalesce(nn.name, na.name) || ' (' || to_char(nb.date, 'YYYY-MM-DD') || ')',
+                   ts_headline('english', nb.description, q.query),
+                   ts_rank(news_broadcast_search_vector(nb.description), q.query)
+            FROM news_broadcasts nb
+            LEFT JOIN news_networks nn ON nn.id = nb.news_network_id
+            LEFT JOIN news_affiliates na ON na.id = nb.news_affiliate_id, q
+            WHERE news_broadcast_search_vector(nb.description) @@ q.query
+            UNION ALL
+            SELECT 'person', p.id, p.name,
+                   ts_headline('english',
+                       coalesce(p.historical_title, '') || ' ' || coalesce(p.description, ''),
+                       q.query),
+                   ts_rank(
+                       person_search_vector(p.name, p.historical_title, p.description), q.query)
+            FROM people p, q
+            WHERE person_search_vector(p.name, p.historical_title, p.description) @@ q.query
+        ) results
+        ORDER BY rank DESC, kind, id
+        LIMIT $2
+        "#,
+        query,
+        limit
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(SearchResult {
+                id: row.id,
+                rank: row.rank,
+                result_type: SearchResultType::try_from(row.kind.as_str())?,
+                snippet: row.snippet,
+                title: row.title,
+            })
+        })
+        .collect()
+}
+
+/// Get the changes saved to a record, oldest first.
+pub async fn get_audit_entries(entity: &str, entity_id: i32) -> Result<Vec<AuditEntry>> {
+    let pool = establish_connection().await?;
+    let entries = sqlx::query_as!(
+        AuditEntry,
+        r#"
+            SELECT id, entity, entity_id, before::text AS before, after::text AS "after!",
+                   changed_by, changed_at
+            FROM audit_log
+            WHERE entity = $1 AND entity_id = $2
+            ORDER BY id
+        "#,
+        entity,
+        entity_id
+    )
+    .fetch_all(&pool)
+    .await?;
+    Ok(entries)
+}
+
+/// Get the version of a record, which is incremented every time the record is saved.
+///
+/// The entity is named in the same way as it is in the audit log.
+pub async fn get_version(entity: &str, id: i32) -> Result<i32> {
+    let pool = establish_connection().await?;
+    let mut conn = pool.acquire().await?;
+    get_version_tx(&mut conn, entity, id).await
+}
+
+async fn get_version_tx(conn: &mut PgConnection, entity: &str, id: i32) -> Result<i32> {
+    let version = match entity {
+        "affiliate" => {
+            sqlx::query_scalar!("SELECT version FROM news_affiliates WHERE id = $1", id)
+                .fetch_optional(&mut *conn)
+                .await?
+        }
+        "broadcast" => {
+            sqlx::query_scalar!("SELECT version FROM news_broadcasts WHERE id = $1", id)
+                .fetch_optional(&mut *conn)
+                .await?
+        }
+        "master" => {
+            sqlx::query_scalar!("SELECT version FROM master_videos WHERE id = $1", id)
+                .fetch_optional(&mut *conn)
+                .await?
+        }
+        "network" => {
+            sqlx::query_scalar!("SELECT version FROM news_networks WHERE id = $1", id)
+                .fetch_optional(&mut *conn)
+                .await?
+        }
+        "nist-tape" => {
+            sqlx::query_scalar!("SELECT version FROM nist_tapes WHERE tape_id = $1", id)
+                .fetch_optional(&mut *conn)
+                .await?
+        }
+        "nist-video" => {
+            sqlx::query_scalar!("SELECT version FROM nist_videos WHERE video_id = $1", id)
+                .fetch_optional(&mut *conn)
+                .await?
+        }
+        "video" => {
+            sqlx::query_scalar!("SELECT version FROM videos WHERE id = $1", id)
+                .fetch_optional(&mut *conn)
+                .await?
+        }
+        _ => return Err(Error::UnknownEntity(entity.to_string())),
+    };
+    version.ok_or_else(|| Error::RecordDeleted(entity.to_string(), id))
+}
+
+/// Checks the outcome of saving a record that was expected to be at a particular version.
+///
+/// Saving updates a record only when it is at the expected version, and gives whether the record
+/// was inserted, or nothing if it wasn't saved. A record inserted in place of the expected one was
+/// deleted in the meantime.
+async fn check_saved_version(
+    conn: &mut PgConnection,
+    entity: &str,
+    id: i32,
+    expected_version: Option<i32>,
+    inserted: Option<bool>,
+) -> Result<()> {
+    match (expected_version, inserted) {
+        (Some(_), Some(true)) => Err(Error::RecordDeleted(entity.to_string(), id)),
+        (_, Some(_)) => Ok(()),
+        (_, None) => {
+            // The version is read again to tell a record that was changed from one that's gone.
+            get_version_tx(conn, entity, id).await?;
+            Err(Error::VersionConflict(entity.to_string(), id))
+        }
+    }
+}
+
 /// ***********************
 /// Insert-based queries
 /// ***********************
-pub async fn import_nist_videos_table_from_csv(csv_path: &Path) -> Result<()> {
-    let pool = establish_connection().await?;
-    let mut tx = pool.begin().await?;
+/// How a row from NIST's database compares with the one already in our database.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RowChange {
+    Changed,
+    Inserted,
+    Unchanged,
+}
+
+/// The number of rows from one of NIST's tables that were inserted, changed or left unchanged.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UpsertCounts {
+    pub changed: usize,
+    pub inserted: usize,
+    pub unchanged: usize,
+}
+
+impl UpsertCounts {
+    fn add(&mut self, change: RowChange) {
+        match change {
+            RowChange::Changed => self.changed += 1,
+            RowChange::Inserted => self.inserted += 1,
+            RowChange::Unchanged => self.unchanged += 1,
+        }
+    }
+}
+
+impl std::fmt::Display for UpsertCounts {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} inserted, {} changed, {} unchanged",
+            self.inserted, self.changed, self.unchanged
+        )
+    }
+}
 
+pub fn read_nist_videos_from_csv(csv_path: &Path) -> Result<Vec<NistVideo>> {
+    let mut videos = Vec::new();
     let mut rdr = ReaderBuilder::new().has_headers(true).from_path(csv_path)?;
     for result in rdr.deserialize() {
         let record: Vec<String> = result?;
         let video = NistVideo::try_from(record)
             .map_err(|e| Error::NistVideoConversionError(e.to_string()))?;
-        sqlx::query_as!(
-            NistVideo,
-            r#"INSERT INTO nist_videos (
-                video_id, 
-                video_title, 
-                network, 
-                broadcast_date, 
-                duration_min, 
-                subject, 
-                notes
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
-            video.video_id,
-            video.video_title,
-            video.network,
-            video.broadcast_date,
-            video.duration_min,
-            video.subject,
-            video.notes,
-        )
-        .execute(&mut *tx)
-        .await?;
+        videos.push(video);
     }
-
-    tx.commit().await?;
-
-    Ok(())
+    Ok(videos)
 }
 
-pub async fn import_nist_tapes_table_from_csv(csv_path: &Path) -> Result<()> {
-    let pool = establish_connection().await?;
-    let mut tx = pool.begin().await?;
-
+pub fn read_nist_tapes_from_csv(csv_path: &Path) -> Result<Vec<NistTape>> {
+    let mut tapes = Vec::new();
     let mut rdr = ReaderBuilder::new().has_headers(true).from_path(csv_path)?;
     for result in rdr.deserialize() {
         let record: Vec<String> = result?;
         let tape = NistTape::try_from(record)
             .map_err(|e| Error::NistTapeConversionError(e.to_string()))?;
-        sqlx::query_as!(
-            NistTape,
-            r#"INSERT INTO nist_tapes (
-                tape_id, 
-                video_id, 
-                tape_name, 
-                tape_source, 
-                copy, 
-                derived_from, 
-                format, 
-                duration_min, 
-                batch, 
-                clips, 
-                timecode
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)"#,
-            tape.tape_id,
-            tape.video.video_id,
-            tape.tape_name,
-            tape.tape_source,
-            tape.copy,
-            tape.derived_from,
-            tape.format,
-            tape.duration_min,
-            tape.batch,
-            tape.clips,
-            tape.timecode,
-        )
-        .execute(&mut *tx)
-        .await?;
+        tapes.push(tape);
+    }
+    Ok(tapes)
+}
+
+/// Imports a CSV export of the Videos table.
+///
+/// Videos that are already in the database are updated, keeping our own notes on them.
+pub async fn import_nist_videos_table_from_csv(csv_path: &Path) -> Result<UpsertCounts> {
+    let videos = read_nist_videos_from_csv(csv_path)?;
+    let pool = establish_connection().await?;
+    let mut tx = pool.begin().await?;
+
+    let mut counts = UpsertCounts::default();
+    for video in videos.iter() {
+        counts.add(upsert_nist_video(&mut tx, video).await?);
     }
 
     tx.commit().await?;
 
-    Ok(())
+    Ok(counts)
 }
 
-pub async fn import_document_database_numbers() -> Result<()> {
+/// Imports a CSV export of the Tapes table.
+///
+/// Tapes that are already in the database are updated, keeping their document database numbers
+/// and release files.
+pub async fn import_nist_tapes_table_from_csv(csv_path: &Path) -> Result<UpsertCounts> {
+    let tapes = read_nist_tapes_from_csv(csv_path)?;
     let pool = establish_connection().await?;
     let mut tx = pool.begin().await?;
 
-    for (id, number) in crate::static_data::DOCUMENT_DATABASE_NUMBERS.iter() {
-        println!("Assigning {} to tape {}", number, id);
-        sqlx::query!(
-            "UPDATE nist_tapes SET document_database_number = $1 WHERE tape_id = $2",
-            number,
-            id
-        )
-        .execute(&mut *tx)
-        .await?;
+    let mut counts = UpsertCounts::default();
+    for tape in tapes.iter() {
+        counts.add(upsert_nist_tape(&mut tx, tape).await?);
     }
 
     tx.commit().await?;
 
-    Ok(())
+    Ok(counts)
 }
 
-pub async fn save_news_network(network: NewsNetwork) -> Result<NewsNetwork> {
-    let pool = establish_connection().await?;
+/// A row from one of NIST's tables that could not be imported.
+#[derive(Clone, Debug)]
+pub struct FailedRow {
+    pub error: String,
+    /// The position of the row in its table, starting from 1.
+    pub row: usize,
+    pub table: String,
+}
 
-    let network_id = if network.id == 0 {
-        sqlx::query!(
-            r#"INSERT INTO news_networks (name, description) VALUES ($1, $2) RETURNING id"#,
-            network.name,
-            network.description,
-        )
-        .fetch_one(&pool)
-        .await?
-        .id
-    } else {
-        sqlx::query!(
-            r#"INSERT INTO news_networks (id, name, description)
-               VALUES ($1, $2, $3)
-               ON CONFLICT (id) DO UPDATE SET
-                   name = EXCLUDED.name,
-                   description = EXCLUDED.description
-               RETURNING id"#,
-            network.id,
-            network.name,
-            network.description,
-        )
-        .fetch_one(&pool)
-        .await?
-        .id
-    };
+/// A row read from one of NIST's tables, with its position so it can be reported if it fails.
+#[derive(Clone, Debug)]
+pub struct NistRow<T> {
+    /// The position of the row in its table, starting from 1.
+    pub row: usize,
+    pub table: String,
+    pub value: T,
+}
 
-    let mut updated_network = network.clone();
-    updated_network.id = network_id;
-    Ok(updated_network)
+impl<T> NistRow<T> {
+    fn failed(&self, error: String) -> FailedRow {
+        FailedRow {
+            error,
+            row: self.row,
+            table: self.table.clone(),
+        }
+    }
 }
 
-pub async fn save_news_affiliate(affiliate: NewsAffiliate) -> Result<NewsAffiliate> {
-    let pool = establish_connection().await?;
+/// The tables read from NIST's Access database.
+#[derive(Clone, Default)]
+pub struct NistTables {
+    pub failed: Vec<FailedRow>,
+    pub other_tables: Vec<Table>,
+    pub tapes: Vec<NistRow<NistTape>>,
+    pub videos: Vec<NistRow<NistVideo>>,
+}
 
-    let affiliate_id = if affiliate.id == 0 {
+/// The outcome of importing NIST's Access database.
+#[derive(Clone, Debug, Default)]
+pub struct NistImportReport {
+    /// The rows the database rejected, which were skipped.
+    pub failed: Vec<FailedRow>,
+    /// The name of each of the other tables with the number of rows it has.
+    pub other_tables: Vec<(String, usize)>,
+    pub tapes: UpsertCounts,
+    pub videos: UpsertCounts,
+}
+
+/// Reads the tables from NIST's Access database.
+///
+/// The Videos and Tapes tables are converted to their models. A row that can't be converted, or a
+/// tape whose video is in neither the Videos table nor our database, is reported as failed rather
+/// than failing the whole import.
+pub async fn read_nist_database(source: &impl TableSource) -> Result<NistTables> {
+    let video_ids: Vec<i32> = get_nist_videos()
+        .await?
+        .into_iter()
+        .map(|v| v.video_id)
+        .collect();
+    read_nist_tables(source, &video_ids)
+}
+
+/// Reads the tables from NIST's Access database, given the IDs of the videos already in ours.
+///
+/// A row with the same ID as an earlier row in its table is also reported as failed.
+fn read_nist_tables(source: &impl TableSource, existing_video_ids: &[i32]) -> Result<NistTables> {
+    let mut tables = NistTables::default();
+    let mut tapes = Vec::new();
+    for name in source.table_names()? {
+        let table = source.read_table(&name)?;
+        if name.eq_ignore_ascii_case("videos") {
+            let mut video_ids = HashSet::new();
+            for (i, values) in table.rows.into_iter().enumerate() {
+                let row = NistRow {
+                    row: i + 1,
+                    table: name.clone(),
+                    value: (),
+                };
+                match NistVideo::try_from(values) {
+                    Ok(video) if !video_ids.insert(video.video_id) => tables
+                        .failed
+                        .push(row.failed(format!("duplicate video {}", video.video_id))),
+                    Ok(video) => tables.videos.push(NistRow {
+                        row: row.row,
+                        table: row.table,
+                        value: video,
+                    }),
+                    Err(e) => tables.failed.push(row.failed(e.to_string())),
+                }
+            }
+        } else if name.eq_ignore_ascii_case("tapes") {
+            for (i, values) in table.rows.into_iter().enumerate() {
+                let row = NistRow {
+                    row: i + 1,
+                    table: name.clone(),
+                    value: (),
+                };
+                match NistTape::try_from(values) {
+                    Ok(tape) => tapes.push(NistRow {
+                        row: row.row,
+                        table: row.table,
+                        value: tape,
+                    }),
+                    Err(e) => tables.failed.push(row.failed(e.to_string())),
+                }
+            }
+        } else {
+            tables.other_tables.push(table);
+        }
+    }
+
+    let mut video_ids: HashSet<i32> = existing_video_ids.iter().copied().collect();
+    video_ids.extend(tables.videos.iter().map(|v| v.value.video_id));
+    let mut tape_ids = HashSet::new();
+    for tape in tapes.into_iter() {
+        if !video_ids.contains(&tape.value.video.video_id) {
+            let error = format!("video {} does not exist", tape.value.video.video_id);
+            tables.failed.push(tape.failed(error));
+        } else if !tape_ids.insert(tape.value.tape_id) {
+            let error = format!("duplicate tape {}", tape.value.tape_id);
+            tables.failed.push(tape.failed(error));
+        } else {
+            tables.tapes.push(tape);
+        }
+    }
+    tables
+        .failed
+        .sort_by(|a, b| (&a.table, a.row).cmp(&(&b.table, b.row)));
+    Ok(tables)
+}
+
+/// Imports the tables read from NIST's Access database.
+///
+/// Videos and tapes that are already in the database are updated, keeping our own columns. Each
+/// one is saved under its own savepoint, so a row the database rejects is reported as failed and
+/// the rest are still imported. The other tables are kept as they are, replacing what was imported
+/// from them before.
+pub async fn import_nist_database(tables: &NistTables) -> Result<NistImportReport> {
+    let mut report = NistImportReport::default();
+    let pool = establish_connection().await?;
+    let mut tx = pool.begin().await?;
+
+    for video in tables.videos.iter() {
+        let mut savepoint = (&mut *tx).begin().await?;
+        match upsert_nist_video(&mut savepoint, &video.value).await {
+            Ok(change) => {
+                savepoint.commit().await?;
+                report.videos.add(change);
+            }
+            Err(e) => {
+                savepoint.rollback().await?;
+                report.failed.push(video.failed(e.to_string()));
+            }
+        }
+    }
+    for tape in tables.tapes.iter() {
+        let mut savepoint = (&mut *tx).begin().await?;
+        match upsert_nist_tape(&mut savepoint, &tape.value).await {
+            Ok(change) => {
+                savepoint.commit().await?;
+                report.tapes.add(change);
+            }
+            Err(e) => {
+                savepoint.rollback().await?;
+                report.failed.push(tape.failed(e.to_string()));
+            }
+        }
+    }
+    for table in tables.other_tables.iter() {
+        sqlx::query!("DELETE FROM nist_tables WHERE name = $1", table.name)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!(
+            "INSERT INTO nist_tables (name, columns) VALUES ($1, $2)",
+            table.name,
+            &table.columns
+        )
+        .execute(&mut *tx)
+        .await?;
+        for (i, row) in table.rows.iter().enumerate() {
+            sqlx::query!(
+                r#"INSERT INTO nist_table_rows (table_name, row_number, row_values)
+                VALUES ($1, $2, $3)"#,
+                table.name,
+                i as i32 + 1,
+                row
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        report
+            .other_tables
+            .push((table.name.clone(), table.rows.len()));
+    }
+
+    tx.commit().await?;
+
+    Ok(report)
+}
+
+/// Gets the number of rows previously imported from each of the other tables in NIST's database.
+pub async fn get_nist_table_row_counts() -> Result<Vec<(String, i64)>> {
+    let pool = establish_connection().await?;
+    let rows = sqlx::query!(
+        r#"
+        SELECT t.name, COUNT(r.row_number) AS "count!"
+        FROM nist_tables t
+        LEFT JOIN nist_table_rows r ON r.table_name = t.name
+        GROUP BY t.name
+        ORDER BY t.name
+        "#
+    )
+    .fetch_all(&pool)
+    .await?;
+    Ok(rows.into_iter().map(|r| (r.name, r.count)).collect())
+}
+
+/// Inserts or updates a video from NIST's database.
+///
+/// Only the columns from NIST's database are updated, and only when one of them has changed. The
+/// missing flag and additional notes are ours, so they are left as they are.
+async fn upsert_nist_video(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    video: &NistVideo,
+) -> Result<RowChange> {
+    let inserted = sqlx::query_scalar!(
+        r#"INSERT INTO nist_videos (
+            video_id, 
+            video_title, 
+            network, 
+            broadcast_date, 
+            duration_min, 
+            subject, 
+            notes
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (video_id) DO UPDATE SET
+            video_title = EXCLUDED.video_title,
+            network = EXCLUDED.network,
+            broadcast_date = EXCLUDED.broadcast_date,
+            duration_min = EXCLUDED.duration_min,
+            subject = EXCLUDED.subject,
+            notes = EXCLUDED.notes,
+            version = nist_videos.version + 1
+        WHERE (
+            nist_videos.video_title, nist_videos.network, nist_videos.broadcast_date,
+            nist_videos.duration_min, nist_videos.subject, nist_videos.notes
+        ) IS DISTINCT FROM (
+            EXCLUDED.video_title, EXCLUDED.network, EXCLUDED.broadcast_date,
+            EXCLUDED.duration_min, EXCLUDED.subject, EXCLUDED.notes
+        )
+        RETURNING (xmax = 0) AS "inserted!""#,
+        video.video_id,
+        video.video_title,
+        video.network,
+        video.broadcast_date,
+        video.duration_min,
+        video.subject,
+        video.notes,
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(match inserted {
+        Some(true) => RowChange::Inserted,
+        Some(false) => RowChange::Changed,
+        None => RowChange::Unchanged,
+    })
+}
+
+/// Inserts or updates a tape from NIST's database.
+///
+/// Only the columns from NIST's database are updated, and only when one of them has changed. The
+/// links to release files are ours, so they are left as they are.
+async fn upsert_nist_tape(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    tape: &NistTape,
+) -> Result<RowChange> {
+    let inserted = sqlx::query_scalar!(
+        r#"INSERT INTO nist_tapes (
+            tape_id, 
+            video_id, 
+            tape_name, 
+            tape_source, 
+            copy, 
+            derived_from, 
+            format, 
+            duration_min, 
+            batch, 
+            clips, 
+            timecode
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        ON CONFLICT (tape_id) DO UPDATE SET
+            video_id = EXCLUDED.video_id,
+            tape_name = EXCLUDED.tape_name,
+            tape_source = EXCLUDED.tape_source,
+            copy = EXCLUDED.copy,
+            derived_from = EXCLUDED.derived_from,
+            format = EXCLUDED.format,
+            duration_min = EXCLUDED.duration_min,
+            batch = EXCLUDED.batch,
+            clips = EXCLUDED.clips,
+            timecode = EXCLUDED.timecode,
+            version = nist_tapes.version + 1
+        WHERE (
+            nist_tapes.video_id, nist_tapes.tape_name, nist_tapes.tape_source, nist_tapes.copy,
+            nist_tapes.derived_from, nist_tapes.format, nist_tapes.duration_min, nist_tapes.batch,
+            nist_tapes.clips, nist_tapes.timecode
+        ) IS DISTINCT FROM (
+            EXCLUDED.video_id, EXCLUDED.tape_name, EXCLUDED.tape_source, EXCLUDED.copy,
+            EXCLUDED.derived_from, EXCLUDED.format, EXCLUDED.duration_min, EXCLUDED.batch,
+            EXCLUDED.clips, EXCLUDED.timecode
+        )
+        RETURNING (xmax = 0) AS "inserted!""#,
+        tape.tape_id,
+        tape.video.video_id,
+        tape.tape_name,
+        tape.tape_source,
+        tape.copy,
+        tape.derived_from,
+        tape.format,
+        tape.duration_min,
+        tape.batch,
+        tape.clips,
+        tape.timecode,
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(match inserted {
+        Some(true) => RowChange::Inserted,
+        Some(false) => RowChange::Changed,
+        None => RowChange::Unchanged,
+    })
+}
+
+/// Records identifiers from a FOIA response for NIST's tapes or videos.
+///
+/// The entity is named in the same way as it is in the audit log. An identifier that was already
+/// recorded from the same response is skipped, so a mapping can be imported again. Returns the
+/// number of identifiers that were added.
+pub async fn import_nist_identifiers(
+    entity: &str,
+    kind: &str,
+    foia_response: &str,
+    mappings: &[(i32, String)],
+) -> Result<usize> {
+    let pool = establish_connection().await?;
+    let mut tx = pool.begin().await?;
+
+    let mut added = 0;
+    for (id, value) in mappings.iter() {
+        let result = sqlx::query!(
+            r#"INSERT INTO nist_identifiers (entity, entity_id, kind, value, foia_response)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT DO NOTHING"#,
+            entity,
+            id,
+            kind,
+            value,
+            foia_response
+        )
+        .execute(&mut *tx)
+        .await?;
+        added += result.rows_affected() as usize;
+    }
+
+    tx.commit().await?;
+
+    Ok(added)
+}
+
+/// Gets every identifier recorded for a tape or video, with the oldest of each kind first.
+pub async fn get_nist_identifiers(entity: &str, entity_id: i32) -> Result<Vec<NistIdentifier>> {
+    let pool = establish_connection().await?;
+    let identifiers = sqlx::query_as!(
+        NistIdentifier,
+        r#"
+            SELECT id, entity, entity_id, kind, value, foia_response, imported_at
+            FROM nist_identifiers
+            WHERE entity = $1 AND entity_id = $2
+            ORDER BY kind, imported_at, id
+        "#,
+        entity,
+        entity_id
+    )
+    .fetch_all(&pool)
+    .await?;
+    Ok(identifiers)
+}
+
+/// Gets every identifier recorded for the NIST tapes and videos.
+pub async fn get_all_nist_identifiers() -> Result<Vec<NistIdentifier>> {
+    let pool = establish_connection().await?;
+    let identifiers = sqlx::query_as!(
+        NistIdentifier,
+        r#"
+            SELECT id, entity, entity_id, kind, value, foia_response, imported_at
+            FROM nist_identifiers
+            ORDER BY id
+        "#
+    )
+    .fetch_all(&pool)
+    .await?;
+    Ok(identifiers)
+}
+
+/// Saves an identifier with the ID it was given, replacing any identifier with that ID.
+pub async fn save_nist_identifier(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    identifier: &NistIdentifier,
+) -> Result<()> {
+    sqlx::query!(
+        r#"INSERT INTO nist_identifiers
+               (id, entity, entity_id, kind, value, foia_response, imported_at)
+           VALUES ($1, $2, $3, $4, $5, $6, $7)
+           ON CONFLICT (id) DO UPDATE SET
+               entity = EXCLUDED.entity,
+               entity_id = EXCLUDED.entity_id,
+               kind = EXCLUDED.kind,
+               value = EXCLUDED.value,
+               foia_response = EXCLUDED.foia_response,
+               imported_at = EXCLUDED.imported_at"#,
+        identifier.id,
+        identifier.entity,
+        identifier.entity_id,
+        identifier.kind,
+        identifier.value,
+        identifier.foia_response,
+        identifier.imported_at,
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+pub async fn save_news_network(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    network: NewsNetwork,
+    expected_version: Option<i32>,
+) -> Result<NewsNetwork> {
+    let network_id = if network.id == 0 {
+        sqlx::query!(
+            r#"INSERT INTO news_networks (name, description) VALUES ($1, $2) RETURNING id"#,
+            network.name,
+            network.description,
+        )
+        .fetch_one(&mut **tx)
+        .await?
+        .id
+    } else {
+        let row = sqlx::query!(
+            r#"INSERT INTO news_networks (id, name, description)
+               VALUES ($1, $2, $3)
+               ON CONFLICT (id) DO UPDATE SET
+                   name = EXCLUDED.name,
+                   description = EXCLUDED.description,
+                   version = news_networks.version + 1
+               WHERE $4::int IS NULL OR news_networks.version = $4
+               RETURNING id, (xmax = 0) AS "inserted!""#,
+            network.id,
+            network.name,
+            network.description,
+            expected_version,
+        )
+        .fetch_optional(&mut **tx)
+        .await?;
+        check_saved_version(
+            tx,
+            "network",
+            network.id,
+            expected_version,
+            row.map(|r| r.inserted),
+        )
+        .await?;
+        network.id
+    };
+
+    let mut updated_network = network.clone();
+    updated_network.id = network_id;
+    Ok(updated_network)
+}
+
+pub async fn save_news_affiliate(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    affiliate: NewsAffiliate,
+    expected_version: Option<i32>,
+) -> Result<NewsAffiliate> {
+    let affiliate_id = if affiliate.id == 0 {
         sqlx::query!(
             r#"
                 INSERT INTO news_affiliates (name, description, region, news_network_id)
@@ -935,28 +1831,39 @@ pub async fn save_news_affiliate(affiliate: NewsAffiliate) -> Result<NewsAffilia
             affiliate.region,
             affiliate.network.id,
         )
-        .fetch_one(&pool)
+        .fetch_one(&mut **tx)
         .await?
         .id
     } else {
-        sqlx::query!(
+        let row = sqlx::query!(
             r#"INSERT INTO news_affiliates (id, name, description, region, news_network_id)
                VALUES ($1, $2, $3, $4, $5)
                ON CONFLICT (id) DO UPDATE SET
                    name = EXCLUDED.name,
                    description = EXCLUDED.description,
                    region = EXCLUDED.region,
-                   news_network_id = EXCLUDED.news_network_id
-               RETURNING id"#,
+                   news_network_id = EXCLUDED.news_network_id,
+                   version = news_affiliates.version + 1
+               WHERE $6::int IS NULL OR news_affiliates.version = $6
+               RETURNING id, (xmax = 0) AS "inserted!""#,
             affiliate.id,
             affiliate.name,
             affiliate.description,
             affiliate.region,
             affiliate.network.id,
+            expected_version,
         )
-        .fetch_one(&pool)
-        .await?
-        .id
+        .fetch_optional(&mut **tx)
+        .await?;
+        check_saved_version(
+            tx,
+            "affiliate",
+            affiliate.id,
+            expected_version,
+            row.map(|r| r.inserted),
+        )
+        .await?;
+        affiliate.id
     };
 
     let mut updated_affiliate = affiliate.clone();
@@ -964,12 +1871,15 @@ pub async fn save_news_affiliate(affiliate: NewsAffiliate) -> Result<NewsAffilia
     Ok(updated_affiliate)
 }
 
-pub async fn save_news_broadcast(broadcast: NewsBroadcast) -> Result<NewsBroadcast> {
+pub async fn save_news_broadcast(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    broadcast: NewsBroadcast,
+    expected_version: Option<i32>,
+) -> Result<NewsBroadcast> {
     if broadcast.news_network.is_some() && broadcast.news_affiliate.is_some() {
         return Err(Error::NewsBroadcastCannotHaveNetworkAndAffiliate);
     }
 
-    let pool = establish_connection().await?;
     let broadcast_id = if let Some(network) = &broadcast.news_network {
         if broadcast.id == 0 {
             sqlx::query!(
@@ -982,28 +1892,39 @@ pub async fn save_news_broadcast(broadcast: NewsBroadcast) -> Result<NewsBroadca
                 broadcast.description,
                 network.id
             )
-            .fetch_one(&pool)
+            .fetch_one(&mut **tx)
             .await?
             .id
         } else {
-            sqlx::query!(
+            let row = sqlx::query!(
                 r#"
                     INSERT INTO news_broadcasts (id, date, description, news_network_id)
                     VALUES ($1, $2, $3, $4)
                     ON CONFLICT (id) DO UPDATE SET
                        date = EXCLUDED.date,
                        description = EXCLUDED.description,
-                       news_network_id = EXCLUDED.news_network_id
-                    RETURNING id
+                       news_network_id = EXCLUDED.news_network_id,
+                       version = news_broadcasts.version + 1
+                    WHERE $5::int IS NULL OR news_broadcasts.version = $5
+                    RETURNING id, (xmax = 0) AS "inserted!"
                "#,
                 broadcast.id,
                 broadcast.date,
                 broadcast.description,
-                network.id
+                network.id,
+                expected_version,
             )
-            .fetch_one(&pool)
-            .await?
-            .id
+            .fetch_optional(&mut **tx)
+            .await?;
+            check_saved_version(
+                tx,
+                "broadcast",
+                broadcast.id,
+                expected_version,
+                row.map(|r| r.inserted),
+            )
+            .await?;
+            broadcast.id
         }
     } else if let Some(affiliate) = &broadcast.news_affiliate {
         if broadcast.id == 0 {
@@ -1017,28 +1938,39 @@ pub async fn save_news_broadcast(broadcast: NewsBroadcast) -> Result<NewsBroadca
                 broadcast.description,
                 affiliate.id
             )
-            .fetch_one(&pool)
+            .fetch_one(&mut **tx)
             .await?
             .id
         } else {
-            sqlx::query!(
+            let row = sqlx::query!(
                 r#"
                     INSERT INTO news_broadcasts (id, date, description, news_affiliate_id)
                     VALUES ($1, $2, $3, $4)
                     ON CONFLICT (id) DO UPDATE SET
                        date = EXCLUDED.date,
                        description = EXCLUDED.description,
-                       news_affiliate_id = EXCLUDED.news_affiliate_id
-                    RETURNING id
+                       news_affiliate_id = EXCLUDED.news_affiliate_id,
+                       version = news_broadcasts.version + 1
+                    WHERE $5::int IS NULL OR news_broadcasts.version = $5
+                    RETURNING id, (xmax = 0) AS "inserted!"
                "#,
                 broadcast.id,
                 broadcast.date,
                 broadcast.description,
-                affiliate.id
+                affiliate.id,
+                expected_version,
             )
-            .fetch_one(&pool)
-            .await?
-            .id
+            .fetch_optional(&mut **tx)
+            .await?;
+            check_saved_version(
+                tx,
+                "broadcast",
+                broadcast.id,
+                expected_version,
+                row.map(|r| r.inserted),
+            )
+            .await?;
+            broadcast.id
         }
     } else {
         return Err(Error::NewsBroadcastDoesNotHaveNetworkOrAffiliate);
@@ -1049,10 +1981,98 @@ pub async fn save_news_broadcast(broadcast: NewsBroadcast) -> Result<NewsBroadca
     Ok(updated_broadcast)
 }
 
-pub async fn save_master_video(video: MasterVideo) -> Result<MasterVideo> {
+/// Saves a person, including their description and historical title, which can't be set from the
+/// master video form.
+pub async fn save_person(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    person: Person,
+) -> Result<Person> {
+    let person_id = if person.id == 0 {
+        sqlx::query!(
+            r#"INSERT INTO people (name, historical_title, description, types)
+               VALUES ($1, $2, $3, $4)
+               RETURNING id"#,
+            person.name,
+            person.historical_title,
+            person.description,
+            person.types as _,
+        )
+        .fetch_one(&mut **tx)
+        .await?
+        .id
+    } else {
+        sqlx::query!(
+            r#"INSERT INTO people (id, name, historical_title, description, types)
+               VALUES ($1, $2, $3, $4, $5)
+               ON CONFLICT (id) DO UPDATE SET
+                   name = EXCLUDED.name,
+                   historical_title = EXCLUDED.historical_title,
+                   description = EXCLUDED.description,
+                   types = EXCLUDED.types
+               RETURNING id"#,
+            person.id,
+            person.name,
+            person.historical_title,
+            person.description,
+            person.types as _,
+        )
+        .fetch_one(&mut **tx)
+        .await?
+        .id
+    };
+
+    let mut updated_person = person.clone();
+    updated_person.id = person_id;
+    Ok(updated_person)
+}
+
+pub async fn save_event_type(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    event_type: EventType,
+) -> Result<EventType> {
+    sqlx::query!(
+        r#"INSERT INTO event_types (slug, label, description, canonical_time)
+           VALUES ($1, $2, $3, $4)
+           ON CONFLICT (slug) DO UPDATE SET
+               label = EXCLUDED.label,
+               description = EXCLUDED.description,
+               canonical_time = EXCLUDED.canonical_time"#,
+        event_type.slug,
+        event_type.label,
+        event_type.description,
+        event_type.canonical_time,
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(event_type)
+}
+
+/// Deletes an event type, which must not be used by any timestamps.
+pub async fn delete_event_type(slug: &str) -> Result<()> {
     let pool = establish_connection().await?;
-    let mut tx = pool.begin().await?;
+    let count = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM event_timestamps WHERE event_type = $1"#,
+        slug
+    )
+    .fetch_one(&pool)
+    .await?;
+    if count > 0 {
+        return Err(Error::EventTypeInUse(slug.to_string(), count));
+    }
+    let result = sqlx::query!("DELETE FROM event_types WHERE slug = $1", slug)
+        .execute(&pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(Error::EventTypeNotFound(slug.to_string()));
+    }
+    Ok(())
+}
 
+pub async fn save_master_video(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    video: MasterVideo,
+    expected_version: Option<i32>,
+) -> Result<MasterVideo> {
     // Unfortunately, you need to handle the special case where the ID is zero, which is for a new
     // record. Postgres allows the insertion of 0, despite the fact that the ID column is defined
     // as `SERIAL`.
@@ -1068,11 +2088,11 @@ pub async fn save_master_video(video: MasterVideo) -> Result<MasterVideo> {
             &video.links,
             video.nist_notes,
         )
-        .fetch_one(&mut *tx)
+        .fetch_one(&mut **tx)
         .await?
         .id
     } else {
-        sqlx::query!(
+        let row = sqlx::query!(
             r#"INSERT INTO master_videos (
                     id, categories, title, date, description, links, nist_notes)
                VALUES ($1, $2, $3, $4, $5, $6, $7)
@@ -1082,8 +2102,10 @@ pub async fn save_master_video(video: MasterVideo) -> Result<MasterVideo> {
                    date = EXCLUDED.date,
                    description = EXCLUDED.description,
                    links = EXCLUDED.links,
-                   nist_notes = EXCLUDED.nist_notes
-               RETURNING id"#,
+                   nist_notes = EXCLUDED.nist_notes,
+                   version = master_videos.version + 1
+               WHERE $8::int IS NULL OR master_videos.version = $8
+               RETURNING id, (xmax = 0) AS "inserted!""#,
             video.id,
             video.categories as _,
             video.title,
@@ -1091,41 +2113,72 @@ pub async fn save_master_video(video: MasterVideo) -> Result<MasterVideo> {
             video.description,
             &video.links,
             video.nist_notes,
+            expected_version,
         )
-        .fetch_one(&mut *tx)
-        .await?
-        .id
+        .fetch_optional(&mut **tx)
+        .await?;
+        check_saved_version(
+            tx,
+            "master",
+            video.id,
+            expected_version,
+            row.map(|r| r.inserted),
+        )
+        .await?;
+        video.id
     };
 
     let mut updated_video = video.clone();
     updated_video.id = video_id;
 
+    // Timestamps parsed from a form or record don't carry their IDs, so each one takes the ID of
+    // an existing timestamp at the same offset with the same description. Otherwise any edit to
+    // the video would replace all of its timestamps.
+    let mut existing = sqlx::query!(
+        r#"SELECT id, timestamp, description FROM event_timestamps WHERE master_video_id = $1"#,
+        video_id
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+    existing.retain(|row| !updated_video.timestamps.iter().any(|t| t.id == row.id));
+    for timestamp in updated_video.timestamps.iter_mut().filter(|t| t.id == 0) {
+        if let Some(position) = existing.iter().position(|row| {
+            row.timestamp == timestamp.timestamp && row.description == timestamp.description
+        }) {
+            timestamp.id = existing.remove(position).id;
+        }
+    }
+
     for timestamp in updated_video.timestamps.iter_mut() {
         let id = if timestamp.id == 0 {
             sqlx::query!(
                 r#"
                 INSERT INTO event_timestamps (
-                    description, timestamp, event_type, time_of_day, master_video_id
-                ) VALUES ($1, $2, $3, $4, $5)
+                    description, timestamp, end_timestamp, event_type, time_of_day,
+                    master_video_id
+                ) VALUES ($1, $2, $3, $4, $5, $6)
                 RETURNING id"#,
                 timestamp.description,
                 timestamp.timestamp,
-                timestamp.event_type as _,
+                timestamp.end_timestamp,
+                timestamp.event_type.slug,
                 timestamp.time_of_day,
                 video_id
             )
-            .fetch_one(&mut *tx)
+            .fetch_one(&mut **tx)
             .await?
             .id
         } else {
             sqlx::query!(
                 r#"
                 INSERT INTO event_timestamps (
-                    id, description, timestamp, event_type, time_of_day, master_video_id
-                ) VALUES ($1, $2, $3, $4, $5, $6)
+                    id, description, timestamp, end_timestamp, event_type, time_of_day,
+                    master_video_id
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7)
                 ON CONFLICT (id) DO UPDATE SET
                    description = EXCLUDED.description,
                    timestamp = EXCLUDED.timestamp,
+                   end_timestamp = EXCLUDED.end_timestamp,
                    event_type = EXCLUDED.event_type,
                    time_of_day = EXCLUDED.time_of_day,
                    master_video_id = EXCLUDED.master_video_id
@@ -1133,32 +2186,82 @@ pub async fn save_master_video(video: MasterVideo) -> Result<MasterVideo> {
                 timestamp.id,
                 timestamp.description,
                 timestamp.timestamp,
-                timestamp.event_type as _,
+                timestamp.end_timestamp,
+                timestamp.event_type.slug,
                 timestamp.time_of_day,
                 video_id
             )
-            .fetch_one(&mut *tx)
+            .fetch_one(&mut **tx)
             .await?
             .id
         };
         timestamp.id = id;
     }
 
+    // Any timestamps that are no longer part of the video are removed.
+    let timestamp_ids: Vec<i32> = updated_video.timestamps.iter().map(|t| t.id).collect();
+    sqlx::query!(
+        "DELETE FROM event_timestamps WHERE master_video_id = $1 AND NOT (id = ANY($2))",
+        video_id,
+        &timestamp_ids
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    // The links are replaced, so that anything removed from the video is no longer associated
+    // with it.
+    sqlx::query!(
+        "DELETE FROM master_videos_news_broadcasts WHERE master_video_id = $1",
+        video_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    sqlx::query!(
+        "DELETE FROM master_videos_people WHERE master_video_id = $1",
+        video_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    sqlx::query!(
+        "DELETE FROM master_videos_release_files WHERE master_video_id = $1",
+        video_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    sqlx::query!(
+        "DELETE FROM master_videos_nist_videos WHERE master_video_id = $1",
+        video_id
+    )
+    .execute(&mut **tx)
+    .await?;
+
     for broadcast in video.news_broadcasts.iter() {
         sqlx::query!(
             r#"INSERT INTO master_videos_news_broadcasts (master_video_id, news_broadcast_id)
                 VALUES ($1, $2)
                 ON CONFLICT DO NOTHING"#,
+            video_id,
             broadcast.id,
+        )
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    for nist_video in video.nist_videos.iter() {
+        sqlx::query!(
+            r#"INSERT INTO master_videos_nist_videos (master_video_id, nist_video_id)
+                VALUES ($1, $2)
+                ON CONFLICT DO NOTHING"#,
             video_id,
+            nist_video.video_id,
         )
-        .execute(&mut *tx)
+        .execute(&mut **tx)
         .await?;
     }
 
     for person in updated_video.people.iter_mut() {
         let row = sqlx::query!("SELECT id FROM people WHERE name = $1", person.name)
-            .fetch_optional(&mut *tx)
+            .fetch_optional(&mut **tx)
             .await?;
         let id = if let Some(row) = row {
             row.id
@@ -1168,7 +2271,7 @@ pub async fn save_master_video(video: MasterVideo) -> Result<MasterVideo> {
                 person.name,
                 person.types as _,
             )
-            .fetch_one(&mut *tx)
+            .fetch_one(&mut **tx)
             .await?
             .id
         };
@@ -1181,7 +2284,7 @@ pub async fn save_master_video(video: MasterVideo) -> Result<MasterVideo> {
             video_id,
             id
         )
-        .execute(&mut *tx)
+        .execute(&mut **tx)
         .await?;
     }
 
@@ -1191,7 +2294,7 @@ pub async fn save_master_video(video: MasterVideo) -> Result<MasterVideo> {
             "SELECT id, path, size FROM release_files WHERE path = $1",
             &path.to_string_lossy()
         )
-        .fetch_one(&mut *tx)
+        .fetch_one(&mut **tx)
         .await?;
 
         sqlx::query!(
@@ -1201,20 +2304,48 @@ pub async fn save_master_video(video: MasterVideo) -> Result<MasterVideo> {
             video_id,
             row.id
         )
-        .execute(&mut *tx)
+        .execute(&mut **tx)
         .await?;
 
         updated_video.nist_files[i] = (path, row.size as u64);
     }
 
-    tx.commit().await?;
-
     Ok(updated_video)
 }
 
-pub async fn save_video(video: Video) -> Result<Video> {
-    let pool = establish_connection().await?;
+/// Sets the time of day for a batch of event timestamps.
+pub async fn save_times_of_day(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    times: &[(i32, NaiveTime)],
+) -> Result<()> {
+    for (id, time_of_day) in times.iter() {
+        sqlx::query!(
+            "UPDATE event_timestamps SET time_of_day = $1 WHERE id = $2",
+            time_of_day,
+            id
+        )
+        .execute(&mut **tx)
+        .await?;
+    }
+    // The times belong to master videos, so changing them is a change to those videos.
+    let ids: Vec<i32> = times.iter().map(|(id, _)| *id).collect();
+    sqlx::query!(
+        r#"
+            UPDATE master_videos SET version = version + 1
+            WHERE id IN (SELECT master_video_id FROM event_timestamps WHERE id = ANY($1))
+        "#,
+        &ids
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
 
+pub async fn save_video(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    video: Video,
+    expected_version: Option<i32>,
+) -> Result<Video> {
     let video_id = if video.id == 0 {
         sqlx::query!(
             r#"
@@ -1231,11 +2362,11 @@ pub async fn save_video(video: Video) -> Result<Video> {
             video.master.id,
             video.title,
         )
-        .fetch_one(&pool)
+        .fetch_one(&mut **tx)
         .await?
         .id
     } else {
-        sqlx::query!(
+        let row = sqlx::query!(
             r#"
                 INSERT INTO videos (
                     id, channel_username, description, duration, is_primary, link, master_id, title)
@@ -1246,8 +2377,10 @@ pub async fn save_video(video: Video) -> Result<Video> {
                     is_primary = EXCLUDED.is_primary,
                     link = EXCLUDED.link,
                     master_id = EXCLUDED.master_id,
-                    title = EXCLUDED.title
-                RETURNING id
+                    title = EXCLUDED.title,
+                    version = videos.version + 1
+                WHERE $9::int IS NULL OR videos.version = $9
+                RETURNING id, (xmax = 0) AS "inserted!"
            "#,
             video.id,
             video.channel_username,
@@ -1257,10 +2390,19 @@ pub async fn save_video(video: Video) -> Result<Video> {
             video.link,
             video.master.id,
             video.title,
+            expected_version,
         )
-        .fetch_one(&pool)
-        .await?
-        .id
+        .fetch_optional(&mut **tx)
+        .await?;
+        check_saved_version(
+            tx,
+            "video",
+            video.id,
+            expected_version,
+            row.map(|r| r.inserted),
+        )
+        .await?;
+        video.id
     };
 
     let mut updated_video = video.clone();
@@ -1314,15 +2456,41 @@ pub async fn save_release(release: Release) -> Result<Release> {
     Ok(updated_release)
 }
 
-pub async fn save_nist_tape_files(tape_id: i32, files: Vec<(PathBuf, u64)>) -> Result<NistTape> {
-    let pool = establish_connection().await?;
-    let mut tx = pool.begin().await?;
+pub async fn save_nist_tape_files(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    tape_id: i32,
+    files: Vec<(PathBuf, u64)>,
+    expected_version: Option<i32>,
+) -> Result<NistTape> {
+    let result = sqlx::query!(
+        r#"
+            UPDATE nist_tapes SET version = version + 1
+            WHERE tape_id = $1 AND ($2::int IS NULL OR version = $2)
+        "#,
+        tape_id,
+        expected_version
+    )
+    .execute(&mut **tx)
+    .await?;
+    if result.rows_affected() == 0 && expected_version.is_none() {
+        return Err(Error::NistTapeNotFound(tape_id));
+    }
+    let saved = (result.rows_affected() > 0).then_some(false);
+    check_saved_version(tx, "nist-tape", tape_id, expected_version, saved).await?;
+    replace_nist_tape_files(tx, tape_id, &files).await?;
+    get_nist_tape_tx(tx, tape_id).await
+}
 
+async fn replace_nist_tape_files(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    tape_id: i32,
+    files: &[(PathBuf, u64)],
+) -> Result<()> {
     sqlx::query!(
         "DELETE FROM nist_tapes_release_files WHERE nist_tape_id = $1",
         tape_id
     )
-    .execute(&mut *tx)
+    .execute(&mut **tx)
     .await?;
 
     for file in files.iter() {
@@ -1331,7 +2499,7 @@ pub async fn save_nist_tape_files(tape_id: i32, files: Vec<(PathBuf, u64)>) -> R
             "SELECT id, path, size FROM release_files WHERE path = $1",
             &path.to_string_lossy()
         )
-        .fetch_one(&mut *tx)
+        .fetch_one(&mut **tx)
         .await?;
 
         sqlx::query!(
@@ -1343,46 +2511,167 @@ pub async fn save_nist_tape_files(tape_id: i32, files: Vec<(PathBuf, u64)>) -> R
             tape_id,
             row.id
         )
-        .execute(&mut *tx)
+        .execute(&mut **tx)
         .await?;
     }
-
-    tx.commit().await?;
-
-    let updated_tape = get_nist_tapes().await?;
-    updated_tape
-        .into_iter()
-        .find(|t| t.tape_id == tape_id)
-        .ok_or_else(|| Error::NistTapeNotFound(tape_id))
+    Ok(())
 }
 
 pub async fn save_nist_video(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
     id: i32,
     is_missing: bool,
     additional_notes: &str,
+    expected_version: Option<i32>,
 ) -> Result<NistVideo> {
-    let pool = establish_connection().await?;
-    let mut tx = pool.begin().await?;
-
-    sqlx::query!(
+    let result = sqlx::query!(
         r#"
-            UPDATE nist_videos SET is_missing = $1, additional_notes = $2
-            WHERE video_id = $3
+            UPDATE nist_videos
+            SET is_missing = $1, additional_notes = $2, version = version + 1
+            WHERE video_id = $3 AND ($4::int IS NULL OR version = $4)
         "#,
         is_missing,
         additional_notes,
-        id
+        id,
+        expected_version
     )
-    .execute(&mut *tx)
+    .execute(&mut **tx)
     .await?;
+    if result.rows_affected() == 0 && expected_version.is_none() {
+        return Err(Error::NistVideoNotFound(id));
+    }
+    let saved = (result.rows_affected() > 0).then_some(false);
+    check_saved_version(tx, "nist-video", id, expected_version, saved).await?;
 
-    tx.commit().await?;
+    get_nist_video_tx(tx, id).await
+}
 
-    let updated_tape = get_nist_videos().await?;
-    updated_tape
-        .into_iter()
-        .find(|v| v.video_id == id)
-        .ok_or_else(|| Error::NistVideoNotFound(id))
+/// Maps a network name from NIST's videos table, replacing any previous mapping for the name.
+pub async fn save_nist_network_mapping(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    mapping: &NistNetworkMapping,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+            INSERT INTO nist_network_mappings (nist_network, news_network_id, news_affiliate_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (nist_network) DO UPDATE
+            SET news_network_id = EXCLUDED.news_network_id,
+                news_affiliate_id = EXCLUDED.news_affiliate_id
+        "#,
+        mapping.nist_network,
+        mapping.news_network.as_ref().map(|n| n.id),
+        mapping.news_affiliate.as_ref().map(|a| a.id)
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Finds the release files at or below each of the given paths.
+///
+/// A path to a file gives that file and a path to a directory gives every file below it, in order
+/// of their paths. A path that matches no release files gives an empty list.
+pub async fn expand_release_paths(paths: &[String]) -> Result<Vec<(String, Vec<PathBuf>)>> {
+    let pool = establish_connection().await?;
+    let rows = sqlx::query!(
+        r#"
+            SELECT p.path AS "input!", rf.path AS "path?"
+            FROM UNNEST($1::text[]) AS p(path)
+            LEFT JOIN release_files rf
+                ON rf.path = p.path OR STARTS_WITH(rf.path, RTRIM(p.path, '/') || '/')
+            ORDER BY rf.path
+        "#,
+        paths
+    )
+    .fetch_all(&pool)
+    .await?;
+    Ok(paths
+        .iter()
+        .map(|input| {
+            let files = rows
+                .iter()
+                .filter(|r| &r.input == input)
+                .filter_map(|r| r.path.as_ref().map(PathBuf::from))
+                .collect();
+            (input.clone(), files)
+        })
+        .collect())
+}
+
+/// Returns the paths from the given list that are not in the release files table.
+pub async fn find_missing_release_files(paths: &[String]) -> Result<Vec<String>> {
+    let pool = establish_connection().await?;
+    let missing = sqlx::query!(
+        r#"SELECT p.path AS "path!" FROM UNNEST($1::text[]) AS p(path)
+           WHERE NOT EXISTS (SELECT 1 FROM release_files rf WHERE rf.path = p.path)"#,
+        paths
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|r| r.path)
+    .collect();
+    Ok(missing)
+}
+
+/// Moves the ID sequences of the curated tables past the highest ID in each table.
+///
+/// This is required after records have been inserted with explicit IDs, otherwise the next record
+/// added without an ID would be given one that's already in use.
+pub async fn reset_id_sequences(tx: &mut sqlx::Transaction<'_, Postgres>) -> Result<()> {
+    sqlx::query!(
+        r#"
+            DO $$
+            DECLARE
+                t TEXT;
+            BEGIN
+                FOREACH t IN ARRAY ARRAY[
+                    'event_timestamps', 'master_videos', 'news_affiliates', 'news_broadcasts',
+                    'news_networks', 'nist_identifiers', 'people', 'videos'
+                ] LOOP
+                    EXECUTE format(
+                        'SELECT setval(pg_get_serial_sequence(%L, ''id''), '
+                        'COALESCE(MAX(id), 1), MAX(id) IS NOT NULL) FROM %I',
+                        t, t
+                    );
+                END LOOP;
+            END $$
+        "#
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Records a change to a record in the audit log.
+///
+/// The user making the change is taken from the `AW_USER` environment variable, or `USER` if that
+/// isn't set.
+pub async fn save_audit_entry(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    entity: &str,
+    entity_id: i32,
+    before: Option<&str>,
+    after: &str,
+) -> Result<()> {
+    let changed_by = std::env::var("AW_USER")
+        .or_else(|_| std::env::var("USER"))
+        .unwrap_or_else(|_| "unknown".to_string());
+    sqlx::query!(
+        r#"
+            INSERT INTO audit_log (entity, entity_id, before, after, changed_by)
+            VALUES ($1, $2, $3::text::jsonb, $4::text::jsonb, $5)
+        "#,
+        entity,
+        entity_id,
+        before,
+        after,
+        changed_by
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
 }
 
 pub async fn save_torrent(release_id: i32, torrent_path: &PathBuf) -> Result<()> {
@@ -1396,3 +2685,93 @@ pub async fn save_torrent(release_id: i32, torrent_path: &PathBuf) -> Result<()>
     query.execute(&pool).await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tables held in memory, in place of NIST's Access database.
+    struct FakeSource {
+        tables: Vec<Table>,
+    }
+
+    impl TableSource for FakeSource {
+        fn table_names(&self) -> Result<Vec<String>> {
+            Ok(self.tables.iter().map(|t| t.name.clone()).collect())
+        }
+
+        fn read_table(&self, name: &str) -> Result<Table> {
+            Ok(self
+                .tables
+                .iter()
+                .find(|t| t.name == name)
+                .cloned()
+                .unwrap())
+        }
+    }
+
+    fn table(name: &str, rows: &[&str]) -> Table {
+        Table {
+            columns: Vec::new(),
+            name: name.to_string(),
+            rows: rows
+                .iter()
+                .map(|r| r.split(',').map(String::from).collect())
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn read_nist_tables_should_report_rows_that_cannot_be_imported() {
+        let source = FakeSource {
+            tables: vec![
+                table(
+                    "Videos",
+                    &[
+                        "1,WTC1 Collapse,ABC,,60,,",
+                        "two,Bad ID,ABC,,60,,",
+                        "1,Duplicate,CBS,,30,,",
+                    ],
+                ),
+                table(
+                    "Tapes",
+                    &[
+                        "10,1,Tape A,NIST,1,0,VHS,60,0,0,0",
+                        "11,9,Tape B,NIST,1,0,VHS,60,0,0,0",
+                        "12,5,Tape C,NIST,1,10,VHS,60,0,0,0",
+                        "10,1,Tape A again,NIST,1,0,VHS,60,0,0,0",
+                    ],
+                ),
+                table("Notes", &["a,b"]),
+            ],
+        };
+
+        let tables = read_nist_tables(&source, &[5]).unwrap();
+        let video_ids: Vec<i32> = tables.videos.iter().map(|v| v.value.video_id).collect();
+        assert_eq!(video_ids, vec![1]);
+        let tape_ids: Vec<i32> = tables.tapes.iter().map(|t| t.value.tape_id).collect();
+        assert_eq!(tape_ids, vec![10, 12]);
+        assert_eq!(tables.other_tables.len(), 1);
+
+        let failed: Vec<(String, usize, String)> = tables
+            .failed
+            .into_iter()
+            .map(|f| (f.table, f.row, f.error))
+            .collect();
+        assert_eq!(failed.len(), 4);
+        assert_eq!(
+            failed[0..2]
+                .iter()
+                .map(|(table, row, _)| (table.as_str(), *row))
+                .collect::<Vec<_>>(),
+            vec![("Tapes", 2), ("Tapes", 4)]
+        );
+        assert_eq!(failed[0].2, "video 9 does not exist");
+        assert_eq!(failed[1].2, "duplicate tape 10");
+        assert_eq!((failed[2].0.as_str(), failed[2].1), ("Videos", 2));
+        assert_eq!(
+            (failed[3].0.as_str(), failed[3].1, failed[3].2.as_str()),
+            ("Videos", 3, "duplicate video 1")
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use crate::error::{Error, Result};
+use csv::ReaderBuilder;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A table read from an Access database, with every value as text.
+///
+/// A null value is an empty string, which is the same as a CSV export from Access.
+#[derive(Clone, Debug, Default)]
+pub struct Table {
+    pub columns: Vec<String>,
+    pub name: String,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Reads the tables from an Access database.
+pub trait TableSource {
+    fn table_names(&self) -> Result<Vec<String>>;
+    fn read_table(&self, name: &str) -> Result<Table>;
+}
+
+/// Reads a Jet or ACE database using the `mdb-tables` and `mdb-export` commands from mdbtools.
+pub struct MdbTools {
+    path: PathBuf,
+}
+
+impl MdbTools {
+    pub fn new(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("{} does not exist", path.to_string_lossy()),
+            )));
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Runs one of the mdbtools commands, which take their options, then the path of the
+    /// database, then the name of a table if the command needs one.
+    fn run(&self, program: &str, options: &[&str], table: Option<&str>) -> Result<String> {
+        let output = Command::new(program)
+            .args(options)
+            .arg(&self.path)
+            .args(table)
+            .output()
+            .map_err(|e| {
+                Error::MdbToolsError(format!(
+                    "could not run {program} ({e}); is mdbtools installed?"
+                ))
+            })?;
+        if !output.status.success() {
+            return Err(Error::MdbToolsError(format!(
+                "{program} failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+impl TableSource for MdbTools {
+    fn table_names(&self) -> Result<Vec<String>> {
+        // The system tables are only listed with -S, so these are the user tables.
+        let output = self.run("mdb-tables", &["-1"], None)?;
+        Ok(output
+            .lines()
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect())
+    }
+
+    fn read_table(&self, name: &str) -> Result<Table> {
+        // Binary columns hold OLE objects, which aren't useful as text.
+        let output = self.run(
+            "mdb-export",
+            &["-D", "%Y-%m-%d %H:%M:%S", "-b", "strip"],
+            Some(name),
+        )?;
+        parse_export(name, &output)
+    }
+}
+
+/// Parses the CSV written by `mdb-export`, which has a header row with the column names.
+pub fn parse_export(name: &str, csv: &str) -> Result<Table> {
+    let mut rdr = ReaderBuilder::new()
+        .has_headers(true)
+        .from_reader(csv.as_bytes());
+    let columns = rdr.headers()?.iter().map(|h| h.to_string()).collect();
+    let mut rows = Vec::new();
+    for result in rdr.records() {
+        let record = result?;
+        rows.push(record.iter().map(|v| v.to_string()).collect());
+    }
+    Ok(Table {
+        columns,
+        name: name.to_string(),
+        rows,
+    })
+}
@@ -6,7 +6,7 @@ use crate::{
         strip_first_two_directories,
     },
 };
-use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use colored::Colorize;
 use image::GenericImageView;
 use magick_rust::{magick_wand_genesis, MagickWand};
@@ -197,12 +197,41 @@ impl TryFrom<PathBuf> for Image {
 pub enum ConversionError {
     #[error(transparent)]
     DateParsingError(#[from] chrono::ParseError),
+    #[error("'{0}' is not a valid category")]
+    InvalidCategory(String),
+    #[error("'{0}' is not a valid date")]
+    InvalidDate(String),
+    #[error("'{0}' is not a valid event type")]
+    InvalidEventType(String),
     #[error("The source list must have {0} elements")]
     InvalidLength(u16),
     #[error(transparent)]
     ParseError(#[from] std::num::ParseIntError),
 }
 
+/// Parses a date from one of NIST's tables.
+///
+/// Access writes dates with a time of midnight, using a two-digit year in its own CSV exports and
+/// whatever format was asked for when the database is read with mdbtools.
+pub fn parse_nist_date(value: &str) -> Result<NaiveDate, ConversionError> {
+    let value = value.trim();
+    for format in [
+        "%m/%d/%y %H:%M:%S",
+        "%m/%d/%Y %H:%M:%S",
+        "%Y-%m-%d %H:%M:%S",
+    ] {
+        if let Ok(date_time) = NaiveDateTime::parse_from_str(value, format) {
+            return Ok(date_time.date());
+        }
+    }
+    for format in ["%m/%d/%y", "%m/%d/%Y", "%Y-%m-%d"] {
+        if let Ok(date) = NaiveDate::parse_from_str(value, format) {
+            return Ok(date);
+        }
+    }
+    Err(ConversionError::InvalidDate(value.to_string()))
+}
+
 #[derive(Clone, Default, sqlx::FromRow)]
 pub struct NistVideo {
     pub video_id: i32,
@@ -279,6 +308,12 @@ impl NistVideo {
     }
 }
 
+impl std::fmt::Display for NistVideo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.video_id, self.video_title)
+    }
+}
+
 impl TryFrom<Vec<String>> for NistVideo {
     type Error = ConversionError;
 
@@ -293,8 +328,7 @@ impl TryFrom<Vec<String>> for NistVideo {
         let broadcast_date = if values[3].is_empty() {
             None
         } else {
-            let date = NaiveDate::parse_from_str(&values[3].clone(), "%m/%d/%y 00:00:00")?;
-            Some(date)
+            Some(parse_nist_date(&values[3])?)
         };
         let duration_min: i32 = values[4].parse()?;
         let subject = if values[5].is_empty() {
@@ -567,15 +601,18 @@ impl std::fmt::Display for Category {
     }
 }
 
-impl From<&str> for Category {
-    fn from(s: &str) -> Self {
+impl TryFrom<&str> for Category {
+    type Error = ConversionError;
+
+    fn try_from(s: &str) -> std::result::Result<Self, Self::Error> {
         match s {
-            "amateur-footage" => Category::AmateurFootage,
-            "compilation" => Category::Compilation,
-            "documentary" => Category::Documentary,
-            "news" => Category::News,
-            "professional-footage" => Category::ProfessionalFootage,
-            _ => panic!("'{s}' is not a valid category"),
+            "amateur-footage" => Ok(Category::AmateurFootage),
+            "compilation" => Ok(Category::Compilation),
+            "documentary" => Ok(Category::Documentary),
+            "news" => Ok(Category::News),
+            "professional-footage" => Ok(Category::ProfessionalFootage),
+            "survivor-account" => Ok(Category::SurvivorAccount),
+            _ => Err(ConversionError::InvalidCategory(s.to_string())),
         }
     }
 }
@@ -586,79 +623,77 @@ impl PgHasArrayType for Category {
     }
 }
 
-#[derive(Clone, Debug, sqlx::Type)]
-#[sqlx(type_name = "event_type", rename_all = "lowercase")]
-pub enum EventType {
-    CameraSource,
-    Jumper,
-    Key,
-    Normal,
-    Person,
-    PentagonAttack,
-    Report,
-    Wtc1Collapse,
-    Wtc1Impact,
-    Wtc2Collapse,
-    Wtc2Impact,
+/// A kind of event that a timestamp can mark, from the `event_types` table.
+///
+/// Event types are identified by their slug, which is how they are written in forms and records.
+/// Those with a canonical time are key events, such as the impacts and collapses, whose time of
+/// day is known.
+#[derive(Clone, Debug, Default, FromRow)]
+pub struct EventType {
+    pub slug: String,
+    pub label: String,
+    pub description: String,
+    /// The time of day at which the event occurred, local to New York and Arlington (EDT).
+    pub canonical_time: Option<NaiveTime>,
+}
+
+impl PartialEq for EventType {
+    fn eq(&self, other: &Self) -> bool {
+        self.slug == other.slug
+    }
 }
 
 impl std::fmt::Display for EventType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let event_type_str = match self {
-            EventType::CameraSource => "camera-source",
-            EventType::Jumper => "jumper",
-            EventType::Key => "key",
-            EventType::Normal => "normal",
-            EventType::Person => "person",
-            EventType::PentagonAttack => "pentagon-attack",
-            EventType::Report => "report",
-            EventType::Wtc1Collapse => "wtc1-collapse",
-            EventType::Wtc1Impact => "wtc1-impact",
-            EventType::Wtc2Collapse => "wtc2-collapse",
-            EventType::Wtc2Impact => "wtc2-impact",
-        };
-        write!(f, "{}", event_type_str)
+        write!(f, "{}", self.slug)
     }
 }
 
-impl From<&str> for EventType {
-    fn from(s: &str) -> Self {
-        match s {
-            "camera-source" => EventType::CameraSource,
-            "jumper" => EventType::Jumper,
-            "key" => EventType::Key,
-            "normal" => EventType::Normal,
-            "person" => EventType::Person,
-            "pentagon-attack" => EventType::PentagonAttack,
-            "report" => EventType::Report,
-            "wtc1-collapse" => EventType::Wtc1Collapse,
-            "wtc1-impact" => EventType::Wtc1Impact,
-            "wtc2-collapse" => EventType::Wtc2Collapse,
-            "wtc2-impact" => EventType::Wtc2Impact,
-            _ => panic!("'{s}' is not a valid event type"),
-        }
+impl EventType {
+    /// Finds the event type with the given slug.
+    pub fn find(slug: &str, event_types: &[EventType]) -> Result<EventType, ConversionError> {
+        event_types
+            .iter()
+            .find(|t| t.slug == slug)
+            .cloned()
+            .ok_or_else(|| ConversionError::InvalidEventType(slug.to_string()))
+    }
+
+    /// The slug is written in the square brackets at the end of each timestamp in a form, and is
+    /// the file name of the event type in a dataset.
+    pub fn is_valid_slug(slug: &str) -> bool {
+        !slug.is_empty()
+            && slug
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
     }
 }
 
+/// An event at an offset into a master video.
+///
+/// Events with a duration, such as a report, can also have the offset at which they end.
 #[derive(Clone, Debug)]
 pub struct EventTimestamp {
     pub id: i32,
     pub description: String,
     pub timestamp: PgInterval,
+    pub end_timestamp: Option<PgInterval>,
     pub event_type: EventType,
     pub time_of_day: Option<NaiveTime>,
 }
 
-impl TryFrom<&str> for EventTimestamp {
-    type Error = &'static str;
-
-    fn try_from(s: &str) -> Result<Self, Self::Error> {
+impl EventTimestamp {
+    /// Parses a timestamp from a line of the form
+    /// `OFFSET[-END]: DESCRIPTION [HHMM] [EVENT TYPE]`, where the time of day is optional.
+    ///
+    /// The event type must be the slug of one of the given event types.
+    pub fn parse(s: &str, event_types: &[EventType]) -> Result<Self, String> {
         let time_and_desc_regex = Regex::new(
-            r"^(?P<time>\d{2}:\d{2}:\d{2}): (?P<description>.+?) \[(?P<time_of_day>\d{4})?\] \[(?P<event_type>[\w-]+)\]$"
+            r"^(?P<time>\d{2}:\d{2}:\d{2})(-(?P<end>\d{2}:\d{2}:\d{2}))?: (?P<description>.+?) \[(?P<time_of_day>\d{4})?\] \[(?P<event_type>[\w-]+)\]$"
         ).map_err(|_| "Regex compilation failed")?;
 
         let time_and_desc_simple_regex = Regex::new(
-            r"^(?P<time>\d{2}:\d{2}:\d{2}): (?P<description>.+?) \[(?P<event_type>[\w-]+)\]$",
+            r"^(?P<time>\d{2}:\d{2}:\d{2})(-(?P<end>\d{2}:\d{2}:\d{2}))?: (?P<description>.+?) \[(?P<event_type>[\w-]+)\]$",
         )
         .map_err(|_| "Regex compilation failed")?;
 
@@ -672,11 +707,18 @@ impl TryFrom<&str> for EventTimestamp {
 
         let timestamp = match PgInterval::try_from(parse_duration(time)) {
             Ok(timestamp) => timestamp,
-            Err(_) => return Err("Invalid timestamp format"),
+            Err(_) => return Err("Invalid timestamp format".to_string()),
+        };
+        let end_timestamp = match caps.name("end") {
+            Some(end) => match PgInterval::try_from(parse_duration(end.as_str())) {
+                Ok(end_timestamp) => Some(end_timestamp),
+                Err(_) => return Err("Invalid timestamp format".to_string()),
+            },
+            None => None,
         };
 
         let event_type_str = caps.name("event_type").unwrap().as_str();
-        let event_type = EventType::from(event_type_str);
+        let event_type = EventType::find(event_type_str, event_types).map_err(|e| e.to_string())?;
 
         let time_of_day = caps.name("time_of_day").and_then(|tod| {
             let hours = tod.as_str()[0..2].parse::<u32>().ok()?;
@@ -688,6 +730,7 @@ impl TryFrom<&str> for EventTimestamp {
             id: 0,
             description: description.to_string(),
             timestamp,
+            end_timestamp,
             event_type,
             time_of_day,
         })
@@ -696,11 +739,14 @@ impl TryFrom<&str> for EventTimestamp {
 
 impl std::fmt::Display for EventTimestamp {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut s = format!(
-            "{}: {}",
-            duration_to_string(&interval_to_duration(&self.timestamp)),
-            self.description
-        );
+        let mut s = duration_to_string(&interval_to_duration(&self.timestamp));
+        if let Some(end) = &self.end_timestamp {
+            s.push_str(&format!(
+                "-{}",
+                duration_to_string(&interval_to_duration(end))
+            ));
+        }
+        s.push_str(&format!(": {}", self.description));
         if let Some(time) = self.time_of_day {
             s.push_str(&format!(" [{}]", time.format("%H%M")))
         }
@@ -811,7 +857,42 @@ impl std::fmt::Display for NewsBroadcast {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, sqlx::Type)]
+/// The news network or affiliate that a network name in NIST's videos table refers to.
+///
+/// A mapping with neither has been reviewed and has no match.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NistNetworkMapping {
+    pub nist_network: String,
+    pub news_affiliate: Option<NewsAffiliate>,
+    pub news_network: Option<NewsNetwork>,
+}
+
+impl NistNetworkMapping {
+    /// Whether the broadcast is from the network or affiliate the name is mapped to.
+    pub fn is_for(&self, broadcast: &NewsBroadcast) -> bool {
+        match (&self.news_network, &self.news_affiliate) {
+            (Some(network), _) => broadcast
+                .news_network
+                .as_ref()
+                .is_some_and(|n| n.id == network.id),
+            (_, Some(affiliate)) => broadcast
+                .news_affiliate
+                .as_ref()
+                .is_some_and(|a| a.id == affiliate.id),
+            _ => false,
+        }
+    }
+
+    pub fn target(&self) -> String {
+        match (&self.news_network, &self.news_affiliate) {
+            (Some(network), _) => network.name.clone(),
+            (_, Some(affiliate)) => format!("{} ({})", affiliate.name, affiliate.network.name),
+            _ => "No match".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, sqlx::Type)]
 #[sqlx(type_name = "person_type", rename_all = "lowercase")]
 pub enum PersonType {
     Eyewitness,
@@ -881,6 +962,7 @@ pub struct MasterVideo {
     pub news_broadcasts: Vec<NewsBroadcast>,
     pub nist_files: Vec<(PathBuf, u64)>,
     pub nist_notes: Option<String>,
+    pub nist_videos: Vec<NistVideo>,
     pub people: Vec<Person>,
     pub timestamps: Vec<EventTimestamp>,
     pub title: String,
@@ -949,6 +1031,20 @@ impl MasterVideo {
         }
         println!("---");
 
+        if self.nist_videos.is_empty() {
+            println!("NIST Videos:");
+        } else {
+            println!(
+                "NIST Videos: {}",
+                self.nist_videos
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<String>>()
+                    .join("; ")
+            );
+        }
+        println!("---");
+
         println!(
             "NIST Notes:\n{}",
             self.nist_notes.as_ref().unwrap_or(&String::new())
@@ -1099,16 +1195,139 @@ impl Video {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub enum SearchResultType {
+    EventTimestamp,
+    MasterVideo,
+    NewsBroadcast,
+    NistTape,
+    NistVideo,
+    Person,
+}
+
+impl std::fmt::Display for SearchResultType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let result_type_str = match self {
+            SearchResultType::EventTimestamp => "timestamp",
+            SearchResultType::MasterVideo => "master",
+            SearchResultType::NewsBroadcast => "broadcast",
+            SearchResultType::NistTape => "nist-tape",
+            SearchResultType::NistVideo => "nist-video",
+            SearchResultType::Person => "person",
+        };
+        write!(f, "{}", result_type_str)
+    }
+}
+
+impl TryFrom<&str> for SearchResultType {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<Self> {
+        match s {
+            "timestamp" => Ok(SearchResultType::EventTimestamp),
+            "master" => Ok(SearchResultType::MasterVideo),
+            "broadcast" => Ok(SearchResultType::NewsBroadcast),
+            "nist-tape" => Ok(SearchResultType::NistTape),
+            "nist-video" => Ok(SearchResultType::NistVideo),
+            "person" => Ok(SearchResultType::Person),
+            _ => Err(Error::InvalidSearchResultType(s.to_string())),
+        }
+    }
+}
+
+/// A single match from a full-text search over the catalogue.
+///
+/// For event timestamps, the ID is the ID of the master video the timestamp belongs to, so it can
+/// be used directly with the `masters` commands.
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    pub id: i32,
+    pub rank: f32,
+    pub result_type: SearchResultType,
+    pub snippet: String,
+    pub title: String,
+}
+
+impl SearchResult {
+    pub fn print_row(&self) {
+        println!(
+            "{} {}: {} ({:.3})",
+            format!("[{}]", self.result_type).blue(),
+            self.id,
+            self.title,
+            self.rank
+        );
+        let snippet = self
+            .snippet
+            .replace('\n', " ")
+            .split("<b>")
+            .enumerate()
+            .map(|(i, part)| {
+                if i == 0 {
+                    return part.to_string();
+                }
+                match part.split_once("</b>") {
+                    Some((matched, rest)) => format!("{}{}", matched.green().bold(), rest),
+                    None => part.to_string(),
+                }
+            })
+            .collect::<String>();
+        println!("    {}", snippet);
+    }
+}
+
+/// An identifier for a NIST tape or video from a table in one of NIST's FOIA responses.
+#[derive(Clone, Debug)]
+pub struct NistIdentifier {
+    pub entity: String,
+    pub entity_id: i32,
+    pub foia_response: String,
+    pub id: i32,
+    pub imported_at: DateTime<Utc>,
+    pub kind: String,
+    pub value: String,
+}
+
+/// A change saved to a record in the catalogue.
+///
+/// The snapshots are JSON documents. There is no snapshot before the change when the record was
+/// added.
+#[derive(Clone, Debug)]
+pub struct AuditEntry {
+    pub after: String,
+    pub before: Option<String>,
+    pub changed_at: DateTime<Utc>,
+    pub changed_by: String,
+    pub entity: String,
+    pub entity_id: i32,
+    pub id: i32,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn event_type(slug: &str) -> EventType {
+        EventType {
+            slug: slug.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn event_types() -> Vec<EventType> {
+        vec![
+            event_type("normal"),
+            event_type("report"),
+            event_type("wtc2-impact"),
+        ]
+    }
+
     #[test]
-    fn try_from_should_parse_timestamp_without_time_of_day() {
+    fn parse_should_parse_timestamp_without_time_of_day() {
         let input_str =
             "00:08:05: Local coverage commences with an ‘Eyewitness News Special Report’. [normal]";
-        let event_timestamp = EventTimestamp::try_from(input_str).unwrap();
-        matches!(event_timestamp.event_type, EventType::Normal);
+        let event_timestamp = EventTimestamp::parse(input_str, &event_types()).unwrap();
+        assert_eq!(event_timestamp.event_type.slug, "normal");
         assert_eq!(
             event_timestamp.description,
             "Local coverage commences with an ‘Eyewitness News Special Report’."
@@ -1121,10 +1340,10 @@ mod tests {
     }
 
     #[test]
-    fn try_from_should_parse_timestamp_with_time_of_day() {
+    fn parse_should_parse_timestamp_with_time_of_day() {
         let input_str = "00:20:00: UA175 hits the South Tower during a call with eyewitness Winston Mitchell. [0903] [wtc2-impact]";
-        let event_timestamp = EventTimestamp::try_from(input_str).unwrap();
-        matches!(event_timestamp.event_type, EventType::Wtc2Impact);
+        let event_timestamp = EventTimestamp::parse(input_str, &event_types()).unwrap();
+        assert_eq!(event_timestamp.event_type.slug, "wtc2-impact");
         assert_eq!(
             event_timestamp.description,
             "UA175 hits the South Tower during a call with eyewitness Winston Mitchell."
@@ -1145,8 +1364,9 @@ mod tests {
             id: 1,
             description: "Local coverage commences with an ‘Eyewitness News Special Report’."
                 .to_string(),
-            event_type: EventType::Normal,
+            event_type: event_type("normal"),
             timestamp: PgInterval::try_from(parse_duration("00:08:05")).unwrap(),
+            end_timestamp: None,
             time_of_day: None,
         };
 
@@ -1164,8 +1384,9 @@ mod tests {
             description:
                 "UA175 hits the South Tower during a call with eyewitness Winston Mitchell."
                     .to_string(),
-            event_type: EventType::Wtc2Impact,
+            event_type: event_type("wtc2-impact"),
             timestamp: PgInterval::try_from(parse_duration("00:20:00")).unwrap(),
+            end_timestamp: None,
             time_of_day: NaiveTime::from_hms_opt(9, 3, 0),
         };
 
@@ -1175,4 +1396,43 @@ mod tests {
             "00:20:00: UA175 hits the South Tower during a call with eyewitness Winston Mitchell. [0903] [wtc2-impact]"
         );
     }
+
+    #[test]
+    fn parse_should_parse_timestamp_with_end_time() {
+        let input_str = "00:35:10-00:38:45: John DelGiorno delivers his first report from NewsCopter7. [0917] [report]";
+        let event_timestamp = EventTimestamp::parse(input_str, &event_types()).unwrap();
+        assert_eq!(
+            event_timestamp.end_timestamp,
+            Some(PgInterval::try_from(parse_duration("00:38:45")).unwrap())
+        );
+        assert_eq!(
+            event_timestamp.description,
+            "John DelGiorno delivers his first report from NewsCopter7."
+        );
+        assert_eq!(event_timestamp.to_string(), input_str);
+    }
+
+    #[test]
+    fn parse_should_reject_an_unknown_event_type() {
+        let input_str = "00:08:05: Local coverage commences. [explosion]";
+        let error = EventTimestamp::parse(input_str, &event_types())
+            .err()
+            .unwrap();
+        assert_eq!(error, "'explosion' is not a valid event type");
+        assert!(Category::try_from("survivor-account").is_ok());
+        assert!(Category::try_from("interview").is_err());
+    }
+
+    #[test]
+    fn parse_nist_date_should_accept_access_and_mdbtools_formats() {
+        let date = NaiveDate::from_ymd_opt(2001, 9, 11).unwrap();
+        assert_eq!(parse_nist_date("09/11/01 00:00:00").unwrap(), date);
+        assert_eq!(parse_nist_date("09/11/2001 00:00:00").unwrap(), date);
+        assert_eq!(parse_nist_date("2001-09-11 00:00:00").unwrap(), date);
+        assert_eq!(parse_nist_date("2001-09-11").unwrap(), date);
+        assert!(matches!(
+            parse_nist_date("11 September 2001"),
+            Err(ConversionError::InvalidDate(_))
+        ));
+    }
 }
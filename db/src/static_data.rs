@@ -1,258 +0,0 @@
-use lazy_static::lazy_static;
-use maplit::hashmap;
-use std::collections::HashMap;
-
-lazy_static! {
-    pub static ref DOCUMENT_DATABASE_NUMBERS: HashMap<i32, &'static str> = hashmap! {
-        14 => "WTCI-332-I",
-        15 => "WTCI-334-I",
-        16 => "WTCI-333-I",
-        19 => "WTCI-335-I",
-        22 => "WTCI-336-I",
-        25 => "WTCI-337-I",
-        28 => "WTCI-338-I",
-        33 => "WTCI-339-I",
-        35 => "WTCI-340-I",
-        36 => "WTCI-341-I",
-        37 => "WTCI-342-I",
-        40 => "WTCI-343-I",
-        42 => "WTCI-344-I",
-        43 => "WTCI-345-I",
-        46 => "WTCI-346-I",
-        47 => "WTCI-347-I",
-        49 => "WTCI-348-I",
-        54 => "WTCI-349-I",
-        56 => "WTCI-350-I",
-        59 => "WTCI-351-I",
-        60 => "WTCI-352-I",
-        61 => "WTCI-353-I",
-        62 => "WTCI-354-I",
-        63 => "WTCI-355-I",
-        64 => "WTCI-356-I",
-        66 => "WTCI-357-I",
-        67 => "WTCI-358-I",
-        70 => "WTCI-359-I",
-        71 => "WTCI-360-I",
-        72 => "WTCI-361-I",
-        73 => "WTCI-362-I",
-        74 => "WTCI-363-I",
-        80 => "WTCI-364-I",
-        81 => "WTCI-365-I",
-        82 => "WTCI-73-S",
-        83 => "WTCI-74-S",
-        84 => "WTCI-75-S",
-        85 => "WTCI-85-S",
-        86 => "WTCI-77-S",
-        87 => "WTCI-78-S",
-        88 => "WTCI-79-S",
-        89 => "WTCI-80-S",
-        90 => "WTCI-81-S",
-        91 => "WTCI-82-S",
-        92 => "WTCI-83-S",
-        94 => "WTCI-84-S",
-        95 => "WTCI-85-S",
-        97 => "WTCI-86-S",
-        98 => "WTCI-87-S",
-        99 => "WTCI-88-S",
-        100 => "WTCI-91-S",
-        101 => "WTCI-92-S",
-        102 => "WTCI-93-S",
-        103 => "WTCI-94-S",
-        104 => "WTCI-96-S",
-        105 => "WTCI-97-S",
-        106 => "WTCI-98-S",
-        107 => "WTCI-100-S",
-        113 => "WTCI-105-S",
-        115 => "WTCI-108-S",
-        116 => "WTCI-109-S",
-        117 => "WTCI-110-S",
-        118 => "WTCI-111-S",
-        119 => "WTCI-112-S",
-        120 => "WTCI-113-S",
-        126 => "WTCI-366-I",
-        128 => "WTCI-367-I",
-        136 => "WTCI-368-I",
-        147 => "WTCI-438-STB",
-        148 => "WTCI-439-STB",
-        149 => "WTCI-440-STB",
-        150 => "WTCI-441-STB",
-        151 => "WTCI-442-STB",
-        152 => "WTCI-443-STB",
-        153 => "WTCI-444-STB",
-        154 => "WTCI-89-S",
-        155 => "WTCI-445-STB",
-        156 => "WTCI-446-STB",
-        157 => "WTCI-447-STB",
-        158 => "WTCI-448-STB",
-        160 => "WTCI-75-FEMA",
-        161 => "WTCI-74-FEMA",
-        162 => "WTCI-99-FEMA",
-        163 => "WTCI-67-FEMA",
-        164 => "WTCI-76-FEMA",
-        165 => "WTCI-94-FEMA",
-        166 => "WTCI-92-FEMA",
-        167 => "WTCI-93-FEMA",
-        168 => "WTCI-100-FEMA",
-        169 => "WTCI-72-FEMA",
-        170 => "WTCI-91-FEMA",
-        171 => "WTCI-68-FEMA",
-        172 => "WTCI-90-FEMA",
-        173 => "WTCI-84-FEMA",
-        174 => "WTCI-73-FEMA",
-        175 => "WTCI-101-FEMA",
-        176 => "WTCI-89-FEMA",
-        177 => "WTCI-95-FEMA",
-        178 => "WTCI-98-FEMA",
-        179 => "WTCI-96-FEMA",
-        180 => "WTCI-97-FEMA",
-        181 => "WTCI-71-FEMA",
-        182 => "WTCI-87-FEMA",
-        183 => "WTCI-88-FEMA",
-        184 => "WTCI-82-FEMA",
-        185 => "WTCI-83-FEMA",
-        186 => "WTCI-81-FEMA",
-        187 => "WTCI-70-FEMA",
-        188 => "WTCI-69-FEMA",
-        190 => "WTCI-79-FEMA",
-        191 => "WTCI-78-FEMA",
-        192 => "WTCI-77-FEMA",
-        193 => "WTCI-86-FEMA",
-        194 => "WTCI-85-FEMA",
-        195 => "WTCI-80-FEMA",
-        196 => "WTCI-70-S",
-        197 => "WTCI-115-S",
-        198 => "WTCI-72-S",
-        199 => "WTCI-89-S",
-        200 => "WTCI-90-S",
-        201 => "WTCI-95-S",
-        202 => "WTCI-99-S",
-        203 => "WTCI-114-S",
-        232 => "WTCI-438-STB",
-        242 => "WTCI-476-I",
-        244 => "WTCI-370-I",
-        245 => "WTCI-474-I",
-        246 => "WTCI-475-I",
-        253 => "WTCI-371-I",
-        254 => "WTCI-439-STB",
-        278 => "WTCI-399-STB",
-        279 => "WTCI-400-STB",
-        280 => "WTCI-401-STB",
-        281 => "WTCI-402-STB",
-        282 => "WTCI-403-STB",
-        290 => "WTCI-372-I",
-        292 => "WTCI-373-I",
-        294 => "WTCI-374-I",
-        297 => "WTCI-2-NYC",
-        299 => "WTCI-376-I",
-        300 => "WTCI-743-I",
-        301 => "WTCI-106-I",
-        302 => "WTCI-107-I",
-        310 => "WTCI-377-I",
-        312 => "WTCI-378-I",
-        314 => "WTCI-379-I",
-        315 => "WTCI-380-I",
-        320 => "WTCI-381-I",
-        322 => "WTCI-110-I",
-        323 => "WTCI-108-I",
-        324 => "WTCI-109-I",
-        325 => "WTCI-111-I",
-        327 => "WTCI-112-I",
-        328 => "WTCI-113-I",
-        329 => "WTCI-114-I",
-        330 => "WTCI-115-I",
-        331 => "WTCI-116-I",
-        334 => "WTCI-117-I",
-        338 => "WTCI-3-ITK",
-        340 => "WTCI-382-I",
-        341 => "WTCI-383-I",
-        344 => "WTCI-472-I",
-        346 => "WTCI-384-I",
-        349 => "WTCI-385-I",
-        375 => "WTCI-386-I",
-        377 => "WTCI-387-I",
-        380 => "WTCI-471-I",
-        382 => "WTCI-388-I",
-        385 => "WTCI-389-I",
-        392 => "WTCI-390-I",
-        394 => "WTCI-391-I",
-        396 => "WTCI-392-I",
-        398 => "WTCI-393-I",
-        400 => "WTCI-394-I",
-        402 => "WTCI-395-I",
-        404 => "WTCI-396-I",
-        406 => "WTCI-397-I",
-        408 => "WTCI-398-I",
-        412 => "WTCI-399-I",
-        414 => "WTCI-400-I",
-        416 => "WTCI-401-I",
-        418 => "WTCI-402-I",
-        420 => "WTCI-403-I",
-        422 => "WTCI-405-I",
-        424 => "WTCI-404-I",
-        426 => "WTCI-406-I",
-        440 => "WTCI-407-I",
-        443 => "WTCI-408-I",
-        446 => "WTCI-409-I",
-        448 => "WTCI-477-I",
-        449 => "WTCI-410-I",
-        451 => "WTCI-411-I",
-        453 => "WTCI-412-I",
-        455 => "WTCI-413-I",
-        457 => "WTCI-414-I",
-        459 => "WTCI-415-I",
-        461 => "WTCI-416-I",
-        463 => "WTCI-417-I",
-        465 => "WTCI-418-I",
-        471 => "WTCI-420-I",
-        473 => "WTCI-421-I",
-        475 => "WTCI-422-I",
-        477 => "WTCI-423-I",
-        479 => "WTCI-424-I",
-        481 => "WTCI-425-I",
-        483 => "WTCI-426-I",
-        485 => "WTCI-427-I",
-        487 => "WTCI-428-I",
-        489 => "WTCI-429-I",
-        491 => "WTCI-430-I",
-        493 => "WTCI-431-I",
-        495 => "WTCI-431-I",
-        496 => "WTCI-432-I",
-        498 => "WTCI-433-I",
-        500 => "WTCI-434-I",
-        502 => "WTCI-435-I",
-        504 => "WTCI-436-I",
-        508 => "WTCI-438-I",
-        511 => "WTCI-259-ITK",
-        513 => "WTCI-260-ITK",
-        515 => "WTCI-261-ITK",
-        516 => "WTCI-439-I",
-        518 => "WTCI-58-SOM",
-        570 => "WTCI-440-I",
-        571 => "WTCI-440-I",
-        586 => "WTCL-441-I",
-        588 => "WTCI-440-I",
-        589 => "WTCI-440-I",
-        592 => "WTCI-442-I",
-        593 => "WTCI-443-I",
-        594 => "WTCI-444-I",
-        595 => "WTCI-445-I",
-        596 => "WTCI-446-I",
-        597 => "WTCI-447-I",
-        598 => "WTCI-448-I",
-        599 => "WTCI-449-I",
-        600 => "WTCI-450-I",
-        619 => "WTCI-236-P",
-        620 => "WTCI-223-I",
-        621 => "WTCI-223-I",
-        622 => "WTCI-223-I",
-        623 => "WTCI-223-I",
-        624 => "WTCI-463-P",
-        625 => "WTCI-119-NYC",
-        626 => "WTCI-636-P",
-        627 => "WTCI-469-I",
-        628 => "WTCI-470-I",
-        629 => "WTCI-478-I",
-        630 => "WTCI-479-I",
-        631 => "WTCI-480-I",
-    };
-}
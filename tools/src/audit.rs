@@ -0,0 +1,329 @@
+use crate::dataset::{
+    AffiliateRecord, BroadcastRecord, MasterRecord, NetworkRecord, NistTapeRecord, NistVideoRecord,
+    VideoRecord,
+};
+use crate::editing::records::RecordEntity;
+use chrono::NaiveTime;
+use clap::ValueEnum;
+use color_eyre::Result;
+use db::models::{
+    MasterVideo, NewsAffiliate, NewsBroadcast, NewsNetwork, NistTape, NistVideo, Video,
+};
+use serde::Serialize;
+use sqlx::{Postgres, Transaction};
+use std::path::PathBuf;
+
+/// The name a kind of record is stored under in the audit log.
+pub fn entity_name(entity: RecordEntity) -> String {
+    entity
+        .to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default()
+}
+
+/// Records a change in the audit log, as part of the transaction that made it.
+///
+/// The snapshots before and after the change use the same representation as the dataset, so the
+/// history of a record can be compared and restored.
+async fn record_change<T: Serialize>(
+    tx: &mut Transaction<'_, Postgres>,
+    entity: RecordEntity,
+    id: i32,
+    before: Option<T>,
+    after: T,
+) -> Result<()> {
+    let before = before.map(|b| serde_json::to_string(&b)).transpose()?;
+    let after = serde_json::to_string(&after)?;
+    // Saving a record without changing it isn't worth recording.
+    if before.as_deref() == Some(after.as_str()) {
+        return Ok(());
+    }
+    db::save_audit_entry(tx, &entity_name(entity), id, before.as_deref(), &after).await?;
+    Ok(())
+}
+
+/// The record as it was before it's saved, which is `None` if the record doesn't exist yet.
+///
+/// Any other error is returned, so an update is never recorded as an addition.
+fn existing<T>(result: db::error::Result<T>) -> Result<Option<T>> {
+    match result {
+        Ok(model) => Ok(Some(model)),
+        Err(e) if e.is_not_found() => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn save_master_video(
+    video: MasterVideo,
+    expected_version: Option<i32>,
+) -> Result<MasterVideo> {
+    let mut tx = db::begin_transaction().await?;
+    let saved = save_master_video_tx(&mut tx, video, expected_version).await?;
+    tx.commit().await?;
+    Ok(saved)
+}
+
+pub async fn save_master_video_tx(
+    tx: &mut Transaction<'_, Postgres>,
+    video: MasterVideo,
+    expected_version: Option<i32>,
+) -> Result<MasterVideo> {
+    let before = if video.id == 0 {
+        None
+    } else {
+        existing(db::get_master_video_tx(tx, video.id).await)?.map(|r| MasterRecord::from(&r))
+    };
+    let saved = db::save_master_video(tx, video, expected_version).await?;
+    // The saved video is read back so the snapshot has the IDs of its timestamps.
+    let after = db::get_master_video_tx(tx, saved.id).await?;
+    record_change(
+        tx,
+        RecordEntity::Master,
+        saved.id,
+        before,
+        MasterRecord::from(&after),
+    )
+    .await?;
+    Ok(saved)
+}
+
+/// Sets the time of day for timestamps belonging to a master video.
+pub async fn save_times_of_day(master_id: i32, times: &[(i32, NaiveTime)]) -> Result<()> {
+    let mut tx = db::begin_transaction().await?;
+    let before = db::get_master_video_tx(&mut tx, master_id).await?;
+    db::save_times_of_day(&mut tx, times).await?;
+    let after = db::get_master_video_tx(&mut tx, master_id).await?;
+    record_change(
+        &mut tx,
+        RecordEntity::Master,
+        master_id,
+        Some(MasterRecord::from(&before)),
+        MasterRecord::from(&after),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn save_video(video: Video, expected_version: Option<i32>) -> Result<Video> {
+    let mut tx = db::begin_transaction().await?;
+    let saved = save_video_tx(&mut tx, video, expected_version).await?;
+    tx.commit().await?;
+    Ok(saved)
+}
+
+pub async fn save_video_tx(
+    tx: &mut Transaction<'_, Postgres>,
+    video: Video,
+    expected_version: Option<i32>,
+) -> Result<Video> {
+    let before = if video.id == 0 {
+        None
+    } else {
+        existing(db::get_video_tx(tx, video.id).await)?.map(|r| VideoRecord::from(&r))
+    };
+    let saved = db::save_video(tx, video, expected_version).await?;
+    record_change(
+        tx,
+        RecordEntity::Video,
+        saved.id,
+        before,
+        VideoRecord::from(&saved),
+    )
+    .await?;
+    Ok(saved)
+}
+
+pub async fn save_news_network(
+    network: NewsNetwork,
+    expected_version: Option<i32>,
+) -> Result<NewsNetwork> {
+    let mut tx = db::begin_transaction().await?;
+    let saved = save_news_network_tx(&mut tx, network, expected_version).await?;
+    tx.commit().await?;
+    Ok(saved)
+}
+
+pub async fn save_news_network_tx(
+    tx: &mut Transaction<'_, Postgres>,
+    network: NewsNetwork,
+    expected_version: Option<i32>,
+) -> Result<NewsNetwork> {
+    let before = if network.id == 0 {
+        None
+    } else {
+        existing(db::get_news_network_tx(tx, network.id).await)?.map(|r| NetworkRecord::from(&r))
+    };
+    let saved = db::save_news_network(tx, network, expected_version).await?;
+    record_change(
+        tx,
+        RecordEntity::Network,
+        saved.id,
+        before,
+        NetworkRecord::from(&saved),
+    )
+    .await?;
+    Ok(saved)
+}
+
+pub async fn save_news_affiliate(
+    affiliate: NewsAffiliate,
+    expected_version: Option<i32>,
+) -> Result<NewsAffiliate> {
+    let mut tx = db::begin_transaction().await?;
+    let saved = save_news_affiliate_tx(&mut tx, affiliate, expected_version).await?;
+    tx.commit().await?;
+    Ok(saved)
+}
+
+pub async fn save_news_affiliate_tx(
+    tx: &mut Transaction<'_, Postgres>,
+    affiliate: NewsAffiliate,
+    expected_version: Option<i32>,
+) -> Result<NewsAffiliate> {
+    let before = if affiliate.id == 0 {
+        None
+    } else {
+        existing(db::get_news_affiliate_tx(tx, affiliate.id).await)?
+            .map(|r| AffiliateRecord::from(&r))
+    };
+    let saved = db::save_news_affiliate(tx, affiliate, expected_version).await?;
+    record_change(
+        tx,
+        RecordEntity::Affiliate,
+        saved.id,
+        before,
+        AffiliateRecord::from(&saved),
+    )
+    .await?;
+    Ok(saved)
+}
+
+pub async fn save_news_broadcast(
+    broadcast: NewsBroadcast,
+    expected_version: Option<i32>,
+) -> Result<NewsBroadcast> {
+    let mut tx = db::begin_transaction().await?;
+    let saved = save_news_broadcast_tx(&mut tx, broadcast, expected_version).await?;
+    tx.commit().await?;
+    Ok(saved)
+}
+
+pub async fn save_news_broadcast_tx(
+    tx: &mut Transaction<'_, Postgres>,
+    broadcast: NewsBroadcast,
+    expected_version: Option<i32>,
+) -> Result<NewsBroadcast> {
+    let before = if broadcast.id == 0 {
+        None
+    } else {
+        existing(db::get_news_broadcast_tx(tx, broadcast.id).await)?
+            .map(|r| BroadcastRecord::from(&r))
+    };
+    let saved = db::save_news_broadcast(tx, broadcast, expected_version).await?;
+    record_change(
+        tx,
+        RecordEntity::Broadcast,
+        saved.id,
+        before,
+        BroadcastRecord::from(&saved),
+    )
+    .await?;
+    Ok(saved)
+}
+
+fn nist_tape_record(tape: &NistTape) -> NistTapeRecord {
+    let files: Vec<PathBuf> = tape.release_files.iter().map(|(p, _)| p.clone()).collect();
+    NistTapeRecord::new(tape.tape_id, &files)
+}
+
+pub async fn save_nist_tape_files(
+    tape_id: i32,
+    files: Vec<(PathBuf, u64)>,
+    expected_version: Option<i32>,
+) -> Result<NistTape> {
+    let mut tx = db::begin_transaction().await?;
+    let saved = save_nist_tape_files_tx(&mut tx, tape_id, files, expected_version).await?;
+    tx.commit().await?;
+    Ok(saved)
+}
+
+pub async fn save_nist_tape_files_tx(
+    tx: &mut Transaction<'_, Postgres>,
+    tape_id: i32,
+    files: Vec<(PathBuf, u64)>,
+    expected_version: Option<i32>,
+) -> Result<NistTape> {
+    let before = existing(db::get_nist_tape_tx(tx, tape_id).await)?.map(|r| nist_tape_record(&r));
+    let saved = db::save_nist_tape_files(tx, tape_id, files, expected_version).await?;
+    record_change(
+        tx,
+        RecordEntity::NistTape,
+        tape_id,
+        before,
+        nist_tape_record(&saved),
+    )
+    .await?;
+    Ok(saved)
+}
+
+/// Saves the release files for several tapes in a single transaction, so either every tape is
+/// saved or none of them are.
+pub async fn save_nist_tapes_files(tapes: &[(i32, Vec<(PathBuf, u64)>)]) -> Result<Vec<NistTape>> {
+    let mut tx = db::begin_transaction().await?;
+    let mut saved = Vec::new();
+    for (tape_id, files) in tapes.iter() {
+        saved.push(save_nist_tape_files_tx(&mut tx, *tape_id, files.clone(), None).await?);
+    }
+    tx.commit().await?;
+    Ok(saved)
+}
+
+fn nist_video_record(video: &NistVideo) -> NistVideoRecord {
+    NistVideoRecord::new(
+        video.video_id,
+        video.is_missing,
+        video.additional_notes.as_deref().unwrap_or_default(),
+    )
+}
+
+pub async fn save_nist_video(
+    video_id: i32,
+    is_missing: bool,
+    additional_notes: &str,
+    expected_version: Option<i32>,
+) -> Result<NistVideo> {
+    let mut tx = db::begin_transaction().await?;
+    let saved = save_nist_video_tx(
+        &mut tx,
+        video_id,
+        is_missing,
+        additional_notes,
+        expected_version,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(saved)
+}
+
+pub async fn save_nist_video_tx(
+    tx: &mut Transaction<'_, Postgres>,
+    video_id: i32,
+    is_missing: bool,
+    additional_notes: &str,
+    expected_version: Option<i32>,
+) -> Result<NistVideo> {
+    let before =
+        existing(db::get_nist_video_tx(tx, video_id).await)?.map(|r| nist_video_record(&r));
+    let saved =
+        db::save_nist_video(tx, video_id, is_missing, additional_notes, expected_version).await?;
+    record_change(
+        tx,
+        RecordEntity::NistVideo,
+        video_id,
+        before,
+        nist_video_record(&saved),
+    )
+    .await?;
+    Ok(saved)
+}
@@ -0,0 +1,326 @@
+use chrono::Duration;
+use clap::ValueEnum;
+use db::helpers::{duration_to_string, interval_to_duration};
+use db::models::EventTimestamp;
+
+/// How long the final chapter or cue lasts when the length of the video isn't known.
+const FINAL_CUE_LENGTH: i64 = 10;
+/// The shortest chapter YouTube will accept.
+const YOUTUBE_MIN_CHAPTER_LENGTH: i64 = 10;
+
+#[derive(Clone, Debug, ValueEnum)]
+pub enum ChapterFormat {
+    /// Metadata file for muxing chapters with ffmpeg
+    Ffmetadata,
+    /// SubRip subtitles
+    Srt,
+    /// WebVTT subtitles
+    Webvtt,
+    /// Chapter list for a YouTube description
+    Youtube,
+}
+
+struct Cue {
+    end: Duration,
+    start: Duration,
+    text: String,
+    time_of_day: Option<String>,
+}
+
+impl Cue {
+    fn title(&self) -> String {
+        match &self.time_of_day {
+            Some(time) => format!("[{time}] {}", self.text),
+            None => self.text.clone(),
+        }
+    }
+}
+
+/// Builds a cue for each offset, each running until the next one.
+///
+/// Timestamps at the same offset are merged into one cue, rather than producing a cue with no
+/// length.
+fn cues(timestamps: &[EventTimestamp], video_length: Option<Duration>) -> Vec<Cue> {
+    let mut sorted: Vec<&EventTimestamp> = timestamps.iter().collect();
+    sorted.sort_by_key(|t| interval_to_duration(&t.timestamp));
+    let mut cues: Vec<Cue> = Vec::new();
+    for timestamp in sorted.iter() {
+        let start = interval_to_duration(&timestamp.timestamp);
+        let text = timestamp.description.replace('\n', " ");
+        let time_of_day = timestamp.time_of_day.map(|t| t.format("%H%M").to_string());
+        if let Some(previous) = cues.last_mut().filter(|c| c.start == start) {
+            previous.text = format!("{} / {text}", previous.text);
+            previous.time_of_day = previous.time_of_day.take().or(time_of_day);
+            continue;
+        }
+        if let Some(previous) = cues.last_mut() {
+            previous.end = start;
+        }
+        cues.push(Cue {
+            end: start,
+            start,
+            text,
+            time_of_day,
+        });
+    }
+    if let Some(last) = cues.last_mut() {
+        last.end = video_length
+            .filter(|l| *l > last.start)
+            .unwrap_or(last.start + Duration::seconds(FINAL_CUE_LENGTH));
+    }
+    cues
+}
+
+/// The chapters for a YouTube description, which must start at zero.
+fn youtube_chapters(timestamps: &[EventTimestamp], video_length: Option<Duration>) -> Vec<Cue> {
+    let mut chapters = cues(timestamps, video_length);
+    if let Some(first) = chapters.first().filter(|c| !c.start.is_zero()) {
+        let start = Cue {
+            end: first.start,
+            start: Duration::zero(),
+            text: "Start".to_string(),
+            time_of_day: None,
+        };
+        chapters.insert(0, start);
+    }
+    chapters
+}
+
+/// Describes each YouTube chapter that is shorter than YouTube allows.
+///
+/// YouTube ignores the whole chapter list if any chapter is under ten seconds.
+pub fn short_youtube_chapters(
+    timestamps: &[EventTimestamp],
+    video_length: Option<Duration>,
+) -> Vec<String> {
+    youtube_chapters(timestamps, video_length)
+        .iter()
+        .filter(|c| c.end - c.start < Duration::seconds(YOUTUBE_MIN_CHAPTER_LENGTH))
+        .map(|c| {
+            format!(
+                "{} {} lasts {}s",
+                duration_to_string(&c.start),
+                c.title(),
+                (c.end - c.start).num_seconds()
+            )
+        })
+        .collect()
+}
+
+fn format_cue_time(d: &Duration, separator: char) -> String {
+    format!(
+        "{}{}{:03}",
+        duration_to_string(d),
+        separator,
+        d.num_milliseconds() % 1000
+    )
+}
+
+fn escape_ffmetadata(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn escape_webvtt(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Renders the timestamps of a master video as chapters or subtitles.
+///
+/// Each timestamp runs until the next one, and timestamps at the same offset share one. The final
+/// one runs until the end of the video, if its length is provided, or for ten seconds otherwise.
+/// The time of day, when there is one, is included with the description.
+///
+/// Returns the output along with the number of chapters or subtitles in it.
+pub fn export_chapters(
+    timestamps: &[EventTimestamp],
+    format: &ChapterFormat,
+    video_length: Option<Duration>,
+) -> (String, usize) {
+    let cues = match format {
+        ChapterFormat::Youtube => youtube_chapters(timestamps, video_length),
+        _ => cues(timestamps, video_length),
+    };
+    let mut output = String::new();
+    match format {
+        ChapterFormat::Ffmetadata => {
+            output.push_str(";FFMETADATA1\n");
+            for cue in cues.iter() {
+                output.push_str("\n[CHAPTER]\nTIMEBASE=1/1000\n");
+                output.push_str(&format!("START={}\n", cue.start.num_milliseconds()));
+                output.push_str(&format!("END={}\n", cue.end.num_milliseconds()));
+                output.push_str(&format!("title={}\n", escape_ffmetadata(&cue.title())));
+            }
+        }
+        ChapterFormat::Srt => {
+            for (i, cue) in cues.iter().enumerate() {
+                output.push_str(&format!(
+                    "{}\n{} --> {}\n",
+                    i + 1,
+                    format_cue_time(&cue.start, ','),
+                    format_cue_time(&cue.end, ',')
+                ));
+                if let Some(time) = &cue.time_of_day {
+                    output.push_str(&format!("{time}\n"));
+                }
+                output.push_str(&format!("{}\n\n", cue.text));
+            }
+        }
+        ChapterFormat::Webvtt => {
+            output.push_str("WEBVTT\n\n");
+            for cue in cues.iter() {
+                output.push_str(&format!(
+                    "{} --> {}\n",
+                    format_cue_time(&cue.start, '.'),
+                    format_cue_time(&cue.end, '.')
+                ));
+                if let Some(time) = &cue.time_of_day {
+                    output.push_str(&format!("{time}\n"));
+                }
+                output.push_str(&format!("{}\n\n", escape_webvtt(&cue.text)));
+            }
+        }
+        ChapterFormat::Youtube => {
+            for cue in cues.iter() {
+                output.push_str(&format!(
+                    "{} {}\n",
+                    duration_to_string(&cue.start),
+                    cue.title()
+                ));
+            }
+        }
+    }
+    (output, cues.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveTime;
+    use db::helpers::parse_duration;
+    use db::models::EventType;
+    use sqlx::postgres::types::PgInterval;
+
+    fn event_type(slug: &str) -> EventType {
+        EventType {
+            slug: slug.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn timestamps() -> Vec<EventTimestamp> {
+        vec![
+            EventTimestamp {
+                id: 2,
+                description: "UA175 hits the South Tower".to_string(),
+                timestamp: PgInterval::try_from(parse_duration("00:20:00")).unwrap(),
+                end_timestamp: None,
+                event_type: event_type("wtc2-impact"),
+                time_of_day: NaiveTime::from_hms_opt(9, 3, 0),
+            },
+            EventTimestamp {
+                id: 1,
+                description: "Coverage commences; at WTC1 & WTC2 = smoke".to_string(),
+                timestamp: PgInterval::try_from(parse_duration("00:08:05")).unwrap(),
+                end_timestamp: None,
+                event_type: event_type("normal"),
+                time_of_day: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn export_chapters_should_render_subtitles() {
+        let (srt, _) = export_chapters(&timestamps(), &ChapterFormat::Srt, None);
+        assert_eq!(
+            srt,
+            "1\n00:08:05,000 --> 00:20:00,000\nCoverage commences; at WTC1 & WTC2 = smoke\n\n\
+             2\n00:20:00,000 --> 00:20:10,000\n0903\nUA175 hits the South Tower\n\n"
+        );
+
+        let (vtt, _) = export_chapters(
+            &timestamps(),
+            &ChapterFormat::Webvtt,
+            Some(Duration::minutes(30)),
+        );
+        assert_eq!(
+            vtt,
+            "WEBVTT\n\n\
+             00:08:05.000 --> 00:20:00.000\nCoverage commences; at WTC1 &amp; WTC2 = smoke\n\n\
+             00:20:00.000 --> 00:30:00.000\n0903\nUA175 hits the South Tower\n\n"
+        );
+    }
+
+    #[test]
+    fn export_chapters_should_render_chapters() {
+        let (ffmetadata, _) = export_chapters(&timestamps(), &ChapterFormat::Ffmetadata, None);
+        assert_eq!(
+            ffmetadata,
+            ";FFMETADATA1\n\n\
+             [CHAPTER]\nTIMEBASE=1/1000\nSTART=485000\nEND=1200000\n\
+             title=Coverage commences\\; at WTC1 & WTC2 \\= smoke\n\n\
+             [CHAPTER]\nTIMEBASE=1/1000\nSTART=1200000\nEND=1210000\n\
+             title=[0903] UA175 hits the South Tower\n"
+        );
+
+        let (youtube, count) = export_chapters(&timestamps(), &ChapterFormat::Youtube, None);
+        assert_eq!(count, 3);
+        assert_eq!(
+            youtube,
+            "00:00:00 Start\n\
+             00:08:05 Coverage commences; at WTC1 & WTC2 = smoke\n\
+             00:20:00 [0903] UA175 hits the South Tower\n"
+        );
+    }
+
+    #[test]
+    fn export_chapters_should_merge_timestamps_at_the_same_offset() {
+        let mut timestamps = timestamps();
+        timestamps.push(EventTimestamp {
+            id: 3,
+            description: "Second plane seen on the horizon".to_string(),
+            timestamp: PgInterval::try_from(parse_duration("00:20:00")).unwrap(),
+            end_timestamp: None,
+            event_type: event_type("normal"),
+            time_of_day: None,
+        });
+        let (srt, count) = export_chapters(&timestamps, &ChapterFormat::Srt, None);
+        assert_eq!(count, 2);
+        assert_eq!(
+            srt,
+            "1\n00:08:05,000 --> 00:20:00,000\nCoverage commences; at WTC1 & WTC2 = smoke\n\n\
+             2\n00:20:00,000 --> 00:20:10,000\n0903\n\
+             UA175 hits the South Tower / Second plane seen on the horizon\n\n"
+        );
+    }
+
+    #[test]
+    fn short_youtube_chapters_should_find_chapters_under_ten_seconds() {
+        let mut timestamps = timestamps();
+        timestamps[1].timestamp = PgInterval::try_from(parse_duration("00:00:05")).unwrap();
+        timestamps.push(EventTimestamp {
+            id: 3,
+            description: "Replay".to_string(),
+            timestamp: PgInterval::try_from(parse_duration("00:20:04")).unwrap(),
+            end_timestamp: None,
+            event_type: event_type("normal"),
+            time_of_day: None,
+        });
+        let short = short_youtube_chapters(&timestamps, Some(Duration::minutes(30)));
+        assert_eq!(
+            short,
+            vec![
+                "00:00:00 Start lasts 5s".to_string(),
+                "00:20:00 [0903] UA175 hits the South Tower lasts 4s".to_string()
+            ]
+        );
+    }
+}
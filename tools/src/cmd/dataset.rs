@@ -0,0 +1,154 @@
+use crate::dataset::{Catalogue, Dataset};
+use crate::helpers::print_banner;
+use color_eyre::{eyre::eyre, Result};
+use colored::Colorize;
+use std::path::Path;
+
+/// Reads the curated part of the catalogue from the database.
+pub async fn load_catalogue() -> Result<Catalogue> {
+    Ok(Catalogue {
+        affiliates: db::get_news_affiliates(None).await?,
+        broadcasts: db::get_news_broadcasts().await?,
+        event_types: db::get_event_types().await?,
+        masters: db::get_master_videos().await?,
+        networks: db::get_news_networks(None).await?,
+        nist_identifiers: db::get_all_nist_identifiers().await?,
+        nist_network_mappings: db::get_nist_network_mappings().await?,
+        nist_tapes: db::get_nist_tapes()
+            .await?
+            .into_iter()
+            .map(|t| {
+                (
+                    t.tape_id,
+                    t.release_files.into_iter().map(|(path, _)| path).collect(),
+                )
+            })
+            .collect(),
+        nist_videos: db::get_nist_videos()
+            .await?
+            .into_iter()
+            .map(|v| {
+                (
+                    v.video_id,
+                    v.is_missing,
+                    v.additional_notes.unwrap_or_default(),
+                )
+            })
+            .collect(),
+        people: db::get_people().await?,
+        videos: db::get_videos().await?,
+    })
+}
+
+pub async fn export(dir: &Path) -> Result<()> {
+    let catalogue = load_catalogue().await?;
+    let count = Dataset::from(&catalogue).write(dir)?;
+    println!("Exported {count} records to {}", dir.to_string_lossy());
+    Ok(())
+}
+
+pub async fn import(dir: &Path) -> Result<()> {
+    let dataset = Dataset::read(dir)?;
+    let resolved = dataset.resolve();
+    let mut problems = resolved.as_ref().err().cloned().unwrap_or_default();
+
+    // The NIST tapes, videos and release files are not part of the dataset, so they must already
+    // be in the database, along with the NIST videos the masters refer to.
+    let tapes = db::get_nist_tapes().await?;
+    for record in dataset.nist_tapes.iter() {
+        if !tapes.iter().any(|t| t.tape_id == record.tape_id) {
+            problems.push(format!("NIST tape {} does not exist", record.tape_id));
+        }
+    }
+    let videos = db::get_nist_videos().await?;
+    for record in dataset.nist_videos.iter() {
+        if !videos.iter().any(|v| v.video_id == record.video_id) {
+            problems.push(format!("NIST video {} does not exist", record.video_id));
+        }
+    }
+    for record in dataset.masters.iter() {
+        for id in record.nist_video_ids.iter() {
+            if !videos.iter().any(|v| v.video_id == *id) {
+                problems.push(format!(
+                    "Master video {} refers to NIST video {id}, which does not exist",
+                    record.id
+                ));
+            }
+        }
+    }
+    for path in db::find_missing_release_files(&dataset.release_file_paths()).await? {
+        problems.push(format!("Release file {path} does not exist"));
+    }
+
+    let catalogue = match resolved {
+        Ok(catalogue) if problems.is_empty() => catalogue,
+        _ => {
+            print_banner("Problems");
+            for problem in problems.iter() {
+                println!("{}", problem.red());
+            }
+            return Err(eyre!(
+                "The dataset has {} problems and was not imported",
+                problems.len()
+            ));
+        }
+    };
+
+    // Everything is saved in one transaction, so a record the database rejects part way through
+    // leaves the catalogue as it was rather than half imported.
+    let mut tx = db::begin_transaction().await?;
+    for event_type in catalogue.event_types.into_iter() {
+        db::save_event_type(&mut tx, event_type).await?;
+    }
+    for network in catalogue.networks.into_iter() {
+        crate::audit::save_news_network_tx(&mut tx, network, None).await?;
+    }
+    for affiliate in catalogue.affiliates.into_iter() {
+        crate::audit::save_news_affiliate_tx(&mut tx, affiliate, None).await?;
+    }
+    for broadcast in catalogue.broadcasts.into_iter() {
+        crate::audit::save_news_broadcast_tx(&mut tx, broadcast, None).await?;
+    }
+    for person in catalogue.people.into_iter() {
+        db::save_person(&mut tx, person).await?;
+    }
+    for master in catalogue.masters.into_iter() {
+        crate::audit::save_master_video_tx(&mut tx, master, None).await?;
+    }
+    for video in catalogue.videos.into_iter() {
+        crate::audit::save_video_tx(&mut tx, video, None).await?;
+    }
+    for (id, files) in catalogue.nist_tapes.into_iter() {
+        let files = files.into_iter().map(|f| (f, 0)).collect();
+        crate::audit::save_nist_tape_files_tx(&mut tx, id, files, None).await?;
+    }
+    for (id, is_missing, additional_notes) in catalogue.nist_videos.iter() {
+        crate::audit::save_nist_video_tx(&mut tx, *id, *is_missing, additional_notes, None).await?;
+    }
+    for identifier in catalogue.nist_identifiers.iter() {
+        db::save_nist_identifier(&mut tx, identifier).await?;
+    }
+    for mapping in catalogue.nist_network_mappings.iter() {
+        db::save_nist_network_mapping(&mut tx, mapping).await?;
+    }
+    db::reset_id_sequences(&mut tx).await?;
+    tx.commit().await?;
+
+    println!(
+        "Imported {} event types, {} networks, {} affiliates, {} broadcasts, {} people, \
+         {} master videos, {} videos, {} NIST tapes, {} NIST videos, {} NIST identifiers and \
+         {} NIST network mappings",
+        dataset.event_types.len(),
+        dataset.networks.len(),
+        dataset.affiliates.len(),
+        dataset.broadcasts.len(),
+        dataset.people.len(),
+        dataset.masters.len(),
+        dataset.videos.len(),
+        dataset.nist_tapes.len(),
+        dataset.nist_videos.len(),
+        dataset.nist_identifiers.len(),
+        dataset.nist_network_mappings.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use chrono::NaiveTime;
+use color_eyre::{eyre::eyre, Result};
+use colored::Colorize;
+use db::models::EventType;
+
+fn print_event_type(event_type: &EventType) {
+    match event_type.canonical_time {
+        Some(time) => println!(
+            "{}: {} (at {})",
+            event_type.slug.bold(),
+            event_type.label,
+            time.format("%H:%M:%S")
+        ),
+        None => println!("{}: {}", event_type.slug.bold(), event_type.label),
+    }
+    if !event_type.description.is_empty() {
+        println!("    {}", event_type.description);
+    }
+}
+
+pub async fn ls() -> Result<()> {
+    for event_type in db::get_event_types().await?.iter() {
+        print_event_type(event_type);
+    }
+    Ok(())
+}
+
+pub async fn add(
+    slug: String,
+    label: String,
+    description: Option<String>,
+    canonical_time: Option<NaiveTime>,
+) -> Result<()> {
+    if !EventType::is_valid_slug(&slug) {
+        return Err(eyre!(
+            "'{slug}' must only contain lowercase letters, numbers and hyphens"
+        ));
+    }
+    let event_types = db::get_event_types().await?;
+    if event_types.iter().any(|t| t.slug == slug) {
+        return Err(eyre!("The '{slug}' event type already exists"));
+    }
+
+    let mut tx = db::begin_transaction().await?;
+    let event_type = db::save_event_type(
+        &mut tx,
+        EventType {
+            slug,
+            label,
+            description: description.unwrap_or_default(),
+            canonical_time,
+        },
+    )
+    .await?;
+    tx.commit().await?;
+    println!("Added event type");
+    print_event_type(&event_type);
+    Ok(())
+}
+
+pub async fn edit(
+    slug: String,
+    label: Option<String>,
+    description: Option<String>,
+    canonical_time: Option<Option<NaiveTime>>,
+) -> Result<()> {
+    let mut event_type = EventType::find(&slug, &db::get_event_types().await?)?;
+    if let Some(label) = label {
+        event_type.label = label;
+    }
+    if let Some(description) = description {
+        event_type.description = description;
+    }
+    if let Some(canonical_time) = canonical_time {
+        event_type.canonical_time = canonical_time;
+    }
+
+    let mut tx = db::begin_transaction().await?;
+    let event_type = db::save_event_type(&mut tx, event_type).await?;
+    tx.commit().await?;
+    println!("Saved event type");
+    print_event_type(&event_type);
+    Ok(())
+}
+
+pub async fn rm(slug: String) -> Result<()> {
+    db::delete_event_type(&slug).await?;
+    println!("Removed the '{slug}' event type");
+    Ok(())
+}
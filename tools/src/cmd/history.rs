@@ -0,0 +1,185 @@
+use crate::audit::entity_name;
+use crate::dataset::{
+    AffiliateRecord, BroadcastRecord, Catalogue, Dataset, DatasetRecord, MasterRecord,
+    NetworkRecord, NistTapeRecord, NistVideoRecord, VideoRecord,
+};
+use crate::diff::{diff_lines, print_diff};
+use crate::editing::records::RecordEntity;
+use color_eyre::{eyre::eyre, Result};
+use colored::Colorize;
+use std::io::Write;
+
+pub async fn history(entity: RecordEntity, id: u32, restore: Option<u32>) -> Result<()> {
+    match entity {
+        RecordEntity::Affiliate => history_of::<AffiliateRecord>(entity, id, restore).await,
+        RecordEntity::Broadcast => history_of::<BroadcastRecord>(entity, id, restore).await,
+        RecordEntity::Master => history_of::<MasterRecord>(entity, id, restore).await,
+        RecordEntity::Network => history_of::<NetworkRecord>(entity, id, restore).await,
+        RecordEntity::NistTape => history_of::<NistTapeRecord>(entity, id, restore).await,
+        RecordEntity::NistVideo => history_of::<NistVideoRecord>(entity, id, restore).await,
+        RecordEntity::Video => history_of::<VideoRecord>(entity, id, restore).await,
+    }
+}
+
+/// Converts a snapshot from the audit log to YAML.
+///
+/// The snapshot is read into its record first because JSONB doesn't keep the order of the keys.
+fn snapshot_to_yaml<T: DatasetRecord>(snapshot: &str) -> Result<String> {
+    let record: T = serde_json::from_str(snapshot)?;
+    Ok(serde_yaml::to_string(&record)?)
+}
+
+async fn history_of<T: DatasetRecord>(
+    entity: RecordEntity,
+    id: u32,
+    restore: Option<u32>,
+) -> Result<()> {
+    let name = entity_name(entity);
+    let entries = db::get_audit_entries(&name, id as i32).await?;
+    if entries.is_empty() {
+        println!("No changes have been recorded for {name} {id}");
+        return Ok(());
+    }
+
+    if let Some(revision) = restore {
+        let entry = entries
+            .iter()
+            .find(|e| e.id == revision as i32)
+            .ok_or_else(|| eyre!("There is no revision {revision} of {name} {id}"))?;
+        let record: T = serde_json::from_str(&entry.after)?;
+        return restore_record(entity, record).await;
+    }
+
+    for entry in entries.iter() {
+        let before = match &entry.before {
+            Some(before) => snapshot_to_yaml::<T>(before)?,
+            None => String::new(),
+        };
+        let after = snapshot_to_yaml::<T>(&entry.after)?;
+        let action = if entry.before.is_none() {
+            "added"
+        } else {
+            "changed"
+        };
+        println!(
+            "{}",
+            format!(
+                "Revision {}: {action} by {} at {}",
+                entry.id,
+                entry.changed_by,
+                entry.changed_at.format("%Y-%m-%d %H:%M:%S")
+            )
+            .bold()
+        );
+        print_diff(&diff_lines(&before, &after), 2);
+        println!();
+    }
+    Ok(())
+}
+
+/// Restores a record to an earlier revision.
+///
+/// The revision is checked against the rest of the catalogue the same way as a dataset import, so
+/// a revision that refers to records that no longer exist can't be restored.
+async fn restore_record<T: DatasetRecord>(entity: RecordEntity, record: T) -> Result<()> {
+    let id = record.id();
+    let mut dataset = Dataset::from(&crate::cmd::dataset::load_catalogue().await?);
+    let current = match T::records(&mut dataset).iter().find(|r| r.id() == id) {
+        Some(current) => serde_yaml::to_string(current)?,
+        None => String::new(),
+    };
+    let restored = serde_yaml::to_string(&record)?;
+    if current == restored {
+        println!("The record already matches this revision");
+        return Ok(());
+    }
+
+    dataset.replace(record);
+    let catalogue = dataset.resolve().map_err(|problems| {
+        for problem in problems.iter() {
+            println!("{}", problem.red());
+        }
+        eyre!("The revision can't be restored")
+    })?;
+
+    print_diff(&diff_lines(&current, &restored), 2);
+    println!("Proceed? [y/n]");
+    std::io::stdout().flush()?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    if input.trim().to_lowercase() != "y" {
+        return Ok(());
+    }
+
+    save_from_catalogue(entity, id, catalogue).await?;
+    println!("Restored {} {id}", entity_name(entity));
+    Ok(())
+}
+
+async fn save_from_catalogue(entity: RecordEntity, id: i32, catalogue: Catalogue) -> Result<()> {
+    let missing = || eyre!("Could not find {} {id}", entity_name(entity));
+    match entity {
+        RecordEntity::Affiliate => {
+            let affiliate = catalogue
+                .affiliates
+                .into_iter()
+                .find(|a| a.id == id)
+                .ok_or_else(missing)?;
+            crate::audit::save_news_affiliate(affiliate, None).await?;
+        }
+        RecordEntity::Broadcast => {
+            let broadcast = catalogue
+                .broadcasts
+                .into_iter()
+                .find(|b| b.id == id)
+                .ok_or_else(missing)?;
+            crate::audit::save_news_broadcast(broadcast, None).await?;
+        }
+        RecordEntity::Master => {
+            let master = catalogue
+                .masters
+                .into_iter()
+                .find(|m| m.id == id)
+                .ok_or_else(missing)?;
+            crate::audit::save_master_video(master, None).await?;
+        }
+        RecordEntity::Network => {
+            let network = catalogue
+                .networks
+                .into_iter()
+                .find(|n| n.id == id)
+                .ok_or_else(missing)?;
+            crate::audit::save_news_network(network, None).await?;
+        }
+        RecordEntity::NistTape => {
+            let (_, files) = catalogue
+                .nist_tapes
+                .into_iter()
+                .find(|(tape_id, _)| *tape_id == id)
+                .ok_or_else(missing)?;
+            crate::audit::save_nist_tape_files(
+                id,
+                files.into_iter().map(|f| (f, 0)).collect(),
+                None,
+            )
+            .await?;
+        }
+        RecordEntity::NistVideo => {
+            let (_, is_missing, additional_notes) = catalogue
+                .nist_videos
+                .into_iter()
+                .find(|(video_id, _, _)| *video_id == id)
+                .ok_or_else(missing)?;
+            crate::audit::save_nist_video(id, is_missing, &additional_notes, None).await?;
+        }
+        RecordEntity::Video => {
+            let video = catalogue
+                .videos
+                .into_iter()
+                .find(|v| v.id == id)
+                .ok_or_else(missing)?;
+            crate::audit::save_video(video, None).await?;
+        }
+    }
+    Ok(())
+}
@@ -1,46 +1,111 @@
+use crate::chapters::ChapterFormat;
+use crate::dataset::CATEGORIES;
 use crate::editing::forms::Form;
+use crate::editing::masters::SimilarNames;
+use crate::editing::prompts::prompt_for_choices;
+use crate::editing::records::RecordEntity;
+use crate::editing::schema::ChoicesSource;
+use crate::helpers::print_banner;
+use crate::times::{check_timestamps, Anchor, SyncStatus};
+use chrono::{Duration, NaiveTime};
 use color_eyre::{eyre::eyre, Result};
-use db::models::MasterVideo;
-use dialoguer::Editor;
-use std::path::PathBuf;
+use colored::Colorize;
+use db::helpers::{duration_to_string, interval_to_duration, parse_duration};
+use db::models::{EventTimestamp, EventType, MasterVideo, NewsBroadcast, NistVideo, Person};
+use sqlx::postgres::types::PgInterval;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
-pub async fn add(path: Option<PathBuf>) -> Result<()> {
+/// The choices for the fields of a master video form when prompting for them.
+///
+/// People who already have the role of a field are listed before everyone else.
+fn master_video_choices(
+    source: ChoicesSource,
+    news_broadcasts: &[NewsBroadcast],
+    nist_videos: &[NistVideo],
+    people: &[Person],
+) -> Vec<String> {
+    match source {
+        ChoicesSource::Categories => CATEGORIES.iter().map(|c| c.to_string()).collect(),
+        ChoicesSource::NewsBroadcasts => news_broadcasts.iter().map(|b| b.to_string()).collect(),
+        ChoicesSource::NistVideos => nist_videos.iter().map(|v| v.to_string()).collect(),
+        ChoicesSource::People(person_type) => {
+            let (with_role, others): (Vec<&Person>, Vec<&Person>) =
+                people.iter().partition(|p| p.types.contains(&person_type));
+            with_role
+                .into_iter()
+                .chain(others)
+                .map(|p| p.name.clone())
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+pub async fn add(path: Option<PathBuf>, prompt: bool, nist_video_id: Option<i32>) -> Result<()> {
     let news_broadcasts = db::get_news_broadcasts().await?;
+    let nist_videos = db::get_nist_videos().await?;
     let people = db::get_people().await?;
+    let event_types = db::get_event_types().await?;
     let video = if let Some(path) = path {
         let completed_form = std::fs::read_to_string(path)?;
         let form = Form::from_master_video_str(&completed_form)?;
-        crate::editing::masters::master_video_from_form(0, &form, &news_broadcasts, &people)?
+        crate::editing::masters::master_video_from_form(
+            0,
+            &form,
+            &news_broadcasts,
+            &nist_videos,
+            &people,
+            &event_types,
+            SimilarNames::Reject,
+        )?
     } else {
         let mut form = Form::from(&MasterVideo::default());
-        form.add_choices(
-            "News Broadcasts",
-            news_broadcasts.iter().map(|b| b.to_string()).collect(),
-        )?;
-        match Editor::new().edit(&form.as_string()) {
-            Ok(completed_form) => {
-                if let Some(cf) = completed_form {
-                    let form = Form::from_master_video_str(&cf)?;
-                    crate::editing::masters::master_video_from_form(
-                        0,
-                        &form,
-                        &news_broadcasts,
-                        &people,
-                    )?
-                } else {
-                    return Err(eyre!(
-                        "An unknown error occurred when editing the master video"
-                    ));
-                }
-            }
-            Err(_) => {
+        if let Some(id) = nist_video_id {
+            let video = nist_videos
+                .iter()
+                .find(|v| v.video_id == id)
+                .ok_or_else(|| eyre!("Could not find NIST video with ID {id}"))?;
+            let mappings = db::get_nist_network_mappings().await?;
+            crate::editing::masters::prefill_from_nist_video(
+                &mut form,
+                video,
+                &mappings,
+                &news_broadcasts,
+            )?;
+        }
+        if prompt {
+            prompt_for_choices(&mut form, |source| {
+                master_video_choices(source, &news_broadcasts, &nist_videos, &people)
+            })?;
+        } else {
+            form.populate_choices(
+                ChoicesSource::NewsBroadcasts,
+                news_broadcasts.iter().map(|b| b.to_string()).collect(),
+            )?;
+        }
+        let video = crate::editing::edit_form(&form, |cf| {
+            let form = Form::from_master_video_str(cf)?;
+            crate::editing::masters::master_video_from_form(
+                0,
+                &form,
+                &news_broadcasts,
+                &nist_videos,
+                &people,
+                &event_types,
+                SimilarNames::Confirm,
+            )
+        })?;
+        match video {
+            Some(video) => video,
+            None => {
                 println!("New record will not be added to the database");
                 return Ok(());
             }
         }
     };
 
-    let updated = db::save_master_video(video).await?;
+    let updated = crate::audit::save_master_video(video, None).await?;
     println!("==================");
     println!("Saved master video");
     println!("==================");
@@ -49,35 +114,47 @@ pub async fn add(path: Option<PathBuf>) -> Result<()> {
     Ok(())
 }
 
-pub async fn edit(id: u32) -> Result<()> {
+pub async fn edit(id: u32, prompt: bool) -> Result<()> {
     let news_broadcasts = db::get_news_broadcasts().await?;
+    let nist_videos = db::get_nist_videos().await?;
     let people = db::get_people().await?;
+    let event_types = db::get_event_types().await?;
     let master_video = db::get_master_video(id as i32, None).await?;
+    let video_length = video_length(master_video.id).await?;
 
-    let form = Form::from(&master_video);
-    let edited_master = match Editor::new().edit(&form.as_string()) {
-        Ok(completed_form) => {
-            if let Some(cf) = completed_form {
-                let form = Form::from_master_video_str(&cf)?;
-                crate::editing::masters::master_video_from_form(
-                    master_video.id,
-                    &form,
-                    &news_broadcasts,
-                    &people,
-                )?
-            } else {
-                println!("New record will not be added to the database");
-                return Ok(());
+    let choices = |source: ChoicesSource| {
+        master_video_choices(source, &news_broadcasts, &nist_videos, &people)
+    };
+    let updated = crate::editing::edit_record_form(
+        RecordEntity::Master,
+        master_video.id,
+        Form::from(&master_video),
+        prompt.then_some(&choices as _),
+        || async { Ok(Form::from(&db::get_master_video(id as i32, None).await?)) },
+        |cf| {
+            let form = Form::from_master_video_str(cf)?;
+            let master = crate::editing::masters::master_video_from_form(
+                master_video.id,
+                &form,
+                &news_broadcasts,
+                &nist_videos,
+                &people,
+                &event_types,
+                SimilarNames::Confirm,
+            )?;
+            if let Some(length) = video_length {
+                crate::editing::masters::check_video_length(&form, &master, length)?;
             }
-        }
-        Err(_) => {
-            return Err(eyre!(
-                "An unknown error occurred when editing the master video"
-            ));
-        }
+            Ok(master)
+        },
+        |master, version| crate::audit::save_master_video(master, Some(version)),
+    )
+    .await?;
+    let Some(updated) = updated else {
+        println!("Changes to the master video will not be saved");
+        return Ok(());
     };
 
-    let updated = db::save_master_video(edited_master).await?;
     println!("==================");
     println!("Saved master video");
     println!("==================");
@@ -111,5 +188,324 @@ pub async fn ls() -> Result<()> {
 pub async fn print(id: u32) -> Result<()> {
     let master_video = db::get_master_video(id as i32, None).await?;
     master_video.print();
+    if master_video.nist_videos.is_empty() {
+        return Ok(());
+    }
+
+    // NIST's own records for the video, with their notes, to read alongside ours.
+    let tapes = db::get_nist_tapes().await?;
+    for video in master_video.nist_videos.iter() {
+        println!();
+        print_banner(&format!("NIST Video {}", video.video_id));
+        video.print();
+        println!("Tapes:");
+        for tape in tapes.iter().filter(|t| t.video.video_id == video.video_id) {
+            tape.print_row(false)?;
+        }
+    }
+    Ok(())
+}
+
+pub async fn sync_times(
+    id: u32,
+    anchors: Vec<Anchor>,
+    use_key_events: bool,
+    tolerance: u32,
+    dry_run: bool,
+) -> Result<()> {
+    let master_video = db::get_master_video(id as i32, None).await?;
+    let result = crate::times::sync_times(
+        &master_video.timestamps,
+        &anchors,
+        use_key_events,
+        Duration::minutes(tolerance as i64),
+    )?;
+
+    print_banner(&master_video.title);
+    let mut gaps = result.gaps.iter().peekable();
+    for timestamp in result.timestamps.iter() {
+        while let Some(gap) = gaps.next_if(|g| g.offset <= timestamp.offset) {
+            println!(
+                "{}",
+                format!(
+                    "-- break in footage before {} ({} missing)",
+                    duration_to_string(&gap.offset),
+                    duration_to_string(&gap.length)
+                )
+                .yellow()
+            );
+        }
+        let time = timestamp
+            .time_of_day
+            .map(|t| t.format("%H%M").to_string())
+            .unwrap_or("----".to_string());
+        let line = format!(
+            "{} [{}] {} [{}]",
+            duration_to_string(&timestamp.offset),
+            time,
+            timestamp.description,
+            timestamp.event_type
+        );
+        match &timestamp.status {
+            SyncStatus::Anchor => println!("{} (anchor)", line.bold()),
+            SyncStatus::Derived { .. } => {
+                if timestamp.is_changed() {
+                    println!("{}", line.green());
+                } else {
+                    println!("{line}");
+                }
+            }
+            SyncStatus::Inconsistent { expected } => println!(
+                "{} (inconsistent: expected {})",
+                line.red(),
+                expected.format("%H%M")
+            ),
+        }
+    }
+
+    let inconsistent = result
+        .timestamps
+        .iter()
+        .filter(|t| matches!(t.status, SyncStatus::Inconsistent { .. }))
+        .count();
+    if inconsistent > 0 {
+        println!(
+            "{inconsistent} timestamps are inconsistent with the anchors and were not changed"
+        );
+    }
+    let times: Vec<(i32, NaiveTime)> = result
+        .timestamps
+        .iter()
+        .filter(|t| t.is_changed())
+        .filter_map(|t| t.time_of_day.map(|time| (t.id, time)))
+        .collect();
+    if times.is_empty() {
+        println!("No timestamps need to be updated");
+        return Ok(());
+    }
+    if dry_run {
+        println!("{} timestamps would be updated", times.len());
+        return Ok(());
+    }
+
+    println!("About to update {} timestamps", times.len());
+    println!("Proceed? [y/n]");
+    std::io::stdout().flush()?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    if input.trim().to_lowercase() != "y" {
+        return Ok(());
+    }
+    crate::audit::save_times_of_day(master_video.id, &times).await?;
+    println!("Updated {} timestamps", times.len());
+    Ok(())
+}
+
+pub async fn audit_times(id: Option<u32>, tolerance: u32) -> Result<()> {
+    let masters = if let Some(id) = id {
+        vec![db::get_master_video(id as i32, None).await?]
+    } else {
+        db::get_master_videos().await?
+    };
+
+    let mut flagged = 0;
+    for master in masters.iter() {
+        let issues =
+            crate::times::audit_times(&master.timestamps, Duration::minutes(tolerance as i64));
+        if issues.is_empty() {
+            continue;
+        }
+        flagged += 1;
+        println!("{}", format!("{}: {}", master.id, master.title).bold());
+        for issue in issues.iter() {
+            println!("    {}", issue.to_string().red());
+        }
+    }
+    println!(
+        "{} of {} master videos have key events inconsistent with the canonical times",
+        flagged,
+        masters.len()
+    );
+    Ok(())
+}
+
+pub async fn export_chapters(
+    id: u32,
+    format: ChapterFormat,
+    out_path: Option<&Path>,
+    video_length: Option<String>,
+) -> Result<()> {
+    let master_video = db::get_master_video(id as i32, None).await?;
+    let video_length = match video_length {
+        Some(length) => {
+            let duration = parse_duration(&length);
+            if duration.is_zero() {
+                return Err(eyre!("'{length}' is not a valid video length"));
+            }
+            Some(duration)
+        }
+        None => None,
+    };
+    if let ChapterFormat::Youtube = format {
+        for chapter in
+            crate::chapters::short_youtube_chapters(&master_video.timestamps, video_length).iter()
+        {
+            eprintln!(
+                "{} {chapter}; YouTube requires chapters of at least 10 seconds",
+                "Warning:".yellow()
+            );
+        }
+    }
+    let (output, count) =
+        crate::chapters::export_chapters(&master_video.timestamps, &format, video_length);
+    if let Some(path) = out_path {
+        std::fs::write(path, output)?;
+        println!("Exported {count} chapters to {}", path.to_string_lossy());
+    } else {
+        print!("{output}");
+    }
+    Ok(())
+}
+
+/// Changes to make to a timestamp. Fields that are `None` are left as they are.
+#[derive(Debug, Default)]
+pub struct TimestampChanges {
+    pub description: Option<String>,
+    pub end: Option<Option<Duration>>,
+    pub event_type: Option<String>,
+    pub offset: Option<Duration>,
+    pub time_of_day: Option<Option<NaiveTime>>,
+}
+
+/// The length of a master video, which is the duration of its longest video.
+///
+/// It's not known if the master doesn't have any videos.
+async fn video_length(master_id: i32) -> Result<Option<Duration>> {
+    let videos = db::get_videos_for_master(master_id).await?;
+    Ok(videos
+        .iter()
+        .map(|v| interval_to_duration(&v.duration))
+        .max())
+}
+
+fn to_interval(duration: Duration) -> Result<PgInterval> {
+    PgInterval::try_from(duration).map_err(|e| eyre!("Could not convert offset: {e}"))
+}
+
+/// Sorts the timestamps of a master video and saves them if they are valid.
+async fn save_timestamps(mut master: MasterVideo) -> Result<MasterVideo> {
+    master
+        .timestamps
+        .sort_by_key(|t| interval_to_duration(&t.timestamp));
+    let issues = check_timestamps(&master.timestamps, video_length(master.id).await?);
+    if !issues.is_empty() {
+        for issue in issues.iter() {
+            let timestamp = &master.timestamps[issue.index()];
+            println!("{}", format!("{timestamp}: {issue}").red());
+        }
+        return Err(eyre!("The timestamps were not saved"));
+    }
+    crate::audit::save_master_video(master, None).await
+}
+
+pub async fn timestamps_ls(id: u32) -> Result<()> {
+    let master = db::get_master_video(id as i32, None).await?;
+    print_banner(&master.title);
+    if master.timestamps.is_empty() {
+        println!("The master video has no timestamps");
+        return Ok(());
+    }
+    for timestamp in master.timestamps.iter() {
+        println!("{:>5}: {timestamp}", timestamp.id);
+    }
+    for issue in check_timestamps(&master.timestamps, video_length(master.id).await?).iter() {
+        let timestamp = &master.timestamps[issue.index()];
+        println!("{}", format!("{}: {issue}", timestamp.id).red());
+    }
+    Ok(())
+}
+
+pub async fn timestamps_add(
+    id: u32,
+    offset: Duration,
+    end: Option<Duration>,
+    event_type: String,
+    time_of_day: Option<NaiveTime>,
+    description: String,
+) -> Result<()> {
+    let event_type = EventType::find(&event_type, &db::get_event_types().await?)?;
+    let mut master = db::get_master_video(id as i32, None).await?;
+    let existing: Vec<i32> = master.timestamps.iter().map(|t| t.id).collect();
+    master.timestamps.push(EventTimestamp {
+        id: 0,
+        description,
+        timestamp: to_interval(offset)?,
+        end_timestamp: end.map(to_interval).transpose()?,
+        event_type,
+        time_of_day,
+    });
+
+    let saved = save_timestamps(master).await?;
+    if let Some(timestamp) = saved.timestamps.iter().find(|t| !existing.contains(&t.id)) {
+        println!("Added timestamp {}: {timestamp}", timestamp.id);
+    }
+    Ok(())
+}
+
+pub async fn timestamps_edit(id: u32, timestamp_id: u32, changes: TimestampChanges) -> Result<()> {
+    let mut master = db::get_master_video(id as i32, None).await?;
+    let timestamp = master
+        .timestamps
+        .iter_mut()
+        .find(|t| t.id == timestamp_id as i32)
+        .ok_or_else(|| eyre!("Master video {id} has no timestamp {timestamp_id}"))?;
+    if let Some(description) = changes.description {
+        timestamp.description = description;
+    }
+    if let Some(end) = changes.end {
+        timestamp.end_timestamp = end.map(to_interval).transpose()?;
+    }
+    if let Some(event_type) = changes.event_type {
+        timestamp.event_type = EventType::find(&event_type, &db::get_event_types().await?)?;
+    }
+    if let Some(offset) = changes.offset {
+        timestamp.timestamp = to_interval(offset)?;
+    }
+    if let Some(time_of_day) = changes.time_of_day {
+        timestamp.time_of_day = time_of_day;
+    }
+
+    let saved = save_timestamps(master).await?;
+    if let Some(timestamp) = saved
+        .timestamps
+        .iter()
+        .find(|t| t.id == timestamp_id as i32)
+    {
+        println!("Saved timestamp {}: {timestamp}", timestamp.id);
+    }
+    Ok(())
+}
+
+pub async fn timestamps_rm(id: u32, timestamp_id: u32) -> Result<()> {
+    let mut master = db::get_master_video(id as i32, None).await?;
+    let index = master
+        .timestamps
+        .iter()
+        .position(|t| t.id == timestamp_id as i32)
+        .ok_or_else(|| eyre!("Master video {id} has no timestamp {timestamp_id}"))?;
+
+    println!("About to remove timestamp {timestamp_id}:");
+    println!("{}", master.timestamps[index]);
+    println!("Proceed? [y/n]");
+    std::io::stdout().flush()?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    if input.trim().to_lowercase() != "y" {
+        return Ok(());
+    }
+
+    master.timestamps.remove(index);
+    crate::audit::save_master_video(master, None).await?;
+    println!("Removed timestamp {timestamp_id}");
     Ok(())
 }
@@ -1,10 +1,18 @@
 pub mod cumulus;
+pub mod dataset;
+pub mod event_types;
+pub mod history;
 pub mod master_videos;
 pub mod news_affiliates;
 pub mod news_broadcasts;
 pub mod news_networks;
 pub mod nist_import;
+pub mod nist_status;
 pub mod nist_tapes;
 pub mod nist_videos;
+pub mod records;
 pub mod releases;
+pub mod search;
+pub mod timeline;
 pub mod videos;
+pub mod youtube;
@@ -1,34 +1,50 @@
 use crate::editing::forms::Form;
-use color_eyre::{eyre::eyre, Result};
-use db::models::NewsAffiliate;
-use dialoguer::Editor;
+use crate::editing::prompts::prompt_for_choices;
+use crate::editing::records::RecordEntity;
+use crate::editing::schema::ChoicesSource;
+use color_eyre::Result;
+use db::models::{NewsAffiliate, NewsNetwork};
 use std::path::PathBuf;
 
-pub async fn add(path: Option<PathBuf>) -> Result<()> {
+/// The choices for the fields of a news affiliate form when prompting for them.
+fn news_affiliate_choices(source: ChoicesSource, networks: &[NewsNetwork]) -> Vec<String> {
+    match source {
+        ChoicesSource::NewsNetworks => networks.iter().map(|n| n.name.clone()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+pub async fn add(path: Option<PathBuf>, prompt: bool) -> Result<()> {
     let networks = db::get_news_networks(None).await?;
     let affiliate = if let Some(path) = path {
         let completed_form = std::fs::read_to_string(path)?;
         let form = Form::from_news_affiliate_str(&completed_form)?;
         crate::editing::news::news_affiliate_from_form(0, &form, &networks)?
     } else {
-        let form = Form::from(&NewsAffiliate::default());
-        match Editor::new().edit(&form.as_string()) {
-            Ok(completed_form) => {
-                if let Some(cf) = completed_form {
-                    let form = Form::from_news_affiliate_str(&cf)?;
-                    crate::editing::news::news_affiliate_from_form(0, &form, &networks)?
-                } else {
-                    println!("New record will not be added to the database");
-                    return Ok(());
-                }
-            }
-            Err(_) => {
-                return Err(eyre!("An unknown error occurred when editing the video"));
+        let mut form = Form::from(&NewsAffiliate::default());
+        if prompt {
+            prompt_for_choices(&mut form, |source| {
+                news_affiliate_choices(source, &networks)
+            })?;
+        } else {
+            form.populate_choices(
+                ChoicesSource::NewsNetworks,
+                networks.iter().map(|n| n.name.clone()).collect(),
+            )?;
+        }
+        match crate::editing::edit_form(&form, |cf| {
+            let form = Form::from_news_affiliate_str(cf)?;
+            crate::editing::news::news_affiliate_from_form(0, &form, &networks)
+        })? {
+            Some(result) => result,
+            None => {
+                println!("New record will not be added to the database");
+                return Ok(());
             }
         }
     };
 
-    let updated = db::save_news_affiliate(affiliate).await?;
+    let updated = crate::audit::save_news_affiliate(affiliate, None).await?;
     println!("===============");
     println!("Saved affiliate");
     println!("===============");
@@ -36,26 +52,31 @@ pub async fn add(path: Option<PathBuf>) -> Result<()> {
     Ok(())
 }
 
-pub async fn edit(id: u32) -> Result<()> {
+pub async fn edit(id: u32, prompt: bool) -> Result<()> {
     let networks = db::get_news_networks(None).await?;
     let affiliate = db::get_news_affiliate(id as i32, None).await?;
-    let form = Form::from(&affiliate);
-    let affiliate = match Editor::new().edit(&form.as_string()) {
-        Ok(completed_form) => {
-            if let Some(cf) = completed_form {
-                let form = Form::from_news_affiliate_str(&cf)?;
-                crate::editing::news::news_affiliate_from_form(affiliate.id, &form, &networks)?
-            } else {
-                println!("New record will not be added to the database");
-                return Ok(());
-            }
-        }
-        Err(_) => {
-            return Err(eyre!("An unknown error occurred when editing the video"));
+    let choices = |source: ChoicesSource| news_affiliate_choices(source, &networks);
+    let updated = match crate::editing::edit_record_form(
+        RecordEntity::Affiliate,
+        affiliate.id,
+        Form::from(&affiliate),
+        prompt.then_some(&choices as _),
+        || async { Ok(Form::from(&db::get_news_affiliate(id as i32, None).await?)) },
+        |cf| {
+            let form = Form::from_news_affiliate_str(cf)?;
+            crate::editing::news::news_affiliate_from_form(affiliate.id, &form, &networks)
+        },
+        |affiliate, version| crate::audit::save_news_affiliate(affiliate, Some(version)),
+    )
+    .await?
+    {
+        Some(result) => result,
+        None => {
+            println!("New record will not be added to the database");
+            return Ok(());
         }
     };
 
-    let updated = db::save_news_affiliate(affiliate).await?;
     println!("===============");
     println!("Saved affiliate");
     println!("===============");
@@ -1,10 +1,25 @@
 use crate::editing::forms::Form;
-use color_eyre::{eyre::eyre, Result};
-use db::models::NewsBroadcast;
-use dialoguer::Editor;
+use crate::editing::prompts::prompt_for_choices;
+use crate::editing::records::RecordEntity;
+use crate::editing::schema::ChoicesSource;
+use color_eyre::Result;
+use db::models::{NewsAffiliate, NewsBroadcast, NewsNetwork};
 use std::path::PathBuf;
 
-pub async fn add(path: Option<PathBuf>) -> Result<()> {
+/// The choices for the fields of a news broadcast form when prompting for them.
+fn news_broadcast_choices(
+    source: ChoicesSource,
+    networks: &[NewsNetwork],
+    affiliates: &[NewsAffiliate],
+) -> Vec<String> {
+    match source {
+        ChoicesSource::NewsAffiliates => affiliates.iter().map(|a| a.name.clone()).collect(),
+        ChoicesSource::NewsNetworks => networks.iter().map(|n| n.name.clone()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+pub async fn add(path: Option<PathBuf>, prompt: bool) -> Result<()> {
     let networks = db::get_news_networks(None).await?;
     let affiliates = db::get_news_affiliates(None).await?;
 
@@ -14,33 +29,33 @@ pub async fn add(path: Option<PathBuf>) -> Result<()> {
         crate::editing::news::news_broadcast_from_form(0, &form, &networks, &affiliates)?
     } else {
         let mut form = Form::from(&NewsBroadcast::default());
-        form.add_choices("Network", networks.iter().map(|n| n.name.clone()).collect())?;
-        form.add_choices(
-            "Affiliate",
-            affiliates.iter().map(|a| a.name.clone()).collect(),
-        )?;
-        match Editor::new().edit(&form.as_string()) {
-            Ok(completed_form) => {
-                if let Some(cf) = completed_form {
-                    let form = Form::from_news_broadcast_str(&cf)?;
-                    crate::editing::news::news_broadcast_from_form(
-                        0,
-                        &form,
-                        &networks,
-                        &affiliates,
-                    )?
-                } else {
-                    println!("New record will not be added to the database");
-                    return Ok(());
-                }
-            }
-            Err(_) => {
-                return Err(eyre!("An unknown error occurred when editing the video"));
+        if prompt {
+            prompt_for_choices(&mut form, |source| {
+                news_broadcast_choices(source, &networks, &affiliates)
+            })?;
+        } else {
+            form.populate_choices(
+                ChoicesSource::NewsNetworks,
+                networks.iter().map(|n| n.name.clone()).collect(),
+            )?;
+            form.populate_choices(
+                ChoicesSource::NewsAffiliates,
+                affiliates.iter().map(|a| a.name.clone()).collect(),
+            )?;
+        }
+        match crate::editing::edit_form(&form, |cf| {
+            let form = Form::from_news_broadcast_str(cf)?;
+            crate::editing::news::news_broadcast_from_form(0, &form, &networks, &affiliates)
+        })? {
+            Some(result) => result,
+            None => {
+                println!("New record will not be added to the database");
+                return Ok(());
             }
         }
     };
 
-    let updated = db::save_news_broadcast(broadcast).await?;
+    let updated = crate::audit::save_news_broadcast(broadcast, None).await?;
     println!("===============");
     println!("Saved broadcast");
     println!("===============");
@@ -48,33 +63,38 @@ pub async fn add(path: Option<PathBuf>) -> Result<()> {
     Ok(())
 }
 
-pub async fn edit(id: u32) -> Result<()> {
+pub async fn edit(id: u32, prompt: bool) -> Result<()> {
     let networks = db::get_news_networks(None).await?;
     let affiliates = db::get_news_affiliates(None).await?;
     let broadcast = db::get_news_broadcast(id as i32, None).await?;
-    let form = Form::from(&broadcast);
 
-    let broadcast = match Editor::new().edit(&form.as_string()) {
-        Ok(completed_form) => {
-            if let Some(cf) = completed_form {
-                let form = Form::from_news_broadcast_str(&cf)?;
-                crate::editing::news::news_broadcast_from_form(
-                    broadcast.id,
-                    &form,
-                    &networks,
-                    &affiliates,
-                )?
-            } else {
-                println!("New record will not be added to the database");
-                return Ok(());
-            }
-        }
-        Err(_) => {
-            return Err(eyre!("An unknown error occurred when editing the video"));
+    let choices = |source: ChoicesSource| news_broadcast_choices(source, &networks, &affiliates);
+    let updated = match crate::editing::edit_record_form(
+        RecordEntity::Broadcast,
+        broadcast.id,
+        Form::from(&broadcast),
+        prompt.then_some(&choices as _),
+        || async { Ok(Form::from(&db::get_news_broadcast(id as i32, None).await?)) },
+        |cf| {
+            let form = Form::from_news_broadcast_str(cf)?;
+            crate::editing::news::news_broadcast_from_form(
+                broadcast.id,
+                &form,
+                &networks,
+                &affiliates,
+            )
+        },
+        |broadcast, version| crate::audit::save_news_broadcast(broadcast, Some(version)),
+    )
+    .await?
+    {
+        Some(result) => result,
+        None => {
+            println!("New record will not be added to the database");
+            return Ok(());
         }
     };
 
-    let updated = db::save_news_broadcast(broadcast).await?;
     println!("===============");
     println!("Saved broadcast");
     println!("===============");
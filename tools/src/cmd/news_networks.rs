@@ -1,7 +1,7 @@
 use crate::editing::forms::Form;
-use color_eyre::{eyre::eyre, Result};
+use crate::editing::records::RecordEntity;
+use color_eyre::Result;
 use db::models::NewsNetwork;
-use dialoguer::Editor;
 use std::path::PathBuf;
 
 pub async fn add(path: Option<PathBuf>) -> Result<()> {
@@ -11,23 +11,19 @@ pub async fn add(path: Option<PathBuf>) -> Result<()> {
         crate::editing::news::news_network_from_form(0, &form)?
     } else {
         let form = Form::from(&NewsNetwork::default());
-        match Editor::new().edit(&form.as_string()) {
-            Ok(completed_form) => {
-                if let Some(cf) = completed_form {
-                    let form = Form::from_news_network_str(&cf)?;
-                    crate::editing::news::news_network_from_form(0, &form)?
-                } else {
-                    println!("New record will not be added to the database");
-                    return Ok(());
-                }
-            }
-            Err(_) => {
-                return Err(eyre!("An unknown error occurred when editing the video"));
+        match crate::editing::edit_form(&form, |cf| {
+            let form = Form::from_news_network_str(cf)?;
+            crate::editing::news::news_network_from_form(0, &form)
+        })? {
+            Some(result) => result,
+            None => {
+                println!("New record will not be added to the database");
+                return Ok(());
             }
         }
     };
 
-    let updated = db::save_news_network(network).await?;
+    let updated = crate::audit::save_news_network(network, None).await?;
     println!("=============");
     println!("Saved network");
     println!("=============");
@@ -38,22 +34,27 @@ pub async fn add(path: Option<PathBuf>) -> Result<()> {
 pub async fn edit(id: u32) -> Result<()> {
     let network = db::get_news_network(id as i32, None).await?;
     let form = Form::from(&network);
-    let network = match Editor::new().edit(&form.as_string()) {
-        Ok(completed_form) => {
-            if let Some(cf) = completed_form {
-                let form = Form::from_news_network_str(&cf)?;
-                crate::editing::news::news_network_from_form(network.id, &form)?
-            } else {
-                println!("New record will not be added to the database");
-                return Ok(());
-            }
-        }
-        Err(_) => {
-            return Err(eyre!("An unknown error occurred when editing the video"));
+    let updated = match crate::editing::edit_record_form(
+        RecordEntity::Network,
+        network.id,
+        form,
+        None,
+        || async { Ok(Form::from(&db::get_news_network(id as i32, None).await?)) },
+        |cf| {
+            let form = Form::from_news_network_str(cf)?;
+            crate::editing::news::news_network_from_form(network.id, &form)
+        },
+        |network, version| crate::audit::save_news_network(network, Some(version)),
+    )
+    .await?
+    {
+        Some(result) => result,
+        None => {
+            println!("New record will not be added to the database");
+            return Ok(());
         }
     };
 
-    let updated = db::save_news_network(network).await?;
     println!("=============");
     println!("Saved network");
     println!("=============");
@@ -1,22 +1,350 @@
-use color_eyre::Result;
+use crate::audit::entity_name;
+use crate::diff::{diff_lines, print_diff};
+use crate::editing::records::{field_key, RecordEntity};
+use color_eyre::{eyre::eyre, Result};
+use colored::Colorize;
+use db::mdb::MdbTools;
+use db::models::{NistTape, NistVideo};
 use std::path::Path;
 
-pub async fn document_numbers() -> Result<()> {
-    print!("Importing the document database numbers from the binary's static data...");
-    db::import_document_database_numbers().await?;
+/// A difference between a row read from NIST's database and the one already in ours.
+#[derive(Debug, PartialEq)]
+enum Difference {
+    Changed {
+        id: i32,
+        before: String,
+        after: String,
+    },
+    Inserted {
+        id: i32,
+        after: String,
+    },
+    /// The import never deletes rows, so these are kept as they are.
+    NotInExport {
+        id: i32,
+        before: String,
+    },
+}
+
+/// Compares rows by their IDs, using the text of each row to find the ones that changed.
+///
+/// Returns the differences with the number of rows that are unchanged.
+fn compare(imported: &[(i32, String)], existing: &[(i32, String)]) -> (Vec<Difference>, usize) {
+    let mut differences = Vec::new();
+    let mut unchanged = 0;
+    for (id, after) in imported.iter() {
+        match existing.iter().find(|(existing_id, _)| existing_id == id) {
+            Some((_, before)) if before == after => unchanged += 1,
+            Some((_, before)) => differences.push(Difference::Changed {
+                id: *id,
+                before: before.clone(),
+                after: after.clone(),
+            }),
+            None => differences.push(Difference::Inserted {
+                id: *id,
+                after: after.clone(),
+            }),
+        }
+    }
+    let mut not_in_export: Vec<&(i32, String)> = existing
+        .iter()
+        .filter(|(id, _)| !imported.iter().any(|(imported_id, _)| imported_id == id))
+        .collect();
+    not_in_export.sort_by_key(|(id, _)| *id);
+    for (id, before) in not_in_export {
+        differences.push(Difference::NotInExport {
+            id: *id,
+            before: before.clone(),
+        });
+    }
+    (differences, unchanged)
+}
+
+fn print_differences(name: &str, differences: &[Difference], unchanged: usize) {
+    let (mut inserted, mut changed, mut not_in_export) = (0, 0, 0);
+    for difference in differences.iter() {
+        match difference {
+            Difference::Changed { id, before, after } => {
+                changed += 1;
+                println!("{}", format!("Changed {name} {id}").bold());
+                print_diff(&diff_lines(before, after), 0);
+            }
+            Difference::Inserted { id, after } => {
+                inserted += 1;
+                println!("{}", format!("Inserted {name} {id}").bold());
+                print_diff(&diff_lines("", after), 0);
+            }
+            Difference::NotInExport { id, before } => {
+                not_in_export += 1;
+                println!(
+                    "{}",
+                    format!("Kept {name} {id}, which is not in the export").bold()
+                );
+                for line in before.lines() {
+                    println!("  {line}");
+                }
+            }
+        }
+    }
+    println!(
+        "{name}s: {inserted} inserted, {changed} changed, {unchanged} unchanged, \
+         {not_in_export} not in export (kept)"
+    );
+}
+
+/// The columns of a video that come from NIST's database, one per line.
+fn video_text(video: &NistVideo) -> String {
+    format!(
+        "Title: {}\nNetwork: {}\nBroadcast date: {}\nDuration: {} minutes\nSubject: {}\nNotes: {}\n",
+        video.video_title,
+        video.network.clone().unwrap_or_default(),
+        video
+            .broadcast_date
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .unwrap_or_default(),
+        video.duration_min,
+        video.subject.clone().unwrap_or_default(),
+        video.notes.clone().unwrap_or_default()
+    )
+}
+
+/// The columns of a tape that come from NIST's database, one per line.
+fn tape_text(tape: &NistTape) -> String {
+    format!(
+        "Video: {}\nName: {}\nSource: {}\nCopy: {}\nDerived from: {}\nFormat: {}\n\
+         Duration: {} minutes\nBatch: {}\nClips: {}\nTimecode: {}\n",
+        tape.video.video_id,
+        tape.tape_name,
+        tape.tape_source,
+        tape.copy,
+        tape.derived_from,
+        tape.format,
+        tape.duration_min,
+        tape.batch,
+        tape.clips,
+        tape.timecode
+    )
+}
+
+async fn print_video_differences(videos: &[NistVideo]) -> Result<()> {
+    let imported: Vec<(i32, String)> = videos.iter().map(|v| (v.video_id, video_text(v))).collect();
+    let existing: Vec<(i32, String)> = db::get_nist_videos()
+        .await?
+        .iter()
+        .map(|v| (v.video_id, video_text(v)))
+        .collect();
+    let (differences, unchanged) = compare(&imported, &existing);
+    print_differences("video", &differences, unchanged);
     Ok(())
 }
 
-pub async fn tapes(path: &Path) -> Result<()> {
-    print!("Importing the Tapes table from the NIST database...");
-    db::import_nist_tapes_table_from_csv(path).await?;
-    print!("done");
+async fn print_tape_differences(tapes: &[NistTape]) -> Result<()> {
+    let imported: Vec<(i32, String)> = tapes.iter().map(|t| (t.tape_id, tape_text(t))).collect();
+    let existing: Vec<(i32, String)> = db::get_nist_tapes()
+        .await?
+        .iter()
+        .map(|t| (t.tape_id, tape_text(t)))
+        .collect();
+    let (differences, unchanged) = compare(&imported, &existing);
+    print_differences("tape", &differences, unchanged);
     Ok(())
 }
 
-pub async fn videos(path: &Path) -> Result<()> {
-    print!("Importing the Videos table from the NIST database...");
-    db::import_nist_videos_table_from_csv(path).await?;
-    print!("done");
+pub async fn mapping(
+    csv_path: &Path,
+    column: &str,
+    kind: Option<String>,
+    entity: RecordEntity,
+    id_column: Option<String>,
+    foia_response: &str,
+) -> Result<()> {
+    // The kind is matched exactly when the identifiers are displayed, so it's kept to one form
+    // rather than taking whatever the heading of the column happens to be.
+    let kind = kind.unwrap_or_else(|| field_key(column));
+    if kind.is_empty()
+        || !kind
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    {
+        return Err(eyre!(
+            "'{kind}' must only contain lowercase letters, numbers and underscores"
+        ));
+    }
+
+    let (default_id_column, ids): (&str, Vec<i32>) = match entity {
+        RecordEntity::NistTape => (
+            "tape_id",
+            db::get_nist_tapes()
+                .await?
+                .iter()
+                .map(|t| t.tape_id)
+                .collect(),
+        ),
+        RecordEntity::NistVideo => (
+            "video_id",
+            db::get_nist_videos()
+                .await?
+                .iter()
+                .map(|v| v.video_id)
+                .collect(),
+        ),
+        _ => {
+            return Err(eyre!(
+                "Identifiers can only be mapped to NIST tapes or videos"
+            ))
+        }
+    };
+    let name = entity_name(entity);
+    let id_column = id_column.unwrap_or_else(|| default_id_column.to_string());
+
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .from_path(csv_path)?;
+    let headers = rdr.headers()?.clone();
+    let position = |column: &str| {
+        headers
+            .iter()
+            .position(|h| h.trim() == column)
+            .ok_or_else(|| eyre!("The CSV does not have a '{column}' column"))
+    };
+    let id_index = position(&id_column)?;
+    let value_index = position(column)?;
+
+    let mut mappings = Vec::new();
+    let mut problems = Vec::new();
+    for (i, result) in rdr.records().enumerate() {
+        let record = result?;
+        let value = record.get(value_index).unwrap_or_default().trim();
+        if value.is_empty() {
+            continue;
+        }
+        let id = record.get(id_index).unwrap_or_default().trim();
+        match id.parse::<i32>() {
+            Ok(id) if ids.contains(&id) => mappings.push((id, value.to_string())),
+            Ok(id) => problems.push(format!("Row {}: {name} {id} does not exist", i + 1)),
+            Err(_) => problems.push(format!("Row {}: '{id}' is not an ID", i + 1)),
+        }
+    }
+
+    let added = db::import_nist_identifiers(&name, &kind, foia_response, &mappings).await?;
+    println!(
+        "Added {added} {kind} identifiers from {foia_response}; {} were already recorded",
+        mappings.len() - added
+    );
+    if !problems.is_empty() {
+        println!(
+            "{}",
+            format!("{} rows could not be imported:", problems.len()).red()
+        );
+        for problem in problems.iter() {
+            println!("{}", problem.red());
+        }
+    }
     Ok(())
 }
+
+pub async fn mdb(path: &Path, dry_run: bool) -> Result<()> {
+    println!("Reading the tables from the NIST database...");
+    let tables = db::read_nist_database(&MdbTools::new(path)?).await?;
+    let mut failed = tables.failed.clone();
+    if dry_run {
+        let videos: Vec<NistVideo> = tables.videos.iter().map(|v| v.value.clone()).collect();
+        let tapes: Vec<NistTape> = tables.tapes.iter().map(|t| t.value.clone()).collect();
+        print_video_differences(&videos).await?;
+        print_tape_differences(&tapes).await?;
+        let counts = db::get_nist_table_row_counts().await?;
+        for table in tables.other_tables.iter() {
+            let current = counts
+                .iter()
+                .find(|(name, _)| *name == table.name)
+                .map(|(_, count)| *count)
+                .unwrap_or(0);
+            println!(
+                "The {} table would be replaced: {} rows, currently {current}",
+                table.name,
+                table.rows.len()
+            );
+        }
+    } else {
+        let report = db::import_nist_database(&tables).await?;
+        println!("Videos: {}", report.videos);
+        println!("Tapes: {}", report.tapes);
+        for (name, rows) in report.other_tables.iter() {
+            println!("Imported {rows} rows from the {name} table");
+        }
+        failed.extend(report.failed);
+        failed.sort_by(|a, b| (&a.table, a.row).cmp(&(&b.table, b.row)));
+    }
+    if !failed.is_empty() {
+        println!(
+            "{}",
+            format!("{} rows could not be imported:", failed.len()).red()
+        );
+        for failed in failed.iter() {
+            println!(
+                "{}",
+                format!("{} row {}: {}", failed.table, failed.row, failed.error).red()
+            );
+        }
+    }
+    Ok(())
+}
+
+pub async fn tapes(path: &Path, dry_run: bool) -> Result<()> {
+    if dry_run {
+        return print_tape_differences(&db::read_nist_tapes_from_csv(path)?).await;
+    }
+    println!("Importing the Tapes table from the NIST database...");
+    let counts = db::import_nist_tapes_table_from_csv(path).await?;
+    println!("Tapes: {counts}");
+    Ok(())
+}
+
+pub async fn videos(path: &Path, dry_run: bool) -> Result<()> {
+    if dry_run {
+        return print_video_differences(&db::read_nist_videos_from_csv(path)?).await;
+    }
+    println!("Importing the Videos table from the NIST database...");
+    let counts = db::import_nist_videos_table_from_csv(path).await?;
+    println!("Videos: {counts}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(id: i32, text: &str) -> (i32, String) {
+        (id, text.to_string())
+    }
+
+    #[test]
+    fn compare_should_find_inserted_changed_and_missing_rows() {
+        let imported = vec![row(2, "b"), row(3, "c2"), row(4, "d")];
+        let existing = vec![row(5, "e"), row(1, "a"), row(2, "b"), row(3, "c")];
+        let (differences, unchanged) = compare(&imported, &existing);
+        assert_eq!(unchanged, 1);
+        assert_eq!(
+            differences,
+            vec![
+                Difference::Changed {
+                    id: 3,
+                    before: "c".to_string(),
+                    after: "c2".to_string()
+                },
+                Difference::Inserted {
+                    id: 4,
+                    after: "d".to_string()
+                },
+                Difference::NotInExport {
+                    id: 1,
+                    before: "a".to_string()
+                },
+                Difference::NotInExport {
+                    id: 5,
+                    before: "e".to_string()
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,367 @@
+use crate::helpers::{print_banner, ExportFormat};
+use crate::lineage::Lineage;
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use color_eyre::Result;
+use colored::Colorize;
+use csv::Writer;
+use db::models::{NistTape, NistVideo};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+const UNKNOWN: &str = "Unknown";
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum StatusGroup {
+    BroadcastDate,
+    Network,
+    Subject,
+}
+
+impl StatusGroup {
+    fn heading(&self) -> &'static str {
+        match self {
+            StatusGroup::BroadcastDate => "Broadcast Date",
+            StatusGroup::Network => "Network",
+            StatusGroup::Subject => "Subject",
+        }
+    }
+
+    fn key(&self, status: &VideoStatus) -> String {
+        let value = match self {
+            StatusGroup::BroadcastDate => status.broadcast_date.map(|d| d.to_string()),
+            StatusGroup::Network => status.network.clone(),
+            StatusGroup::Subject => status.subject.clone(),
+        };
+        value
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| UNKNOWN.to_string())
+    }
+}
+
+/// How far a NIST video has got through the archive.
+#[derive(Debug, Serialize)]
+struct VideoStatus {
+    video_id: i32,
+    title: String,
+    network: Option<String>,
+    subject: Option<String>,
+    broadcast_date: Option<NaiveDate>,
+    is_missing: bool,
+    has_tapes: bool,
+    is_allocated: bool,
+    has_master: bool,
+    has_online_video: bool,
+}
+
+/// The number of videos in a group that have reached each stage.
+#[derive(Debug, Default, PartialEq, Serialize)]
+struct Totals {
+    group: String,
+    videos: usize,
+    missing: usize,
+    with_tapes: usize,
+    allocated: usize,
+    with_master: usize,
+    with_online_video: usize,
+}
+
+impl Totals {
+    fn new(group: &str) -> Self {
+        Self {
+            group: group.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn add(&mut self, status: &VideoStatus) {
+        self.videos += 1;
+        self.missing += status.is_missing as usize;
+        self.with_tapes += status.has_tapes as usize;
+        self.allocated += status.is_allocated as usize;
+        self.with_master += status.has_master as usize;
+        self.with_online_video += status.has_online_video as usize;
+    }
+}
+
+/// Works out the status of each video.
+///
+/// A video is allocated when one of its tapes has release files, or is covered by a copy in the
+/// same lineage that has them. The link counts are the number of masters and online videos for
+/// each video ID.
+fn video_statuses(
+    videos: &[NistVideo],
+    tapes: &[NistTape],
+    link_counts: &[(i32, i64, i64)],
+) -> Vec<VideoStatus> {
+    let lineage = Lineage::new(tapes);
+    let mut tapes_by_video: HashMap<i32, Vec<&NistTape>> = HashMap::new();
+    for tape in tapes.iter() {
+        tapes_by_video
+            .entry(tape.video.video_id)
+            .or_default()
+            .push(tape);
+    }
+    let link_counts: HashMap<i32, (i64, i64)> = link_counts
+        .iter()
+        .map(|(id, masters, online_videos)| (*id, (*masters, *online_videos)))
+        .collect();
+    videos
+        .iter()
+        .map(|video| {
+            let video_tapes = tapes_by_video
+                .get(&video.video_id)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let (masters, online_videos) =
+                link_counts.get(&video.video_id).copied().unwrap_or((0, 0));
+            VideoStatus {
+                video_id: video.video_id,
+                title: video.video_title.clone(),
+                network: video.network.clone(),
+                subject: video.subject.clone(),
+                broadcast_date: video.broadcast_date,
+                is_missing: video.is_missing,
+                has_tapes: !video_tapes.is_empty(),
+                is_allocated: video_tapes
+                    .iter()
+                    .any(|t| !t.release_files.is_empty() || lineage.covered_by(t).is_some()),
+                has_master: masters > 0,
+                has_online_video: online_videos > 0,
+            }
+        })
+        .collect()
+}
+
+fn totals(statuses: &[VideoStatus]) -> Totals {
+    let mut totals = Totals::new("All");
+    for status in statuses.iter() {
+        totals.add(status);
+    }
+    totals
+}
+
+/// The totals for each value of the group, in order of the value, with unknown values last.
+fn totals_by(statuses: &[VideoStatus], group: StatusGroup) -> Vec<Totals> {
+    let mut grouped: BTreeMap<String, Totals> = BTreeMap::new();
+    for status in statuses.iter() {
+        let key = group.key(status);
+        grouped
+            .entry(key.clone())
+            .or_insert_with(|| Totals::new(&key))
+            .add(status);
+    }
+    let (known, unknown): (Vec<Totals>, Vec<Totals>) =
+        grouped.into_values().partition(|t| t.group != UNKNOWN);
+    known.into_iter().chain(unknown).collect()
+}
+
+fn percentage(count: usize, total: usize) -> String {
+    if total == 0 {
+        return "0%".to_string();
+    }
+    format!("{:.0}%", count as f64 / total as f64 * 100.0)
+}
+
+fn print_summary(totals: &Totals) {
+    print_banner("Summary");
+    let line = |label: &str, count: usize| {
+        println!(
+            "{label}: {count} ({})",
+            percentage(count, totals.videos).bold()
+        );
+    };
+    println!("Total videos: {}", totals.videos);
+    line("Missing videos", totals.missing);
+    line("Videos with tapes", totals.with_tapes);
+    line("Allocated videos", totals.allocated);
+    line("Videos with a master", totals.with_master);
+    line("Videos online", totals.with_online_video);
+}
+
+fn print_totals(heading: &str, rows: &[Totals]) {
+    let width = rows
+        .iter()
+        .map(|r| r.group.chars().count())
+        .chain(std::iter::once(heading.len()))
+        .max()
+        .unwrap_or_default();
+    println!();
+    print_banner(&format!("By {heading}"));
+    println!(
+        "{}",
+        format!(
+            "{heading:<width$} {:>7} {:>7} {:>7} {:>9} {:>7} {:>7}",
+            "Videos", "Missing", "Tapes", "Allocated", "Master", "Online"
+        )
+        .bold()
+    );
+    for row in rows.iter() {
+        println!(
+            "{:<width$} {:>7} {:>7} {:>7} {:>9} {:>7} {:>7}",
+            row.group,
+            row.videos,
+            row.missing,
+            row.with_tapes,
+            row.allocated,
+            row.with_master,
+            row.with_online_video
+        );
+    }
+}
+
+fn write_rows<T: Serialize>(
+    rows: &[T],
+    format: ExportFormat,
+    out_path: Option<&Path>,
+) -> Result<()> {
+    let writer: Box<dyn std::io::Write> = if let Some(path) = out_path {
+        Box::new(std::fs::File::create(path)?)
+    } else {
+        Box::new(std::io::stdout())
+    };
+    match format {
+        ExportFormat::Csv => {
+            let mut writer = Writer::from_writer(writer);
+            for row in rows.iter() {
+                writer.serialize(row)?;
+            }
+            writer.flush()?;
+        }
+        ExportFormat::Json => {
+            serde_json::to_writer_pretty(writer, rows)?;
+            if out_path.is_none() {
+                println!();
+            }
+        }
+    }
+    Ok(())
+}
+
+pub async fn status(
+    by: Option<StatusGroup>,
+    format: Option<ExportFormat>,
+    out_path: Option<&Path>,
+) -> Result<()> {
+    let videos = db::get_nist_videos().await?;
+    let tapes = db::get_nist_tapes().await?;
+    let link_counts = db::get_nist_video_link_counts().await?;
+    let statuses = video_statuses(&videos, &tapes, &link_counts);
+
+    let format = match format {
+        Some(format) => format,
+        None => {
+            if let Some(path) = out_path {
+                match path.extension().and_then(|e| e.to_str()) {
+                    Some("json") => ExportFormat::Json,
+                    _ => ExportFormat::Csv,
+                }
+            } else {
+                print_summary(&totals(&statuses));
+                let groups = match by {
+                    Some(group) => vec![group],
+                    None => vec![
+                        StatusGroup::Network,
+                        StatusGroup::Subject,
+                        StatusGroup::BroadcastDate,
+                    ],
+                };
+                for group in groups {
+                    print_totals(group.heading(), &totals_by(&statuses, group));
+                }
+                return Ok(());
+            }
+        }
+    };
+
+    let exported = match by {
+        Some(group) => {
+            let rows = totals_by(&statuses, group);
+            write_rows(&rows, format, out_path)?;
+            rows.len()
+        }
+        None => {
+            write_rows(&statuses, format, out_path)?;
+            statuses.len()
+        }
+    };
+    if let Some(path) = out_path {
+        println!("Exported {exported} rows to {}", path.to_string_lossy());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn video(video_id: i32, network: Option<&str>, is_missing: bool) -> NistVideo {
+        NistVideo {
+            video_id,
+            network: network.map(String::from),
+            is_missing,
+            ..Default::default()
+        }
+    }
+
+    fn tape(tape_id: i32, video: &NistVideo, derived_from: i32) -> NistTape {
+        NistTape {
+            tape_id,
+            derived_from,
+            video: video.clone(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn video_statuses_should_find_the_stage_of_each_video() {
+        let videos = vec![
+            video(1, Some("ABC"), false),
+            video(2, Some("ABC"), false),
+            video(3, Some("CBS"), false),
+            video(4, None, true),
+        ];
+        let mut tapes = vec![
+            tape(10, &videos[0], 0),
+            tape(11, &videos[1], 10),
+            tape(12, &videos[2], 0),
+        ];
+        tapes[0].release_files = vec![(PathBuf::from("a.mpg"), 1)];
+        let link_counts = vec![(1, 1, 2), (2, 1, 0), (3, 0, 0), (4, 0, 0)];
+
+        let statuses = video_statuses(&videos, &tapes, &link_counts);
+        let allocated: Vec<bool> = statuses.iter().map(|s| s.is_allocated).collect();
+        assert_eq!(allocated, vec![true, true, false, false]);
+        assert!(!statuses[3].has_tapes);
+        assert!(statuses[1].has_master && !statuses[1].has_online_video);
+        assert!(statuses[0].has_online_video);
+
+        assert_eq!(
+            totals(&statuses),
+            Totals {
+                group: "All".to_string(),
+                videos: 4,
+                missing: 1,
+                with_tapes: 3,
+                allocated: 2,
+                with_master: 2,
+                with_online_video: 1,
+            }
+        );
+        let by_network: Vec<(String, usize)> = totals_by(&statuses, StatusGroup::Network)
+            .into_iter()
+            .map(|t| (t.group, t.videos))
+            .collect();
+        assert_eq!(
+            by_network,
+            vec![
+                ("ABC".to_string(), 2),
+                ("CBS".to_string(), 1),
+                ("Unknown".to_string(), 1)
+            ]
+        );
+    }
+}
@@ -1,9 +1,13 @@
-use crate::{editing::forms::Form, helpers::print_banner};
+use crate::audit::entity_name;
+use crate::editing::{forms::Form, records::RecordEntity};
+use crate::helpers::print_banner;
+use crate::lineage::Lineage;
 use color_eyre::{eyre::eyre, Result};
 use colored::Colorize;
-use dialoguer::Editor;
+use db::models::NistTape;
 use skim::prelude::*;
 use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
 
 #[derive(Default)]
 struct ReportSummary {
@@ -26,6 +30,171 @@ impl ReportSummary {
     }
 }
 
+/// The release files a tape will have after an allocation.
+#[derive(Debug, PartialEq)]
+struct TapeAllocation {
+    tape_id: i32,
+    files: Vec<PathBuf>,
+    added: usize,
+    removed: usize,
+}
+
+/// Works out the release files of each tape from the files allocated to it.
+///
+/// The files are added to the ones the tape already has, unless `replace` is used, in which case
+/// they are the only files the tape will have. Tapes that wouldn't change are left out.
+fn plan_allocations(
+    allocated: &[(i32, Vec<PathBuf>)],
+    tapes: &[NistTape],
+    replace: bool,
+) -> Vec<TapeAllocation> {
+    let mut tape_ids: Vec<i32> = allocated.iter().map(|(id, _)| *id).collect();
+    tape_ids.sort();
+    tape_ids.dedup();
+
+    let mut plan = Vec::new();
+    for tape_id in tape_ids {
+        let existing: Vec<PathBuf> = tapes
+            .iter()
+            .find(|t| t.tape_id == tape_id)
+            .map(|t| t.release_files.iter().map(|(p, _)| p.clone()).collect())
+            .unwrap_or_default();
+        let mut files: Vec<PathBuf> = if replace {
+            Vec::new()
+        } else {
+            existing.clone()
+        };
+        for (_, paths) in allocated.iter().filter(|(id, _)| *id == tape_id) {
+            for path in paths.iter() {
+                if !files.contains(path) {
+                    files.push(path.clone());
+                }
+            }
+        }
+        let added = files.iter().filter(|f| !existing.contains(f)).count();
+        let removed = existing.iter().filter(|f| !files.contains(f)).count();
+        if added == 0 && removed == 0 {
+            continue;
+        }
+        plan.push(TapeAllocation {
+            tape_id,
+            files,
+            added,
+            removed,
+        });
+    }
+    plan
+}
+
+pub async fn allocate(csv_path: &Path, replace: bool, dry_run: bool) -> Result<()> {
+    let tapes = db::get_nist_tapes().await?;
+
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .from_path(csv_path)?;
+    let headers = rdr.headers()?.clone();
+    let position = |column: &str| {
+        headers
+            .iter()
+            .position(|h| h.trim() == column)
+            .ok_or_else(|| eyre!("The CSV does not have a '{column}' column"))
+    };
+    let id_index = position("tape_id")?;
+    let path_index = position("path")?;
+
+    let mut rows: Vec<(usize, i32, String)> = Vec::new();
+    let mut problems = Vec::new();
+    for (i, result) in rdr.records().enumerate() {
+        let record = result?;
+        let row = i + 1;
+        let id = record.get(id_index).unwrap_or_default().trim();
+        let path = record.get(path_index).unwrap_or_default().trim();
+        if path.is_empty() {
+            problems.push((row, "there is no path".to_string()));
+            continue;
+        }
+        match id.parse::<i32>() {
+            Ok(id) if tapes.iter().any(|t| t.tape_id == id) => {
+                rows.push((row, id, path.to_string()))
+            }
+            Ok(id) => problems.push((row, format!("tape {id} does not exist"))),
+            Err(_) => problems.push((row, format!("'{id}' is not an ID"))),
+        }
+    }
+
+    let mut paths: Vec<String> = rows.iter().map(|(_, _, path)| path.clone()).collect();
+    paths.sort();
+    paths.dedup();
+    let expanded = db::expand_release_paths(&paths).await?;
+    let mut allocated = Vec::new();
+    for (row, tape_id, path) in rows.iter() {
+        let files = expanded
+            .iter()
+            .find(|(input, _)| input == path)
+            .map(|(_, files)| files.clone())
+            .unwrap_or_default();
+        if files.is_empty() {
+            problems.push((*row, format!("'{path}' is not a release file or directory")));
+            continue;
+        }
+        allocated.push((*tape_id, files));
+    }
+
+    if !problems.is_empty() {
+        problems.sort();
+        println!(
+            "{}",
+            format!("{} rows have problems:", problems.len()).red()
+        );
+        for (row, problem) in problems.iter() {
+            println!("{}", format!("Row {row}: {problem}").red());
+        }
+        return Err(eyre!("No tapes were allocated"));
+    }
+
+    let plan = plan_allocations(&allocated, &tapes, replace);
+    for allocation in plan.iter() {
+        let name = tapes
+            .iter()
+            .find(|t| t.tape_id == allocation.tape_id)
+            .map(|t| t.tape_name.clone())
+            .unwrap_or_default();
+        println!(
+            "{}: {} files added, {} removed",
+            format!("{} {}", allocation.tape_id, name).bold(),
+            allocation.added,
+            allocation.removed
+        );
+    }
+    let added: usize = plan.iter().map(|a| a.added).sum();
+    let removed: usize = plan.iter().map(|a| a.removed).sum();
+    let unchanged = allocated
+        .iter()
+        .map(|(id, _)| *id)
+        .filter(|id| !plan.iter().any(|a| a.tape_id == *id))
+        .collect::<std::collections::BTreeSet<i32>>()
+        .len();
+    println!(
+        "{} tapes changed ({added} files added, {removed} removed), {unchanged} unchanged",
+        plan.len()
+    );
+    if dry_run {
+        println!("This was a dry run; nothing was saved");
+        return Ok(());
+    }
+    if plan.is_empty() {
+        return Ok(());
+    }
+
+    let files: Vec<(i32, Vec<(PathBuf, u64)>)> = plan
+        .into_iter()
+        .map(|a| (a.tape_id, a.files.into_iter().map(|f| (f, 0)).collect()))
+        .collect();
+    let saved = crate::audit::save_nist_tapes_files(&files).await?;
+    println!("Saved the release files of {} tapes", saved.len());
+    Ok(())
+}
+
 pub async fn edit(id: Option<u32>) -> Result<()> {
     let tapes = db::get_nist_tapes().await?;
     let tape = if let Some(id) = id {
@@ -67,22 +236,35 @@ pub async fn edit(id: Option<u32>) -> Result<()> {
     }
 
     let form = Form::from(&tape);
-    let files = match Editor::new().edit(&form.as_string()) {
-        Ok(completed_form) => {
-            if let Some(cf) = completed_form {
-                let form = Form::from_nist_tape_str(&cf)?;
-                crate::editing::nist_tapes::get_release_files_from_form(&form)?
-            } else {
-                println!("New record will not be added to the database");
-                return Ok(());
-            }
-        }
-        Err(_) => {
-            return Err(eyre!("An unknown error occurred when editing the video"));
+    let tape_id = tape.tape_id;
+    let updated = match crate::editing::edit_record_form(
+        RecordEntity::NistTape,
+        tape_id,
+        form,
+        None,
+        || async {
+            let tape = db::get_nist_tapes()
+                .await?
+                .into_iter()
+                .find(|t| t.tape_id == tape_id)
+                .ok_or_else(|| eyre!("Could not find tape with ID {tape_id}"))?;
+            Ok(Form::from(&tape))
+        },
+        |cf| {
+            let form = Form::from_nist_tape_str(cf)?;
+            crate::editing::nist_tapes::get_release_files_from_form(&form)
+        },
+        |files, version| crate::audit::save_nist_tape_files(tape_id, files, Some(version)),
+    )
+    .await?
+    {
+        Some(result) => result,
+        None => {
+            println!("New record will not be added to the database");
+            return Ok(());
         }
     };
 
-    let updated = db::save_nist_tape_files(tape.tape_id, files).await?;
     println!("===============");
     println!("Saved NIST tape");
     println!("===============");
@@ -100,9 +282,20 @@ pub async fn ls(
     let mut summary = ReportSummary::default();
     let tapes_grouped_by_video = db::get_nist_tapes_grouped_by_video().await?;
     summary.total = tapes_grouped_by_video.len();
+    let all_tapes = db::get_nist_tapes().await?;
+    let lineage = Lineage::new(&all_tapes);
+    let covered_by = |tape: &NistTape| {
+        lineage
+            .get(tape.tape_id)
+            .and_then(|t| lineage.covered_by(t))
+            .map(|t| t.tape_id)
+    };
 
     for (video, tapes) in tapes_grouped_by_video.iter() {
-        if tapes.iter().any(|t| !t.release_files.is_empty()) {
+        if tapes
+            .iter()
+            .any(|t| !t.release_files.is_empty() || covered_by(t).is_some())
+        {
             summary.allocated += 1;
             if only_display_unallocated {
                 continue;
@@ -164,6 +357,10 @@ pub async fn ls(
                 s.push_str(&format!(" {}", "T".to_string().blue()));
             }
 
+            if let Some(id) = covered_by(tape) {
+                s.push_str(&format!(" {}", format!("covered by {id}").cyan()));
+            }
+
             if let Some(term) = &find {
                 if s.to_lowercase().contains(&term.to_lowercase()) {
                     println!("{}", s);
@@ -204,6 +401,78 @@ pub async fn ls(
     Ok(())
 }
 
+pub async fn lineage(id: u32) -> Result<()> {
+    let tapes = db::get_nist_tapes().await?;
+    let lineage = Lineage::new(&tapes);
+    let tape = lineage
+        .get(id as i32)
+        .ok_or_else(|| eyre!("Could not find tape with ID {id}"))?;
+
+    println!(
+        "{}",
+        format!("{}: {}", tape.video.video_id, tape.video.video_title)
+            .blue()
+            .bold()
+    );
+    let root = lineage.root(tape);
+    if root.derived_from != 0 && root.derived_from != root.tape_id {
+        println!(
+            "{}",
+            format!(
+                "Tape {} is derived from tape {}, which does not exist",
+                root.tape_id, root.derived_from
+            )
+            .red()
+        );
+    }
+    for (depth, member) in lineage.members(tape) {
+        let mut s = format!(
+            "{}{}: {} (copy {}, {}m)",
+            "  ".repeat(depth),
+            member.tape_id,
+            member.tape_name,
+            member.copy,
+            member.duration_min
+        );
+        if member.video.video_id != tape.video.video_id {
+            s.push_str(&format!(" [video {}]", member.video.video_id));
+        }
+        let s = if member.tape_id == tape.tape_id {
+            s.bold()
+        } else {
+            s.normal()
+        };
+        if !member.release_files.is_empty() {
+            println!("{s} {}", "allocated".green());
+            for (path, _) in member.release_files.iter() {
+                println!("{}  {}", "  ".repeat(depth), path.to_string_lossy().green());
+            }
+        } else if let Some(covering) = lineage.covered_by(member) {
+            println!("{s} {}", format!("covered by {}", covering.tape_id).cyan());
+        } else {
+            println!("{s}");
+        }
+    }
+    Ok(())
+}
+
+pub async fn orphans() -> Result<()> {
+    let tapes = db::get_nist_tapes().await?;
+    let orphans = Lineage::new(&tapes).orphans();
+    if orphans.is_empty() {
+        println!("Every tape is derived from a tape that exists");
+        return Ok(());
+    }
+    for tape in orphans.iter() {
+        println!(
+            "{}: {} is derived from tape {}, which does not exist",
+            tape.tape_id, tape.tape_name, tape.derived_from
+        );
+    }
+    println!("{} orphaned tapes", orphans.len());
+    Ok(())
+}
+
 pub async fn print(id: u32) -> Result<()> {
     let tape = db::get_nist_tapes()
         .await?
@@ -211,6 +480,21 @@ pub async fn print(id: u32) -> Result<()> {
         .find(|t| t.tape_id == id as i32)
         .ok_or_else(|| eyre!("Could not find tape with ID {id}"))?;
     tape.print();
+
+    let identifiers =
+        db::get_nist_identifiers(&entity_name(RecordEntity::NistTape), id as i32).await?;
+    if !identifiers.is_empty() {
+        println!("Identifiers:");
+        for identifier in identifiers.iter() {
+            println!(
+                "  {}: {} (from {}, imported {})",
+                identifier.kind,
+                identifier.value,
+                identifier.foia_response,
+                identifier.imported_at.format("%Y-%m-%d")
+            );
+        }
+    }
     Ok(())
 }
 
@@ -225,3 +509,51 @@ fn print_additional_notes(notes: &str, wrap_length: Option<usize>) {
         println!("{}{}", indent, line.purple());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tape(tape_id: i32, files: &[&str]) -> NistTape {
+        NistTape {
+            tape_id,
+            release_files: files.iter().map(|f| (PathBuf::from(f), 0)).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn paths(files: &[&str]) -> Vec<PathBuf> {
+        files.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn plan_allocations_should_add_files_to_the_existing_ones() {
+        let tapes = vec![tape(1, &["a/1.mpg"]), tape(2, &["b/1.mpg"]), tape(3, &[])];
+        let allocated = vec![
+            (1, paths(&["a/1.mpg", "a/2.mpg"])),
+            (2, paths(&["b/1.mpg"])),
+            (1, paths(&["a/3.mpg"])),
+        ];
+        let plan = plan_allocations(&allocated, &tapes, false);
+        assert_eq!(
+            plan,
+            vec![TapeAllocation {
+                tape_id: 1,
+                files: paths(&["a/1.mpg", "a/2.mpg", "a/3.mpg"]),
+                added: 2,
+                removed: 0,
+            }]
+        );
+
+        let plan = plan_allocations(&[(2, paths(&["b/2.mpg"]))], &tapes, true);
+        assert_eq!(
+            plan,
+            vec![TapeAllocation {
+                tape_id: 2,
+                files: paths(&["b/2.mpg"]),
+                added: 1,
+                removed: 1,
+            }]
+        );
+    }
+}
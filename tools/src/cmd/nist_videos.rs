@@ -1,8 +1,60 @@
 use crate::editing::forms::Form;
+use crate::editing::prompts::select;
+use crate::editing::records::RecordEntity;
 use color_eyre::{eyre::eyre, Result};
-use dialoguer::Editor;
+use colored::Colorize;
+use db::models::{NistNetworkMapping, NistVideo};
+use std::collections::BTreeMap;
 use std::io::Write;
 
+fn print_video(video: &NistVideo) {
+    if video.is_missing {
+        println!("  {} {}", video, "[MISSING]".bright_red());
+    } else {
+        println!("  {video}");
+    }
+}
+
+pub async fn by_network() -> Result<()> {
+    let videos = db::get_nist_videos().await?;
+    let mappings = db::get_nist_network_mappings().await?;
+
+    let mut mapped: BTreeMap<String, Vec<&NistVideo>> = BTreeMap::new();
+    let mut no_match: Vec<&NistVideo> = Vec::new();
+    let mut not_mapped: BTreeMap<String, usize> = BTreeMap::new();
+    for video in videos.iter() {
+        let name = video.network.as_deref().unwrap_or_default().trim();
+        match mappings.iter().find(|m| m.nist_network == name) {
+            Some(mapping) if mapping.news_network.is_some() || mapping.news_affiliate.is_some() => {
+                mapped.entry(mapping.target()).or_default().push(video);
+            }
+            Some(_) => no_match.push(video),
+            None => *not_mapped.entry(name.to_string()).or_default() += 1,
+        }
+    }
+
+    for (target, videos) in mapped.iter() {
+        println!("{}", target.bold());
+        for video in videos.iter() {
+            print_video(video);
+        }
+    }
+    if !no_match.is_empty() {
+        println!("{}", "No match".bold());
+        for video in no_match.iter() {
+            print_video(video);
+        }
+    }
+    if !not_mapped.is_empty() {
+        println!("{}", "Network names that are not mapped:".yellow());
+        for (name, count) in not_mapped.iter() {
+            let name = if name.is_empty() { "[none]" } else { name };
+            println!("  {name} ({count} videos)");
+        }
+    }
+    Ok(())
+}
+
 pub async fn edit(id: u32) -> Result<()> {
     let videos = db::get_nist_videos().await?;
     let video = videos
@@ -20,22 +72,38 @@ pub async fn edit(id: u32) -> Result<()> {
     }
 
     let form = Form::from(&video);
-    let (is_missing, additional_notes) = match Editor::new().edit(&form.as_string()) {
-        Ok(completed_form) => {
-            if let Some(cf) = completed_form {
-                let form = Form::from_nist_video_str(&cf)?;
-                crate::editing::nist_videos::get_missing_and_additional_notes_field(&form)?
-            } else {
-                println!("The video will not be saved");
-                return Ok(());
-            }
-        }
-        Err(_) => {
-            return Err(eyre!("An unknown error occurred when editing the video"));
+    let video_id = video.video_id;
+    let updated = match crate::editing::edit_record_form(
+        RecordEntity::NistVideo,
+        video.video_id,
+        form,
+        None,
+        || async {
+            let video = db::get_nist_videos()
+                .await?
+                .into_iter()
+                .find(|v| v.video_id as u32 == id)
+                .ok_or_else(|| eyre!("Could not find video with ID {id}"))?;
+            Ok(Form::from(&video))
+        },
+        |cf| {
+            let form = Form::from_nist_video_str(cf)?;
+            crate::editing::nist_videos::get_missing_and_additional_notes_field(&form)
+        },
+        |(is_missing, additional_notes), version| async move {
+            crate::audit::save_nist_video(video_id, is_missing, &additional_notes, Some(version))
+                .await
+        },
+    )
+    .await?
+    {
+        Some(result) => result,
+        None => {
+            println!("The video will not be saved");
+            return Ok(());
         }
     };
 
-    let updated = db::save_nist_video(video.video_id, is_missing, &additional_notes).await?;
     println!("===============");
     println!("Saved NIST video");
     println!("===============");
@@ -50,3 +118,98 @@ pub async fn ls() -> Result<()> {
     }
     Ok(())
 }
+
+pub async fn map_networks(remap: bool) -> Result<()> {
+    let networks = db::get_nist_networks().await?;
+    let mappings = db::get_nist_network_mappings().await?;
+
+    let mut targets: Vec<(String, NistNetworkMapping)> = Vec::new();
+    for network in db::get_news_networks(None).await? {
+        targets.push((
+            format!("Network: {}", network.name),
+            NistNetworkMapping {
+                news_network: Some(network),
+                ..Default::default()
+            },
+        ));
+    }
+    for affiliate in db::get_news_affiliates(None).await? {
+        targets.push((
+            format!("Affiliate: {} ({})", affiliate.name, affiliate.network.name),
+            NistNetworkMapping {
+                news_affiliate: Some(affiliate),
+                ..Default::default()
+            },
+        ));
+    }
+    targets.push(("No match".to_string(), NistNetworkMapping::default()));
+    let options: Vec<String> = targets.iter().map(|(label, _)| label.clone()).collect();
+
+    let mut saved = 0;
+    for (name, count) in networks.iter() {
+        let current = mappings.iter().find(|m| &m.nist_network == name);
+        if current.is_some() && !remap {
+            continue;
+        }
+        let current: Vec<String> = current
+            .and_then(|m| {
+                targets.iter().find(|(_, t)| {
+                    t.news_network == m.news_network && t.news_affiliate == m.news_affiliate
+                })
+            })
+            .map(|(label, _)| label.clone())
+            .into_iter()
+            .collect();
+        let Some(selected) = select(
+            &format!("{name} ({count} videos)"),
+            &options,
+            &current,
+            false,
+            "skip this network",
+        )?
+        else {
+            continue;
+        };
+        let Some((_, target)) = selected
+            .first()
+            .and_then(|s| targets.iter().find(|(label, _)| label == s))
+        else {
+            continue;
+        };
+        let mapping = NistNetworkMapping {
+            nist_network: name.clone(),
+            ..target.clone()
+        };
+        let mut tx = db::begin_transaction().await?;
+        db::save_nist_network_mapping(&mut tx, &mapping).await?;
+        tx.commit().await?;
+        println!("{name} -> {}", mapping.target());
+        saved += 1;
+    }
+
+    let mappings = db::get_nist_network_mappings().await?;
+    let remaining = networks
+        .iter()
+        .filter(|(name, _)| !mappings.iter().any(|m| &m.nist_network == name))
+        .count();
+    println!("Saved {saved} mappings; {remaining} network names are not mapped");
+    Ok(())
+}
+
+pub async fn no_master(exclude_missing: bool) -> Result<()> {
+    let videos = db::get_nist_videos_without_master().await?;
+    let mut count = 0;
+    for video in videos.iter() {
+        if video.is_missing {
+            if exclude_missing {
+                continue;
+            }
+            println!("{} {}", video, "[MISSING]".bright_red());
+        } else {
+            println!("{video}");
+        }
+        count += 1;
+    }
+    println!("{count} NIST videos have no master video");
+    Ok(())
+}
@@ -0,0 +1,255 @@
+use crate::editing::forms::Form;
+use crate::editing::masters::SimilarNames;
+use crate::editing::records::{
+    form_from_record, parse_records, record_from_form, write_records, RecordEntity, RecordFormat,
+};
+use color_eyre::{eyre::eyre, Report, Result};
+use serde_json::Value;
+use std::path::Path;
+
+pub async fn export(
+    entity: RecordEntity,
+    id: Option<u32>,
+    format: Option<RecordFormat>,
+    out_path: Option<&Path>,
+) -> Result<()> {
+    let format = match format {
+        Some(format) => format,
+        None => out_path
+            .and_then(RecordFormat::from_path)
+            .unwrap_or(RecordFormat::Yaml),
+    };
+
+    let records: Vec<Value> = match entity {
+        RecordEntity::Affiliate => {
+            let affiliates = match id {
+                Some(id) => vec![db::get_news_affiliate(id as i32, None).await?],
+                None => db::get_news_affiliates(None).await?,
+            };
+            affiliates
+                .iter()
+                .map(|a| record_from_form(a.id, &Form::from(a)))
+                .collect()
+        }
+        RecordEntity::Broadcast => {
+            let broadcasts = match id {
+                Some(id) => vec![db::get_news_broadcast(id as i32, None).await?],
+                None => db::get_news_broadcasts().await?,
+            };
+            broadcasts
+                .iter()
+                .map(|b| record_from_form(b.id, &Form::from(b)))
+                .collect()
+        }
+        RecordEntity::Master => {
+            let masters = match id {
+                Some(id) => vec![db::get_master_video(id as i32, None).await?],
+                None => db::get_master_videos().await?,
+            };
+            masters
+                .iter()
+                .map(|m| record_from_form(m.id, &Form::from(m)))
+                .collect()
+        }
+        RecordEntity::Network => {
+            let networks = match id {
+                Some(id) => vec![db::get_news_network(id as i32, None).await?],
+                None => db::get_news_networks(None).await?,
+            };
+            networks
+                .iter()
+                .map(|n| record_from_form(n.id, &Form::from(n)))
+                .collect()
+        }
+        RecordEntity::NistTape => db::get_nist_tapes()
+            .await?
+            .iter()
+            .filter(|t| id.is_none_or(|id| t.tape_id == id as i32))
+            .map(|t| record_from_form(t.tape_id, &Form::from(t)))
+            .collect(),
+        RecordEntity::NistVideo => db::get_nist_videos()
+            .await?
+            .iter()
+            .filter(|v| id.is_none_or(|id| v.video_id == id as i32))
+            .map(|v| record_from_form(v.video_id, &Form::from(v)))
+            .collect(),
+        RecordEntity::Video => {
+            let videos = match id {
+                Some(id) => vec![db::get_video(id as i32, None).await?],
+                None => db::get_videos().await?,
+            };
+            videos
+                .iter()
+                .map(|v| record_from_form(v.id, &Form::from(v)))
+                .collect()
+        }
+    };
+    if records.is_empty() {
+        return Err(eyre!("There are no records to export"));
+    }
+
+    let count = records.len();
+    let output = write_records(records, format)?;
+    if let Some(path) = out_path {
+        std::fs::write(path, output)?;
+        println!("Exported {count} records to {}", path.to_string_lossy());
+    } else {
+        print!("{output}");
+    }
+    Ok(())
+}
+
+/// Reads the records in a file and converts each of them to a form.
+///
+/// Errors are reported with the position of the record in the file, so that a problem in a large
+/// batch can be found.
+fn read_forms(entity: RecordEntity, path: &Path) -> Result<Vec<(i32, Form)>> {
+    let format = RecordFormat::from_path(path).ok_or_else(|| {
+        eyre!("The file must have a yaml, yml or toml extension to determine its format")
+    })?;
+    let records = parse_records(&std::fs::read_to_string(path)?, format)?;
+    let mut forms = Vec::new();
+    for (i, record) in records.iter().enumerate() {
+        let (id, form) = form_from_record(entity.schema(), record)
+            .map_err(|e| eyre!("Record {}: {e}", i + 1))?;
+        if id == 0 && matches!(entity, RecordEntity::NistTape | RecordEntity::NistVideo) {
+            return Err(eyre!(
+                "Record {}: NIST records can only be updated, so they require an id",
+                i + 1
+            ));
+        }
+        forms.push((id, form));
+    }
+    Ok(forms)
+}
+
+fn print_saved(id: i32, description: &str) {
+    println!("Saved {id}: {description}");
+}
+
+/// Adds or updates each record in a YAML or TOML file.
+///
+/// Records with an id update the existing record and those without one are added. All the records
+/// are validated before any of them are saved.
+pub async fn import(entity: RecordEntity, path: &Path) -> Result<()> {
+    let forms = read_forms(entity, path)?;
+    let with_position = |i: usize| move |e: Report| eyre!("Record {}: {e}", i + 1);
+    match entity {
+        RecordEntity::Affiliate => {
+            let networks = db::get_news_networks(None).await?;
+            let mut affiliates = Vec::new();
+            for (i, (id, form)) in forms.iter().enumerate() {
+                affiliates.push(
+                    crate::editing::news::news_affiliate_from_form(*id, form, &networks)
+                        .map_err(with_position(i))?,
+                );
+            }
+            for affiliate in affiliates.into_iter() {
+                let saved = crate::audit::save_news_affiliate(affiliate, None).await?;
+                print_saved(saved.id, &saved.name);
+            }
+        }
+        RecordEntity::Broadcast => {
+            let networks = db::get_news_networks(None).await?;
+            let affiliates = db::get_news_affiliates(None).await?;
+            let mut broadcasts = Vec::new();
+            for (i, (id, form)) in forms.iter().enumerate() {
+                broadcasts.push(
+                    crate::editing::news::news_broadcast_from_form(
+                        *id,
+                        form,
+                        &networks,
+                        &affiliates,
+                    )
+                    .map_err(with_position(i))?,
+                );
+            }
+            for broadcast in broadcasts.into_iter() {
+                let saved = crate::audit::save_news_broadcast(broadcast, None).await?;
+                print_saved(saved.id, &saved.to_string());
+            }
+        }
+        RecordEntity::Master => {
+            let news_broadcasts = db::get_news_broadcasts().await?;
+            let nist_videos = db::get_nist_videos().await?;
+            let people = db::get_people().await?;
+            let event_types = db::get_event_types().await?;
+            let mut masters = Vec::new();
+            for (i, (id, form)) in forms.iter().enumerate() {
+                masters.push(
+                    crate::editing::masters::master_video_from_form(
+                        *id,
+                        form,
+                        &news_broadcasts,
+                        &nist_videos,
+                        &people,
+                        &event_types,
+                        SimilarNames::Reject,
+                    )
+                    .map_err(with_position(i))?,
+                );
+            }
+            for master in masters.into_iter() {
+                let saved = crate::audit::save_master_video(master, None).await?;
+                print_saved(saved.id, &saved.title);
+            }
+        }
+        RecordEntity::Network => {
+            let mut networks = Vec::new();
+            for (i, (id, form)) in forms.iter().enumerate() {
+                networks.push(
+                    crate::editing::news::news_network_from_form(*id, form)
+                        .map_err(with_position(i))?,
+                );
+            }
+            for network in networks.into_iter() {
+                let saved = crate::audit::save_news_network(network, None).await?;
+                print_saved(saved.id, &saved.name);
+            }
+        }
+        RecordEntity::NistTape => {
+            let mut allocations = Vec::new();
+            for (i, (id, form)) in forms.iter().enumerate() {
+                allocations.push((
+                    *id,
+                    crate::editing::nist_tapes::get_release_files_from_form(form)
+                        .map_err(with_position(i))?,
+                ));
+            }
+            for (id, files) in allocations.into_iter() {
+                let saved = crate::audit::save_nist_tape_files(id, files, None).await?;
+                print_saved(saved.tape_id, &saved.tape_name);
+            }
+        }
+        RecordEntity::NistVideo => {
+            let mut notes = Vec::new();
+            for (i, (id, form)) in forms.iter().enumerate() {
+                let (is_missing, additional_notes) =
+                    crate::editing::nist_videos::get_missing_and_additional_notes_field(form)
+                        .map_err(with_position(i))?;
+                notes.push((*id, is_missing, additional_notes));
+            }
+            for (id, is_missing, additional_notes) in notes.into_iter() {
+                let saved =
+                    crate::audit::save_nist_video(id, is_missing, &additional_notes, None).await?;
+                print_saved(saved.video_id, &saved.video_title);
+            }
+        }
+        RecordEntity::Video => {
+            let masters = db::get_master_videos().await?;
+            let mut videos = Vec::new();
+            for (i, (id, form)) in forms.iter().enumerate() {
+                videos.push(
+                    crate::editing::videos::video_from_form(*id, form, &masters)
+                        .map_err(with_position(i))?,
+                );
+            }
+            for video in videos.into_iter() {
+                let saved = crate::audit::save_video(video, None).await?;
+                print_saved(saved.id, &saved.title);
+            }
+        }
+    }
+    println!("Imported {} records", forms.len());
+    Ok(())
+}
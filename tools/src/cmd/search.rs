@@ -0,0 +1,13 @@
+use color_eyre::Result;
+
+pub async fn search(query: &str, limit: u32) -> Result<()> {
+    let results = db::search(query, limit as i64).await?;
+    if results.is_empty() {
+        println!("No results found for '{query}'");
+        return Ok(());
+    }
+    for result in results.iter() {
+        result.print_row();
+    }
+    Ok(())
+}
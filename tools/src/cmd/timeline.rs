@@ -0,0 +1,293 @@
+use crate::helpers::ExportFormat;
+use chrono::NaiveTime;
+use color_eyre::Result;
+use colored::Colorize;
+use csv::Writer;
+use db::helpers::{duration_to_string, interval_to_duration};
+use db::models::{EventTimestamp, MasterVideo};
+use serde::Serialize;
+use std::path::Path;
+
+#[derive(Default)]
+pub struct TimelineFilter {
+    pub end_time: Option<NaiveTime>,
+    pub event_types: Vec<String>,
+    pub network: Option<String>,
+    pub person: Option<String>,
+    pub start_time: Option<NaiveTime>,
+}
+
+impl TimelineFilter {
+    fn matches_master(&self, master: &MasterVideo) -> bool {
+        if let Some(network) = &self.network {
+            let network = network.to_lowercase();
+            let has_network = master.news_broadcasts.iter().any(|b| {
+                b.news_network
+                    .as_ref()
+                    .is_some_and(|n| n.name.to_lowercase() == network)
+                    || b.news_affiliate.as_ref().is_some_and(|a| {
+                        a.name.to_lowercase() == network || a.network.name.to_lowercase() == network
+                    })
+            });
+            if !has_network {
+                return false;
+            }
+        }
+        if let Some(person) = &self.person {
+            let person = person.to_lowercase();
+            if !master
+                .people
+                .iter()
+                .any(|p| p.name.to_lowercase() == person)
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn matches_timestamp(&self, timestamp: &EventTimestamp, time_of_day: NaiveTime) -> bool {
+        if !self.event_types.is_empty()
+            && !self
+                .event_types
+                .iter()
+                .any(|t| *t == timestamp.event_type.to_string())
+        {
+            return false;
+        }
+        if let Some(start) = self.start_time {
+            if time_of_day < start {
+                return false;
+            }
+        }
+        if let Some(end) = self.end_time {
+            if time_of_day > end {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TimelineEntry {
+    pub time_of_day: NaiveTime,
+    pub master_id: i32,
+    pub master_title: String,
+    pub timestamp: String,
+    pub event_type: String,
+    pub description: String,
+}
+
+impl TimelineEntry {
+    fn print_row(&self) {
+        println!(
+            "{} [{}] {}: {} ({})",
+            self.time_of_day.format("%H%M").to_string().bold(),
+            self.event_type.blue(),
+            self.master_id,
+            self.master_title,
+            self.timestamp
+        );
+        println!("    {}", self.description);
+    }
+}
+
+/// Merges the timestamps from all the master videos into a single chronological list.
+///
+/// Only timestamps that have a time of day can be placed on the timeline. Entries at the same time
+/// of day are ordered by master video ID, then by their offset into the video.
+pub fn build_timeline(masters: &[MasterVideo], filter: &TimelineFilter) -> Vec<TimelineEntry> {
+    let mut entries = Vec::new();
+    for master in masters.iter().filter(|m| filter.matches_master(m)) {
+        for timestamp in master.timestamps.iter() {
+            let Some(time_of_day) = timestamp.time_of_day else {
+                continue;
+            };
+            if !filter.matches_timestamp(timestamp, time_of_day) {
+                continue;
+            }
+            entries.push((
+                interval_to_duration(&timestamp.timestamp),
+                TimelineEntry {
+                    time_of_day,
+                    master_id: master.id,
+                    master_title: master.title.clone(),
+                    timestamp: duration_to_string(&interval_to_duration(&timestamp.timestamp)),
+                    event_type: timestamp.event_type.to_string(),
+                    description: timestamp.description.clone(),
+                },
+            ));
+        }
+    }
+    entries.sort_by(|(a_offset, a), (b_offset, b)| {
+        a.time_of_day
+            .cmp(&b.time_of_day)
+            .then(a.master_id.cmp(&b.master_id))
+            .then(a_offset.cmp(b_offset))
+    });
+    entries.into_iter().map(|(_, entry)| entry).collect()
+}
+
+pub async fn timeline(
+    filter: TimelineFilter,
+    format: Option<ExportFormat>,
+    out_path: Option<&Path>,
+) -> Result<()> {
+    let masters = db::get_master_videos().await?;
+    let entries = build_timeline(&masters, &filter);
+
+    let format = match format {
+        Some(format) => format,
+        None => {
+            if let Some(path) = out_path {
+                match path.extension().and_then(|e| e.to_str()) {
+                    Some("json") => ExportFormat::Json,
+                    _ => ExportFormat::Csv,
+                }
+            } else {
+                for entry in entries.iter() {
+                    entry.print_row();
+                }
+                return Ok(());
+            }
+        }
+    };
+
+    let writer: Box<dyn std::io::Write> = if let Some(path) = out_path {
+        Box::new(std::fs::File::create(path)?)
+    } else {
+        Box::new(std::io::stdout())
+    };
+    match format {
+        ExportFormat::Csv => {
+            let mut writer = Writer::from_writer(writer);
+            for entry in entries.iter() {
+                writer.serialize(entry)?;
+            }
+            writer.flush()?;
+        }
+        ExportFormat::Json => {
+            serde_json::to_writer_pretty(writer, &entries)?;
+            if out_path.is_none() {
+                println!();
+            }
+        }
+    }
+
+    if let Some(path) = out_path {
+        println!(
+            "Exported {} timeline entries to {}",
+            entries.len(),
+            path.to_string_lossy()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db::helpers::parse_duration;
+    use db::models::{EventType, NewsBroadcast, NewsNetwork, Person, PersonType};
+    use sqlx::postgres::types::PgInterval;
+
+    fn timestamp(offset: &str, time_of_day: Option<NaiveTime>, slug: &str) -> EventTimestamp {
+        EventTimestamp {
+            id: 0,
+            description: format!("Event at {offset}"),
+            timestamp: PgInterval::try_from(parse_duration(offset)).unwrap(),
+            end_timestamp: None,
+            event_type: EventType {
+                slug: slug.to_string(),
+                ..Default::default()
+            },
+            time_of_day,
+        }
+    }
+
+    fn masters() -> Vec<MasterVideo> {
+        vec![
+            MasterVideo {
+                id: 1,
+                title: "WABC-TV".to_string(),
+                news_broadcasts: vec![NewsBroadcast {
+                    news_network: Some(NewsNetwork {
+                        id: 1,
+                        name: "ABC News".to_string(),
+                        description: String::new(),
+                    }),
+                    ..Default::default()
+                }],
+                people: vec![Person {
+                    id: 1,
+                    name: "Lori Stokes".to_string(),
+                    historical_title: None,
+                    description: None,
+                    types: vec![PersonType::Reporter],
+                }],
+                timestamps: vec![
+                    timestamp("00:20:00", NaiveTime::from_hms_opt(9, 3, 0), "wtc2-impact"),
+                    timestamp("00:08:05", None, "normal"),
+                    timestamp(
+                        "01:16:10",
+                        NaiveTime::from_hms_opt(9, 59, 0),
+                        "wtc2-collapse",
+                    ),
+                ],
+                ..Default::default()
+            },
+            MasterVideo {
+                id: 2,
+                title: "Amateur footage".to_string(),
+                timestamps: vec![
+                    timestamp("00:01:00", NaiveTime::from_hms_opt(9, 3, 0), "wtc2-impact"),
+                    timestamp("00:00:10", NaiveTime::from_hms_opt(8, 50, 0), "normal"),
+                ],
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn build_timeline_should_order_entries_by_time_of_day() {
+        let entries = build_timeline(&masters(), &TimelineFilter::default());
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0].master_id, 2);
+        assert_eq!(
+            entries[0].time_of_day,
+            NaiveTime::from_hms_opt(8, 50, 0).unwrap()
+        );
+        assert_eq!(entries[1].master_id, 1);
+        assert_eq!(entries[2].master_id, 2);
+        assert_eq!(entries[3].event_type, "wtc2-collapse");
+    }
+
+    #[test]
+    fn build_timeline_should_apply_filters() {
+        let filter = TimelineFilter {
+            event_types: vec!["wtc2-impact".to_string()],
+            ..Default::default()
+        };
+        let entries = build_timeline(&masters(), &filter);
+        assert_eq!(entries.len(), 2);
+
+        let filter = TimelineFilter {
+            network: Some("abc news".to_string()),
+            start_time: NaiveTime::from_hms_opt(9, 30, 0),
+            ..Default::default()
+        };
+        let entries = build_timeline(&masters(), &filter);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].timestamp, "01:16:10");
+
+        let filter = TimelineFilter {
+            person: Some("Lori Stokes".to_string()),
+            end_time: NaiveTime::from_hms_opt(9, 30, 0),
+            ..Default::default()
+        };
+        let entries = build_timeline(&masters(), &filter);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].master_id, 1);
+    }
+}
@@ -1,13 +1,70 @@
-use crate::{editing::forms::Form, export_master_videos, releases::export_video_list};
+use crate::cmd::youtube::get_imported_youtube_ids;
+use crate::editing::prompts::{prompt_for_choices, select};
+use crate::editing::{forms::Form, records::RecordEntity, schema::ChoicesSource};
+use crate::helpers::youtube_id_from_link;
+use crate::{export_master_videos, releases::export_video_list};
 use color_eyre::{eyre::eyre, Result};
-use db::{cumulus::convert_videos_to_csv, helpers::parse_duration, models::Video};
-use dialoguer::Editor;
+use colored::Colorize;
+use db::{
+    cumulus::convert_videos_to_csv, helpers::parse_duration, models::MasterVideo, models::Video,
+};
+use db_youtube::models::YouTubeVideo;
 use sqlx::postgres::types::PgInterval;
 use std::path::{Path, PathBuf};
 
+/// The choices for the fields of a video form when prompting for them.
+fn video_choices(source: ChoicesSource, masters: &[MasterVideo]) -> Vec<String> {
+    match source {
+        ChoicesSource::MasterVideos => masters.iter().map(|m| m.title.clone()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Parses a list of YouTube videos, with one ID or link on each line.
+///
+/// Blank lines and lines starting with `#` are ignored, as are repeats of a video.
+fn parse_youtube_ids(input: &str) -> Result<Vec<String>> {
+    let mut ids: Vec<String> = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let id = if line.starts_with("http") {
+            youtube_id_from_link(line)
+                .ok_or_else(|| eyre!("Line {}: '{line}' is not a link to a video", i + 1))?
+        } else {
+            line.to_string()
+        };
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    Ok(ids)
+}
+
+fn video_from_youtube(yt_video: YouTubeVideo, master: &MasterVideo, is_primary: bool) -> Video {
+    let link = yt_video.link();
+    Video {
+        channel_username: yt_video.channel_name,
+        description: yt_video.description,
+        duration: if let Some(duration) = yt_video.duration {
+            PgInterval::try_from(parse_duration(&duration)).unwrap()
+        } else {
+            PgInterval::try_from(parse_duration("0")).unwrap()
+        },
+        id: 0,
+        is_primary,
+        link,
+        master: master.clone(),
+        title: yt_video.title,
+    }
+}
+
 pub async fn add(
     master_id: Option<u32>,
     path: Option<PathBuf>,
+    prompt: bool,
     youtube_id: Option<String>,
 ) -> Result<()> {
     let video = if let Some(youtube_id) = youtube_id {
@@ -19,21 +76,10 @@ pub async fn add(
         )
         .await?;
         let yt_video = db_youtube::get_video(&youtube_id).await?;
-
-        Video {
-            channel_username: yt_video.channel_name,
-            description: yt_video.description,
-            duration: if let Some(duration) = yt_video.duration {
-                PgInterval::try_from(parse_duration(&duration)).unwrap()
-            } else {
-                PgInterval::try_from(parse_duration("0")).unwrap()
-            },
-            id: 0,
-            is_primary: false,
-            link: format!("https://www.youtube.com/watch?v={youtube_id}"),
-            master: master.clone(),
-            title: yt_video.title,
+        if get_imported_youtube_ids().await?.contains(&yt_video.id) {
+            return Err(eyre!("{} has already been added", yt_video.link()));
         }
+        video_from_youtube(yt_video, &master, false)
     } else if let Some(path) = path {
         let masters = db::get_master_videos().await?;
         let completed_form = std::fs::read_to_string(path)?;
@@ -42,24 +88,27 @@ pub async fn add(
     } else {
         let masters = db::get_master_videos().await?;
         let mut form = Form::from(&Video::default());
-        form.add_choices("Master", masters.iter().map(|m| m.title.clone()).collect())?;
-        match Editor::new().edit(&form.as_string()) {
-            Ok(completed_form) => {
-                if let Some(cf) = completed_form {
-                    let form = Form::from_video_str(&cf)?;
-                    crate::editing::videos::video_from_form(0, &form, &masters)?
-                } else {
-                    println!("New record will not be added to the database");
-                    return Ok(());
-                }
-            }
-            Err(_) => {
-                return Err(eyre!("An unknown error occurred when editing the video"));
+        if prompt {
+            prompt_for_choices(&mut form, |source| video_choices(source, &masters))?;
+        } else {
+            form.populate_choices(
+                ChoicesSource::MasterVideos,
+                masters.iter().map(|m| m.title.clone()).collect(),
+            )?;
+        }
+        match crate::editing::edit_form(&form, |cf| {
+            let form = Form::from_video_str(cf)?;
+            crate::editing::videos::video_from_form(0, &form, &masters)
+        })? {
+            Some(result) => result,
+            None => {
+                println!("New record will not be added to the database");
+                return Ok(());
             }
         }
     };
 
-    let updated = db::save_video(video).await?;
+    let updated = crate::audit::save_video(video, None).await?;
     println!("===========");
     println!("Saved video");
     println!("===========");
@@ -79,27 +128,32 @@ pub async fn convert(cumulus_export_path: &Path, out_path: &Path) -> Result<()>
     Ok(())
 }
 
-pub async fn edit(id: u32) -> Result<()> {
+pub async fn edit(id: u32, prompt: bool) -> Result<()> {
     let masters = db::get_master_videos().await?;
     let video = db::get_video(id as i32, None).await?;
 
-    let form = Form::from(&video);
-    let edited_video = match Editor::new().edit(&form.as_string()) {
-        Ok(completed_form) => {
-            if let Some(cf) = completed_form {
-                let form = Form::from_video_str(&cf)?;
-                crate::editing::videos::video_from_form(video.id, &form, &masters)?
-            } else {
-                println!("Changes to the video record will not be saved");
-                return Ok(());
-            }
-        }
-        Err(_) => {
-            return Err(eyre!("An unknown error occurred when editing the video"));
+    let choices = |source: ChoicesSource| video_choices(source, &masters);
+    let updated = match crate::editing::edit_record_form(
+        RecordEntity::Video,
+        video.id,
+        Form::from(&video),
+        prompt.then_some(&choices as _),
+        || async { Ok(Form::from(&db::get_video(id as i32, None).await?)) },
+        |cf| {
+            let form = Form::from_video_str(cf)?;
+            crate::editing::videos::video_from_form(video.id, &form, &masters)
+        },
+        |video, version| crate::audit::save_video(video, Some(version)),
+    )
+    .await?
+    {
+        Some(result) => result,
+        None => {
+            println!("Changes to the video record will not be saved");
+            return Ok(());
         }
     };
 
-    let updated = db::save_video(edited_video).await?;
     println!("===========");
     println!("Saved video");
     println!("===========");
@@ -122,6 +176,138 @@ pub async fn export_master(out_path: &Path) -> Result<()> {
     Ok(())
 }
 
+pub async fn import_youtube(
+    master_id: u32,
+    ids_file: Option<PathBuf>,
+    channel: Option<String>,
+    primary_id: Option<String>,
+    dry_run: bool,
+) -> Result<()> {
+    let master = db::get_master_video(master_id as i32, None).await?;
+    let candidates = if let Some(path) = ids_file {
+        let ids = parse_youtube_ids(&std::fs::read_to_string(path)?)?;
+        let mut candidates = Vec::new();
+        let mut not_found = Vec::new();
+        for id in ids.iter() {
+            match db_youtube::get_video(id).await {
+                Ok(video) => candidates.push(video),
+                Err(db_youtube::error::Error::SqlError(sqlx::Error::RowNotFound)) => {
+                    not_found.push(id.clone())
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        if !not_found.is_empty() {
+            return Err(eyre!(
+                "These videos are not in the YouTube archive: {}",
+                not_found.join(", ")
+            ));
+        }
+        candidates
+    } else if let Some(channel) = channel {
+        let videos = db_youtube::get_videos_for_channel(&channel).await?;
+        if videos.is_empty() {
+            return Err(eyre!("There are no videos for the '{channel}' channel"));
+        }
+        let options: Vec<String> = videos
+            .iter()
+            .map(|v| format!("{}: {}", v.id, v.title))
+            .collect();
+        let Some(selected) = select("Videos", &options, &[], true, "import nothing")? else {
+            println!("No videos were imported");
+            return Ok(());
+        };
+        videos
+            .into_iter()
+            .filter(|v| selected.contains(&format!("{}: {}", v.id, v.title)))
+            .collect()
+    } else {
+        return Err(eyre!("Either --ids-file or --channel must be used"));
+    };
+
+    let imported = get_imported_youtube_ids().await?;
+    let (existing, new): (Vec<YouTubeVideo>, Vec<YouTubeVideo>) = candidates
+        .into_iter()
+        .partition(|v| imported.contains(&v.id));
+    if let Some(primary_id) = &primary_id {
+        if !new.iter().any(|v| &v.id == primary_id) {
+            return Err(eyre!(
+                "The primary video {primary_id} is not one of the videos being imported"
+            ));
+        }
+    }
+
+    for video in existing.iter() {
+        println!(
+            "{} {}: {}",
+            "Skipping".yellow(),
+            video.id,
+            "already added".yellow()
+        );
+    }
+    for video in new.iter() {
+        let primary = primary_id.as_ref() == Some(&video.id);
+        println!(
+            "{} {}: {}{}",
+            "Importing".green(),
+            video.id,
+            video.title,
+            if primary { " [PRIMARY]" } else { "" }
+        );
+    }
+    println!(
+        "{} videos to import, {} already added",
+        new.len(),
+        existing.len()
+    );
+    if dry_run {
+        println!("This was a dry run; nothing was saved");
+        return Ok(());
+    }
+
+    // The videos are saved together, so the master is never left without a primary video, or with
+    // only some of the videos, if one of them can't be saved.
+    let mut tx = db::begin_transaction().await?;
+    let mut saved = Vec::new();
+    let mut demoted = Vec::new();
+    if primary_id.is_some() {
+        for video in db::get_videos_for_master(master.id).await? {
+            if video.is_primary {
+                demoted.push(video.id);
+                crate::audit::save_video_tx(
+                    &mut tx,
+                    Video {
+                        is_primary: false,
+                        ..video
+                    },
+                    None,
+                )
+                .await?;
+            }
+        }
+    }
+    for yt_video in new.into_iter() {
+        let primary = primary_id.as_ref() == Some(&yt_video.id);
+        saved.push(
+            crate::audit::save_video_tx(
+                &mut tx,
+                video_from_youtube(yt_video, &master, primary),
+                None,
+            )
+            .await?,
+        );
+    }
+    tx.commit().await?;
+
+    for id in demoted.iter() {
+        println!("Video {id} is no longer the primary video");
+    }
+    for video in saved.iter() {
+        println!("Saved video {}: {}", video.id, video.title);
+    }
+    Ok(())
+}
+
 pub async fn ls() -> Result<()> {
     let videos = db::get_videos().await?;
     for video in videos.iter() {
@@ -135,3 +321,27 @@ pub async fn print(id: u32) -> Result<()> {
     video.print();
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_youtube_ids_should_read_ids_and_links() {
+        let input = "\
+# From the archive
+abc123
+
+https://www.youtube.com/watch?v=def456&t=10s
+https://youtu.be/ghi789
+https://m.youtube.com/watch?v=jkl012
+abc123
+";
+        assert_eq!(
+            parse_youtube_ids(input).unwrap(),
+            vec!["abc123", "def456", "ghi789", "jkl012"]
+        );
+        assert!(parse_youtube_ids("https://www.youtube.com/channel/UC1").is_err());
+        assert!(parse_youtube_ids("https://example.com/watch?v=abc123").is_err());
+    }
+}
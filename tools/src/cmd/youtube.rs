@@ -0,0 +1,79 @@
+use crate::helpers::youtube_id_from_link;
+use color_eyre::{eyre::eyre, Result};
+use colored::Colorize;
+use db_youtube::models::YouTubeVideo;
+use std::collections::HashSet;
+
+/// The IDs of the YouTube videos that have already been added to the catalogue.
+///
+/// Videos are compared by ID because the same video can have been added with a different form of
+/// link, such as a short link or one with a start time.
+pub async fn get_imported_youtube_ids() -> Result<HashSet<String>> {
+    Ok(db::get_video_links()
+        .await?
+        .iter()
+        .filter_map(|link| youtube_id_from_link(link))
+        .collect())
+}
+
+fn print_video_row(video: &YouTubeVideo, imported: &HashSet<String>) {
+    let mut s = format!(
+        "{}: {} [{}, {}, {} views]",
+        video.id,
+        video.title,
+        video
+            .upload_date
+            .map(|d| d.to_string())
+            .unwrap_or_else(|| "unknown date".to_string()),
+        video.duration.as_deref().unwrap_or("unknown length"),
+        video
+            .view_count
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| "?".to_string())
+    );
+    if imported.contains(&video.id) {
+        s.push_str(&format!(" {}", "[IMPORTED]".green()));
+    }
+    println!("{s}");
+}
+
+fn print_video_rows(videos: &[YouTubeVideo], imported: &HashSet<String>) {
+    for video in videos.iter() {
+        print_video_row(video, imported);
+    }
+    let count = videos.iter().filter(|v| imported.contains(&v.id)).count();
+    println!("{} videos, {count} already imported", videos.len());
+}
+
+pub async fn ls(channel: Option<String>) -> Result<()> {
+    match channel {
+        Some(channel) => {
+            let videos = db_youtube::get_videos_for_channel(&channel).await?;
+            print_video_rows(&videos, &get_imported_youtube_ids().await?);
+        }
+        None => {
+            for channel in db_youtube::get_channels().await?.iter() {
+                channel.print_row();
+            }
+        }
+    }
+    Ok(())
+}
+
+pub async fn print(id: &str) -> Result<()> {
+    let video = db_youtube::get_video(id).await?;
+    video.print();
+    if get_imported_youtube_ids().await?.contains(&video.id) {
+        println!("{}", "This video has already been imported".green());
+    }
+    Ok(())
+}
+
+pub async fn search(term: &str) -> Result<()> {
+    if term.trim().is_empty() {
+        return Err(eyre!("The search term can't be empty"));
+    }
+    let videos = db_youtube::search_videos(term).await?;
+    print_video_rows(&videos, &get_imported_youtube_ids().await?);
+    Ok(())
+}
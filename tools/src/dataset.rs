@@ -0,0 +1,1271 @@
+use crate::audit::entity_name;
+use crate::editing::records::RecordEntity;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use color_eyre::{eyre::eyre, Result};
+use db::helpers::{duration_to_string, interval_to_duration, parse_duration};
+use db::models::{
+    Category, EventTimestamp, EventType, MasterVideo, NewsAffiliate, NewsBroadcast, NewsNetwork,
+    NistIdentifier, NistNetworkMapping, NistVideo, Person, PersonType, Video,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sqlx::postgres::types::PgInterval;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+pub const CATEGORIES: [Category; 6] = [
+    Category::AmateurFootage,
+    Category::Compilation,
+    Category::Documentary,
+    Category::News,
+    Category::ProfessionalFootage,
+    Category::SurvivorAccount,
+];
+
+const PERSON_TYPES: [PersonType; 8] = [
+    PersonType::Eyewitness,
+    PersonType::Fire,
+    PersonType::Police,
+    PersonType::PortAuthority,
+    PersonType::Reporter,
+    PersonType::Survivor,
+    PersonType::Victim,
+    PersonType::Videographer,
+];
+
+fn parse_variant<T: Clone + std::fmt::Display>(s: &str, variants: &[T]) -> Option<T> {
+    variants.iter().find(|v| v.to_string() == s).cloned()
+}
+
+/// A record in the dataset.
+pub trait DatasetRecord: Serialize + DeserializeOwned {
+    /// The directory the records are written to.
+    const DIR: &'static str;
+
+    fn id(&self) -> i32;
+
+    /// The records of this kind in a dataset.
+    fn records(dataset: &mut Dataset) -> &mut Vec<Self>;
+}
+
+/// A file in the dataset.
+///
+/// Records are named by their ID, but some entries, such as the event types, are identified by
+/// something else.
+pub trait DatasetFile: Serialize + DeserializeOwned {
+    /// The directory the files are written to.
+    const DIR: &'static str;
+
+    /// The name of the file, without its extension.
+    fn file_stem(&self) -> String;
+}
+
+impl<T: DatasetRecord> DatasetFile for T {
+    const DIR: &'static str = <T as DatasetRecord>::DIR;
+
+    fn file_stem(&self) -> String {
+        format!("{:04}", self.id())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct EventTypeRecord {
+    pub slug: String,
+    pub label: String,
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canonical_time: Option<NaiveTime>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkRecord {
+    pub id: i32,
+    pub name: String,
+    pub description: String,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct AffiliateRecord {
+    pub id: i32,
+    pub network_id: i32,
+    pub name: String,
+    pub description: String,
+    pub region: String,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BroadcastRecord {
+    pub id: i32,
+    pub date: NaiveDate,
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network_id: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub affiliate_id: Option<i32>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PersonRecord {
+    pub id: i32,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub historical_title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub types: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TimestampRecord {
+    pub id: i32,
+    pub offset: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_offset: Option<String>,
+    pub event_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_of_day: Option<NaiveTime>,
+    pub description: String,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct MasterRecord {
+    pub id: i32,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<NaiveDate>,
+    pub categories: Vec<String>,
+    pub description: String,
+    #[serde(default)]
+    pub links: Vec<String>,
+    #[serde(default)]
+    pub news_broadcast_ids: Vec<i32>,
+    #[serde(default)]
+    pub person_ids: Vec<i32>,
+    #[serde(default)]
+    pub nist_video_ids: Vec<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nist_notes: Option<String>,
+    #[serde(default)]
+    pub nist_files: Vec<String>,
+    #[serde(default)]
+    pub timestamps: Vec<TimestampRecord>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct VideoRecord {
+    pub id: i32,
+    pub master_id: i32,
+    pub title: String,
+    pub channel: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub link: String,
+    pub duration: String,
+    pub is_primary: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct NistTapeRecord {
+    pub tape_id: i32,
+    pub release_files: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct NistVideoRecord {
+    pub video_id: i32,
+    pub is_missing: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub additional_notes: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct NistIdentifierRecord {
+    pub id: i32,
+    pub entity: String,
+    pub entity_id: i32,
+    pub kind: String,
+    pub value: String,
+    pub foia_response: String,
+    pub imported_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct NistNetworkMappingRecord {
+    pub nist_network: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network_id: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub affiliate_id: Option<i32>,
+}
+
+/// A file name for a name that might contain characters that can't be used in one, e.g., "CBS/USA"
+/// becomes `cbs-usa`.
+fn file_stem_for(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("-")
+}
+
+impl DatasetFile for EventTypeRecord {
+    const DIR: &'static str = "event_types";
+
+    fn file_stem(&self) -> String {
+        self.slug.clone()
+    }
+}
+
+impl DatasetFile for NistNetworkMappingRecord {
+    const DIR: &'static str = "nist_network_mappings";
+
+    fn file_stem(&self) -> String {
+        file_stem_for(&self.nist_network)
+    }
+}
+
+impl DatasetRecord for NetworkRecord {
+    const DIR: &'static str = "news_networks";
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn records(dataset: &mut Dataset) -> &mut Vec<Self> {
+        &mut dataset.networks
+    }
+}
+
+impl DatasetRecord for AffiliateRecord {
+    const DIR: &'static str = "news_affiliates";
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn records(dataset: &mut Dataset) -> &mut Vec<Self> {
+        &mut dataset.affiliates
+    }
+}
+
+impl DatasetRecord for BroadcastRecord {
+    const DIR: &'static str = "news_broadcasts";
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn records(dataset: &mut Dataset) -> &mut Vec<Self> {
+        &mut dataset.broadcasts
+    }
+}
+
+impl DatasetRecord for PersonRecord {
+    const DIR: &'static str = "people";
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn records(dataset: &mut Dataset) -> &mut Vec<Self> {
+        &mut dataset.people
+    }
+}
+
+impl DatasetRecord for MasterRecord {
+    const DIR: &'static str = "master_videos";
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn records(dataset: &mut Dataset) -> &mut Vec<Self> {
+        &mut dataset.masters
+    }
+}
+
+impl DatasetRecord for VideoRecord {
+    const DIR: &'static str = "videos";
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn records(dataset: &mut Dataset) -> &mut Vec<Self> {
+        &mut dataset.videos
+    }
+}
+
+impl DatasetRecord for NistIdentifierRecord {
+    const DIR: &'static str = "nist_identifiers";
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn records(dataset: &mut Dataset) -> &mut Vec<Self> {
+        &mut dataset.nist_identifiers
+    }
+}
+
+impl DatasetRecord for NistTapeRecord {
+    const DIR: &'static str = "nist_tapes";
+
+    fn id(&self) -> i32 {
+        self.tape_id
+    }
+
+    fn records(dataset: &mut Dataset) -> &mut Vec<Self> {
+        &mut dataset.nist_tapes
+    }
+}
+
+impl DatasetRecord for NistVideoRecord {
+    const DIR: &'static str = "nist_videos";
+
+    fn id(&self) -> i32 {
+        self.video_id
+    }
+
+    fn records(dataset: &mut Dataset) -> &mut Vec<Self> {
+        &mut dataset.nist_videos
+    }
+}
+
+/// The curated part of the catalogue, as models ready to be saved.
+///
+/// The NIST tapes and videos themselves come from the NIST data, so only the release files
+/// allocated to each tape and the notes added for each video are part of the catalogue.
+#[derive(Clone, Default)]
+pub struct Catalogue {
+    pub affiliates: Vec<NewsAffiliate>,
+    pub broadcasts: Vec<NewsBroadcast>,
+    pub event_types: Vec<EventType>,
+    pub masters: Vec<MasterVideo>,
+    pub networks: Vec<NewsNetwork>,
+    pub nist_identifiers: Vec<NistIdentifier>,
+    pub nist_network_mappings: Vec<NistNetworkMapping>,
+    pub nist_tapes: Vec<(i32, Vec<PathBuf>)>,
+    pub nist_videos: Vec<(i32, bool, String)>,
+    pub people: Vec<Person>,
+    pub videos: Vec<Video>,
+}
+
+/// The catalogue as records that refer to each other by ID, with one file per record.
+///
+/// Records are written in ID order, with their lists sorted, so that exporting the same data
+/// always produces the same files.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Dataset {
+    pub affiliates: Vec<AffiliateRecord>,
+    pub broadcasts: Vec<BroadcastRecord>,
+    pub event_types: Vec<EventTypeRecord>,
+    pub masters: Vec<MasterRecord>,
+    pub networks: Vec<NetworkRecord>,
+    pub nist_identifiers: Vec<NistIdentifierRecord>,
+    pub nist_network_mappings: Vec<NistNetworkMappingRecord>,
+    pub nist_tapes: Vec<NistTapeRecord>,
+    pub nist_videos: Vec<NistVideoRecord>,
+    pub people: Vec<PersonRecord>,
+    pub videos: Vec<VideoRecord>,
+}
+
+impl From<&EventType> for EventTypeRecord {
+    fn from(event_type: &EventType) -> Self {
+        EventTypeRecord {
+            slug: event_type.slug.clone(),
+            label: event_type.label.clone(),
+            description: event_type.description.clone(),
+            canonical_time: event_type.canonical_time,
+        }
+    }
+}
+
+impl From<&NewsNetwork> for NetworkRecord {
+    fn from(network: &NewsNetwork) -> Self {
+        NetworkRecord {
+            id: network.id,
+            name: network.name.clone(),
+            description: network.description.clone(),
+        }
+    }
+}
+
+impl From<&NewsAffiliate> for AffiliateRecord {
+    fn from(affiliate: &NewsAffiliate) -> Self {
+        AffiliateRecord {
+            id: affiliate.id,
+            network_id: affiliate.network.id,
+            name: affiliate.name.clone(),
+            description: affiliate.description.clone(),
+            region: affiliate.region.clone(),
+        }
+    }
+}
+
+impl From<&NewsBroadcast> for BroadcastRecord {
+    fn from(broadcast: &NewsBroadcast) -> Self {
+        BroadcastRecord {
+            id: broadcast.id,
+            date: broadcast.date,
+            description: broadcast.description.clone(),
+            network_id: broadcast.news_network.as_ref().map(|n| n.id),
+            affiliate_id: broadcast.news_affiliate.as_ref().map(|a| a.id),
+        }
+    }
+}
+
+impl From<&Person> for PersonRecord {
+    fn from(person: &Person) -> Self {
+        PersonRecord {
+            id: person.id,
+            name: person.name.clone(),
+            historical_title: person.historical_title.clone(),
+            description: person.description.clone(),
+            types: person.types.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+}
+
+impl From<&Video> for VideoRecord {
+    fn from(video: &Video) -> Self {
+        VideoRecord {
+            id: video.id,
+            master_id: video.master.id,
+            title: video.title.clone(),
+            channel: video.channel_username.clone(),
+            description: video.description.clone(),
+            link: video.link.clone(),
+            duration: duration_to_string(&interval_to_duration(&video.duration)),
+            is_primary: video.is_primary,
+        }
+    }
+}
+
+impl From<&NistIdentifier> for NistIdentifierRecord {
+    fn from(identifier: &NistIdentifier) -> Self {
+        NistIdentifierRecord {
+            id: identifier.id,
+            entity: identifier.entity.clone(),
+            entity_id: identifier.entity_id,
+            kind: identifier.kind.clone(),
+            value: identifier.value.clone(),
+            foia_response: identifier.foia_response.clone(),
+            imported_at: identifier.imported_at,
+        }
+    }
+}
+
+impl From<&NistNetworkMapping> for NistNetworkMappingRecord {
+    fn from(mapping: &NistNetworkMapping) -> Self {
+        NistNetworkMappingRecord {
+            nist_network: mapping.nist_network.clone(),
+            network_id: mapping.news_network.as_ref().map(|n| n.id),
+            affiliate_id: mapping.news_affiliate.as_ref().map(|a| a.id),
+        }
+    }
+}
+
+impl NistTapeRecord {
+    pub fn new(tape_id: i32, files: &[PathBuf]) -> Self {
+        let mut release_files: Vec<String> = files
+            .iter()
+            .map(|f| f.to_string_lossy().to_string())
+            .collect();
+        release_files.sort();
+        NistTapeRecord {
+            tape_id,
+            release_files,
+        }
+    }
+}
+
+impl NistVideoRecord {
+    pub fn new(video_id: i32, is_missing: bool, additional_notes: &str) -> Self {
+        NistVideoRecord {
+            video_id,
+            is_missing,
+            additional_notes: (!additional_notes.is_empty()).then(|| additional_notes.to_string()),
+        }
+    }
+}
+
+impl From<&Catalogue> for Dataset {
+    fn from(catalogue: &Catalogue) -> Self {
+        let mut dataset = Dataset {
+            affiliates: catalogue
+                .affiliates
+                .iter()
+                .map(AffiliateRecord::from)
+                .collect(),
+            broadcasts: catalogue
+                .broadcasts
+                .iter()
+                .map(BroadcastRecord::from)
+                .collect(),
+            event_types: catalogue
+                .event_types
+                .iter()
+                .map(EventTypeRecord::from)
+                .collect(),
+            masters: catalogue.masters.iter().map(MasterRecord::from).collect(),
+            networks: catalogue.networks.iter().map(NetworkRecord::from).collect(),
+            nist_identifiers: catalogue
+                .nist_identifiers
+                .iter()
+                .map(NistIdentifierRecord::from)
+                .collect(),
+            nist_network_mappings: catalogue
+                .nist_network_mappings
+                .iter()
+                .map(NistNetworkMappingRecord::from)
+                .collect(),
+            nist_tapes: catalogue
+                .nist_tapes
+                .iter()
+                .filter(|(_, files)| !files.is_empty())
+                .map(|(id, files)| NistTapeRecord::new(*id, files))
+                .collect(),
+            nist_videos: catalogue
+                .nist_videos
+                .iter()
+                .filter(|(_, is_missing, notes)| *is_missing || !notes.is_empty())
+                .map(|(id, is_missing, notes)| NistVideoRecord::new(*id, *is_missing, notes))
+                .collect(),
+            people: catalogue.people.iter().map(PersonRecord::from).collect(),
+            videos: catalogue.videos.iter().map(VideoRecord::from).collect(),
+        };
+        dataset.affiliates.sort_by_key(|r| r.id);
+        dataset.broadcasts.sort_by_key(|r| r.id);
+        dataset.event_types.sort_by(|a, b| a.slug.cmp(&b.slug));
+        dataset.masters.sort_by_key(|r| r.id);
+        dataset.networks.sort_by_key(|r| r.id);
+        dataset.nist_identifiers.sort_by_key(|r| r.id);
+        dataset
+            .nist_network_mappings
+            .sort_by(|a, b| a.nist_network.cmp(&b.nist_network));
+        dataset.nist_tapes.sort_by_key(|r| r.tape_id);
+        dataset.nist_videos.sort_by_key(|r| r.video_id);
+        dataset.people.sort_by_key(|r| r.id);
+        dataset.videos.sort_by_key(|r| r.id);
+        dataset
+    }
+}
+
+impl From<&MasterVideo> for MasterRecord {
+    fn from(master: &MasterVideo) -> Self {
+        let mut news_broadcast_ids: Vec<i32> =
+            master.news_broadcasts.iter().map(|b| b.id).collect();
+        news_broadcast_ids.sort();
+        let mut person_ids: Vec<i32> = master.people.iter().map(|p| p.id).collect();
+        person_ids.sort();
+        let mut nist_video_ids: Vec<i32> = master.nist_videos.iter().map(|v| v.video_id).collect();
+        nist_video_ids.sort();
+        let mut nist_files: Vec<String> = master
+            .nist_files
+            .iter()
+            .map(|(path, _)| path.to_string_lossy().to_string())
+            .collect();
+        nist_files.sort();
+        let mut timestamps: Vec<&EventTimestamp> = master.timestamps.iter().collect();
+        timestamps.sort_by_key(|t| (interval_to_duration(&t.timestamp), t.id));
+        MasterRecord {
+            id: master.id,
+            title: master.title.clone(),
+            date: master.date,
+            categories: master.categories.iter().map(|c| c.to_string()).collect(),
+            description: master.description.clone(),
+            links: master.links.clone(),
+            news_broadcast_ids,
+            person_ids,
+            nist_video_ids,
+            nist_notes: master.nist_notes.clone(),
+            nist_files,
+            timestamps: timestamps
+                .into_iter()
+                .map(|t| TimestampRecord {
+                    id: t.id,
+                    offset: duration_to_string(&interval_to_duration(&t.timestamp)),
+                    end_offset: t
+                        .end_timestamp
+                        .as_ref()
+                        .map(|e| duration_to_string(&interval_to_duration(e))),
+                    event_type: t.event_type.to_string(),
+                    time_of_day: t.time_of_day,
+                    description: t.description.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Notes any IDs that appear more than once for one kind of record.
+fn check_ids(kind: &str, ids: impl Iterator<Item = i32>, problems: &mut Vec<String>) {
+    let mut seen = HashSet::new();
+    for id in ids {
+        if !seen.insert(id) {
+            problems.push(format!("There is more than one {kind} with ID {id}"));
+        }
+    }
+}
+
+impl Dataset {
+    /// Converts the records to models, checking that every reference between them is valid.
+    ///
+    /// All the problems found are returned together, so they can be fixed in one pass.
+    pub fn resolve(&self) -> Result<Catalogue, Vec<String>> {
+        let mut problems = Vec::new();
+        check_ids("network", self.networks.iter().map(|r| r.id), &mut problems);
+        check_ids(
+            "affiliate",
+            self.affiliates.iter().map(|r| r.id),
+            &mut problems,
+        );
+        check_ids(
+            "broadcast",
+            self.broadcasts.iter().map(|r| r.id),
+            &mut problems,
+        );
+        check_ids("person", self.people.iter().map(|r| r.id), &mut problems);
+        check_ids(
+            "master video",
+            self.masters.iter().map(|r| r.id),
+            &mut problems,
+        );
+        check_ids("video", self.videos.iter().map(|r| r.id), &mut problems);
+        check_ids(
+            "NIST tape",
+            self.nist_tapes.iter().map(|r| r.tape_id),
+            &mut problems,
+        );
+        check_ids(
+            "NIST video",
+            self.nist_videos.iter().map(|r| r.video_id),
+            &mut problems,
+        );
+        check_ids(
+            "NIST identifier",
+            self.nist_identifiers.iter().map(|r| r.id),
+            &mut problems,
+        );
+        check_ids(
+            "timestamp",
+            self.masters
+                .iter()
+                .flat_map(|m| m.timestamps.iter().map(|t| t.id)),
+            &mut problems,
+        );
+
+        let mut event_types = Vec::new();
+        let mut slugs = HashSet::new();
+        for r in self.event_types.iter() {
+            if !slugs.insert(r.slug.as_str()) {
+                problems.push(format!("There is more than one event type '{}'", r.slug));
+            }
+            if !EventType::is_valid_slug(&r.slug) {
+                problems.push(format!(
+                    "Event type '{}' must only contain lowercase letters, numbers and hyphens",
+                    r.slug
+                ));
+            }
+            event_types.push(EventType {
+                slug: r.slug.clone(),
+                label: r.label.clone(),
+                description: r.description.clone(),
+                canonical_time: r.canonical_time,
+            });
+        }
+
+        let networks: Vec<NewsNetwork> = self
+            .networks
+            .iter()
+            .map(|r| NewsNetwork {
+                id: r.id,
+                name: r.name.clone(),
+                description: r.description.clone(),
+            })
+            .collect();
+
+        let mut affiliates = Vec::new();
+        for r in self.affiliates.iter() {
+            match networks.iter().find(|n| n.id == r.network_id) {
+                Some(network) => affiliates.push(NewsAffiliate {
+                    id: r.id,
+                    name: r.name.clone(),
+                    description: r.description.clone(),
+                    region: r.region.clone(),
+                    network: network.clone(),
+                }),
+                None => problems.push(format!(
+                    "Affiliate {} refers to network {}, which does not exist",
+                    r.id, r.network_id
+                )),
+            }
+        }
+
+        let mut broadcasts = Vec::new();
+        for r in self.broadcasts.iter() {
+            let news_network = r.network_id.and_then(|id| {
+                let network = networks.iter().find(|n| n.id == id).cloned();
+                if network.is_none() {
+                    problems.push(format!(
+                        "Broadcast {} refers to network {id}, which does not exist",
+                        r.id
+                    ));
+                }
+                network
+            });
+            let news_affiliate = r.affiliate_id.and_then(|id| {
+                let affiliate = affiliates.iter().find(|a| a.id == id).cloned();
+                if affiliate.is_none() {
+                    problems.push(format!(
+                        "Broadcast {} refers to affiliate {id}, which does not exist",
+                        r.id
+                    ));
+                }
+                affiliate
+            });
+            if r.network_id.is_some() == r.affiliate_id.is_some() {
+                problems.push(format!(
+                    "Broadcast {} must have either a network or an affiliate",
+                    r.id
+                ));
+            }
+            broadcasts.push(NewsBroadcast {
+                id: r.id,
+                date: r.date,
+                description: r.description.clone(),
+                news_network,
+                news_affiliate,
+            });
+        }
+
+        let mut people = Vec::new();
+        let mut names = HashSet::new();
+        for r in self.people.iter() {
+            if !names.insert(r.name.as_str()) {
+                problems.push(format!("There is more than one person named {}", r.name));
+            }
+            let mut types = Vec::new();
+            for t in r.types.iter() {
+                match parse_variant(t, &PERSON_TYPES) {
+                    Some(t) => types.push(t),
+                    None => problems.push(format!("Person {} has an invalid type '{t}'", r.id)),
+                }
+            }
+            people.push(Person {
+                id: r.id,
+                name: r.name.clone(),
+                historical_title: r.historical_title.clone(),
+                description: r.description.clone(),
+                types,
+            });
+        }
+
+        let mut masters = Vec::new();
+        for r in self.masters.iter() {
+            let mut categories = Vec::new();
+            for c in r.categories.iter() {
+                match parse_variant(c, &CATEGORIES) {
+                    Some(c) => categories.push(c),
+                    None => problems.push(format!(
+                        "Master video {} has an invalid category '{c}'",
+                        r.id
+                    )),
+                }
+            }
+            let mut news_broadcasts = Vec::new();
+            for id in r.news_broadcast_ids.iter() {
+                match broadcasts.iter().find(|b| b.id == *id) {
+                    Some(b) => news_broadcasts.push(b.clone()),
+                    None => problems.push(format!(
+                        "Master video {} refers to broadcast {id}, which does not exist",
+                        r.id
+                    )),
+                }
+            }
+            let mut master_people = Vec::new();
+            for id in r.person_ids.iter() {
+                match people.iter().find(|p| p.id == *id) {
+                    Some(p) => master_people.push(p.clone()),
+                    None => problems.push(format!(
+                        "Master video {} refers to person {id}, which does not exist",
+                        r.id
+                    )),
+                }
+            }
+            let mut timestamps = Vec::new();
+            for t in r.timestamps.iter() {
+                let Ok(event_type) = EventType::find(&t.event_type, &event_types) else {
+                    problems.push(format!(
+                        "Timestamp {} has an invalid event type '{}'",
+                        t.id, t.event_type
+                    ));
+                    continue;
+                };
+                let end_timestamp = match &t.end_offset {
+                    Some(end_offset) => match PgInterval::try_from(parse_duration(end_offset)) {
+                        Ok(end_timestamp) => Some(end_timestamp),
+                        Err(_) => {
+                            problems.push(format!(
+                                "Timestamp {} has an invalid end offset '{end_offset}'",
+                                t.id
+                            ));
+                            continue;
+                        }
+                    },
+                    None => None,
+                };
+                match PgInterval::try_from(parse_duration(&t.offset)) {
+                    Ok(timestamp) => timestamps.push(EventTimestamp {
+                        id: t.id,
+                        description: t.description.clone(),
+                        timestamp,
+                        end_timestamp,
+                        event_type,
+                        time_of_day: t.time_of_day,
+                    }),
+                    Err(_) => problems.push(format!(
+                        "Timestamp {} has an invalid offset '{}'",
+                        t.id, t.offset
+                    )),
+                }
+            }
+            masters.push(MasterVideo {
+                categories,
+                date: r.date,
+                description: r.description.clone(),
+                id: r.id,
+                links: r.links.clone(),
+                news_broadcasts,
+                nist_files: r.nist_files.iter().map(|f| (PathBuf::from(f), 0)).collect(),
+                nist_notes: r.nist_notes.clone(),
+                // Only the curated NIST videos are in the dataset, so the IDs are checked against
+                // the database when the dataset is imported.
+                nist_videos: r
+                    .nist_video_ids
+                    .iter()
+                    .map(|id| NistVideo {
+                        video_id: *id,
+                        ..Default::default()
+                    })
+                    .collect(),
+                people: master_people,
+                timestamps,
+                title: r.title.clone(),
+            });
+        }
+
+        let mut videos = Vec::new();
+        for r in self.videos.iter() {
+            let Some(master) = masters.iter().find(|m| m.id == r.master_id) else {
+                problems.push(format!(
+                    "Video {} refers to master video {}, which does not exist",
+                    r.id, r.master_id
+                ));
+                continue;
+            };
+            match PgInterval::try_from(parse_duration(&r.duration)) {
+                Ok(duration) => videos.push(Video {
+                    channel_username: r.channel.clone(),
+                    description: r.description.clone(),
+                    duration,
+                    id: r.id,
+                    is_primary: r.is_primary,
+                    link: r.link.clone(),
+                    master: master.clone(),
+                    title: r.title.clone(),
+                }),
+                Err(_) => problems.push(format!(
+                    "Video {} has an invalid duration '{}'",
+                    r.id, r.duration
+                )),
+            }
+        }
+
+        // Like the table they come from, the identifiers don't require the tape or video to have
+        // been imported, so only the kind of record they refer to is checked.
+        let entities = [
+            entity_name(RecordEntity::NistTape),
+            entity_name(RecordEntity::NistVideo),
+        ];
+        let mut nist_identifiers = Vec::new();
+        for r in self.nist_identifiers.iter() {
+            if !entities.contains(&r.entity) {
+                problems.push(format!(
+                    "NIST identifier {} is for '{}', which is not a NIST tape or video",
+                    r.id, r.entity
+                ));
+            }
+            nist_identifiers.push(NistIdentifier {
+                entity: r.entity.clone(),
+                entity_id: r.entity_id,
+                foia_response: r.foia_response.clone(),
+                id: r.id,
+                imported_at: r.imported_at,
+                kind: r.kind.clone(),
+                value: r.value.clone(),
+            });
+        }
+
+        let mut nist_network_mappings = Vec::new();
+        let mut nist_networks = HashSet::new();
+        for r in self.nist_network_mappings.iter() {
+            if !nist_networks.insert(r.nist_network.as_str()) {
+                problems.push(format!(
+                    "There is more than one mapping for the NIST network '{}'",
+                    r.nist_network
+                ));
+            }
+            let news_network = r.network_id.and_then(|id| {
+                let network = networks.iter().find(|n| n.id == id).cloned();
+                if network.is_none() {
+                    problems.push(format!(
+                        "The mapping for the NIST network '{}' refers to network {id}, which does \
+                         not exist",
+                        r.nist_network
+                    ));
+                }
+                network
+            });
+            let news_affiliate = r.affiliate_id.and_then(|id| {
+                let affiliate = affiliates.iter().find(|a| a.id == id).cloned();
+                if affiliate.is_none() {
+                    problems.push(format!(
+                        "The mapping for the NIST network '{}' refers to affiliate {id}, which \
+                         does not exist",
+                        r.nist_network
+                    ));
+                }
+                affiliate
+            });
+            // A mapping with neither records that the network was reviewed and has no match.
+            if r.network_id.is_some() && r.affiliate_id.is_some() {
+                problems.push(format!(
+                    "The mapping for the NIST network '{}' can't have both a network and an \
+                     affiliate",
+                    r.nist_network
+                ));
+            }
+            nist_network_mappings.push(NistNetworkMapping {
+                nist_network: r.nist_network.clone(),
+                news_affiliate,
+                news_network,
+            });
+        }
+
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+        Ok(Catalogue {
+            affiliates,
+            broadcasts,
+            event_types,
+            masters,
+            networks,
+            nist_identifiers,
+            nist_network_mappings,
+            nist_tapes: self
+                .nist_tapes
+                .iter()
+                .map(|r| {
+                    (
+                        r.tape_id,
+                        r.release_files.iter().map(PathBuf::from).collect(),
+                    )
+                })
+                .collect(),
+            nist_videos: self
+                .nist_videos
+                .iter()
+                .map(|r| {
+                    (
+                        r.video_id,
+                        r.is_missing,
+                        r.additional_notes.clone().unwrap_or_default(),
+                    )
+                })
+                .collect(),
+            people,
+            videos,
+        })
+    }
+
+    /// All the release file paths the records refer to.
+    pub fn release_file_paths(&self) -> Vec<String> {
+        let mut paths: Vec<String> = self
+            .masters
+            .iter()
+            .flat_map(|m| m.nist_files.iter().cloned())
+            .chain(
+                self.nist_tapes
+                    .iter()
+                    .flat_map(|t| t.release_files.iter().cloned()),
+            )
+            .collect();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+
+    /// Writes each record to its own YAML file, in a directory for each kind of record.
+    ///
+    /// Existing YAML files in those directories are removed first, so records that no longer exist
+    /// don't remain in the dataset.
+    pub fn write(&self, dir: &Path) -> Result<usize> {
+        let mut count = 0;
+        count += write_dir(dir, &self.event_types)?;
+        count += write_dir(dir, &self.networks)?;
+        count += write_dir(dir, &self.affiliates)?;
+        count += write_dir(dir, &self.broadcasts)?;
+        count += write_dir(dir, &self.people)?;
+        count += write_dir(dir, &self.masters)?;
+        count += write_dir(dir, &self.videos)?;
+        count += write_dir(dir, &self.nist_tapes)?;
+        count += write_dir(dir, &self.nist_videos)?;
+        count += write_dir(dir, &self.nist_identifiers)?;
+        count += write_dir(dir, &self.nist_network_mappings)?;
+        Ok(count)
+    }
+
+    /// Adds a record to the dataset, replacing any existing record with the same ID.
+    pub fn replace<T: DatasetRecord>(&mut self, record: T) {
+        let records = T::records(self);
+        match records.iter().position(|r| r.id() == record.id()) {
+            Some(i) => records[i] = record,
+            None => records.push(record),
+        }
+    }
+
+    pub fn read(dir: &Path) -> Result<Self> {
+        if !dir.is_dir() {
+            return Err(eyre!("{} is not a directory", dir.to_string_lossy()));
+        }
+        Ok(Dataset {
+            affiliates: read_dir(dir)?,
+            broadcasts: read_dir(dir)?,
+            event_types: read_dir(dir)?,
+            masters: read_dir(dir)?,
+            networks: read_dir(dir)?,
+            nist_identifiers: read_dir(dir)?,
+            nist_network_mappings: read_dir(dir)?,
+            nist_tapes: read_dir(dir)?,
+            nist_videos: read_dir(dir)?,
+            people: read_dir(dir)?,
+            videos: read_dir(dir)?,
+        })
+    }
+}
+
+fn is_yaml(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("yaml") | Some("yml")
+    )
+}
+
+fn write_dir<T: DatasetFile>(dir: &Path, records: &[T]) -> Result<usize> {
+    let path = dir.join(T::DIR);
+    let mut stems = HashSet::new();
+    for record in records.iter() {
+        let stem = record.file_stem();
+        if !stems.insert(stem.clone()) {
+            return Err(eyre!(
+                "More than one record in {} would be written to {stem}.yaml",
+                T::DIR
+            ));
+        }
+    }
+    std::fs::create_dir_all(&path)?;
+    for entry in std::fs::read_dir(&path)? {
+        let entry = entry?.path();
+        if is_yaml(&entry) {
+            std::fs::remove_file(entry)?;
+        }
+    }
+    for record in records.iter() {
+        let file_path = path.join(format!("{}.yaml", record.file_stem()));
+        std::fs::write(file_path, serde_yaml::to_string(record)?)?;
+    }
+    Ok(records.len())
+}
+
+fn read_dir<T: DatasetFile>(dir: &Path) -> Result<Vec<T>> {
+    let path = dir.join(T::DIR);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(&path)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<PathBuf>>>()?;
+    paths.retain(|p| is_yaml(p));
+    paths.sort();
+    let mut records = Vec::new();
+    for path in paths.iter() {
+        let record = serde_yaml::from_str(&std::fs::read_to_string(path)?)
+            .map_err(|e| eyre!("{}: {e}", path.to_string_lossy()))?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalogue() -> Catalogue {
+        let network = NewsNetwork {
+            id: 1,
+            name: "ABC News".to_string(),
+            description: "National ABC News coverage".to_string(),
+        };
+        let affiliate = NewsAffiliate {
+            id: 3,
+            name: "WABC-TV".to_string(),
+            description: "New York local coverage".to_string(),
+            region: "NYC".to_string(),
+            network: network.clone(),
+        };
+        let broadcast = NewsBroadcast {
+            id: 2,
+            date: NaiveDate::from_ymd_opt(2001, 9, 11).unwrap(),
+            description: "Local coverage".to_string(),
+            news_network: None,
+            news_affiliate: Some(affiliate.clone()),
+        };
+        let person = Person {
+            id: 5,
+            name: "John DelGiorno".to_string(),
+            historical_title: Some("Reporter for NewsCopter7".to_string()),
+            description: None,
+            types: vec![PersonType::Reporter, PersonType::PortAuthority],
+        };
+        let master = MasterVideo {
+            categories: vec![Category::News],
+            date: NaiveDate::from_ymd_opt(2001, 9, 11),
+            description: "WABC-TV coverage".to_string(),
+            id: 4,
+            links: vec!["https://example.com".to_string()],
+            news_broadcasts: vec![broadcast.clone()],
+            nist_files: Vec::new(),
+            nist_notes: None,
+            nist_videos: vec![NistVideo {
+                video_id: 12,
+                ..Default::default()
+            }],
+            people: vec![person.clone()],
+            timestamps: vec![
+                EventTimestamp {
+                    id: 8,
+                    description: "UA175 hits the South Tower".to_string(),
+                    timestamp: PgInterval::try_from(parse_duration("00:20:00")).unwrap(),
+                    end_timestamp: None,
+                    event_type: event_type("wtc2-impact"),
+                    time_of_day: NaiveTime::from_hms_opt(9, 3, 2),
+                },
+                EventTimestamp {
+                    id: 7,
+                    description: "Coverage commences".to_string(),
+                    timestamp: PgInterval::try_from(parse_duration("00:08:05")).unwrap(),
+                    end_timestamp: None,
+                    event_type: event_type("normal"),
+                    time_of_day: None,
+                },
+            ],
+            title: "WABC-TV: 9/11 Broadcast".to_string(),
+        };
+        Catalogue {
+            affiliates: vec![affiliate],
+            broadcasts: vec![broadcast],
+            event_types: event_types(),
+            masters: vec![master],
+            networks: vec![network.clone()],
+            nist_identifiers: Vec::new(),
+            nist_network_mappings: vec![NistNetworkMapping {
+                nist_network: "ABC/WABC".to_string(),
+                news_affiliate: None,
+                news_network: Some(network.clone()),
+            }],
+            nist_tapes: vec![(10, Vec::new())],
+            nist_videos: vec![(11, true, String::new()), (12, false, String::new())],
+            people: vec![person],
+            videos: Vec::new(),
+        }
+    }
+
+    fn event_type(slug: &str) -> EventType {
+        EventType {
+            slug: slug.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn event_types() -> Vec<EventType> {
+        vec![event_type("normal"), event_type("wtc2-impact")]
+    }
+
+    #[test]
+    fn dataset_should_round_trip_through_a_directory() {
+        let dataset = Dataset::from(&catalogue());
+        assert_eq!(dataset.masters[0].timestamps[0].id, 7);
+        assert!(dataset.nist_tapes.is_empty());
+        assert_eq!(dataset.nist_videos.len(), 1);
+
+        let dir = std::env::temp_dir().join(format!("dataset-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(dataset.write(&dir).unwrap(), 9);
+        assert!(dir.join("nist_network_mappings/abc-wabc.yaml").exists());
+        let written = std::fs::read_to_string(dir.join("people/0005.yaml")).unwrap();
+        assert_eq!(
+            written,
+            "id: 5\nname: John DelGiorno\nhistorical_title: Reporter for NewsCopter7\n\
+             types:\n- Reporter\n- Port Authority\n"
+        );
+
+        let read = Dataset::read(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(read, dataset);
+        let resolved = read.resolve().unwrap();
+        assert_eq!(Dataset::from(&resolved), dataset);
+    }
+
+    #[test]
+    fn resolve_should_report_every_invalid_reference() {
+        let mut dataset = Dataset::from(&catalogue());
+        dataset.affiliates[0].network_id = 9;
+        dataset.masters[0].person_ids.push(6);
+        dataset.masters[0].timestamps[0].event_type = "explosion".to_string();
+        dataset.videos.push(VideoRecord {
+            id: 1,
+            master_id: 2,
+            title: "Video".to_string(),
+            channel: "channel".to_string(),
+            description: None,
+            link: "https://example.com".to_string(),
+            duration: "01:00:00".to_string(),
+            is_primary: true,
+        });
+        let problems = dataset.resolve().err().unwrap();
+        assert_eq!(
+            problems,
+            vec![
+                "Affiliate 3 refers to network 9, which does not exist",
+                "Broadcast 2 refers to affiliate 3, which does not exist",
+                "Master video 4 refers to person 6, which does not exist",
+                "Timestamp 7 has an invalid event type 'explosion'",
+                "Video 1 refers to master video 2, which does not exist",
+            ]
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use colored::Colorize;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiffLine<'a> {
+    Added(&'a str),
+    Removed(&'a str),
+    Unchanged(&'a str),
+}
+
+/// Compares two texts line by line, using the longest common subsequence of lines.
+pub fn diff_lines<'a>(before: &'a str, after: &'a str) -> Vec<DiffLine<'a>> {
+    let a: Vec<&str> = before.lines().collect();
+    let b: Vec<&str> = after.lines().collect();
+
+    // lengths[i][j] is the length of the longest common subsequence of a[i..] and b[j..].
+    let mut lengths = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            lines.push(DiffLine::Unchanged(a[i]));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            lines.push(DiffLine::Removed(a[i]));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(b[j]));
+            j += 1;
+        }
+    }
+    lines.extend(a[i..].iter().map(|l| DiffLine::Removed(l)));
+    lines.extend(b[j..].iter().map(|l| DiffLine::Added(l)));
+    lines
+}
+
+/// Prints the changed lines of a diff, along with the given number of unchanged lines around each
+/// change.
+pub fn print_diff(lines: &[DiffLine], context: usize) {
+    let changed: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| !matches!(l, DiffLine::Unchanged(_)))
+        .map(|(i, _)| i)
+        .collect();
+    let mut last_printed = None;
+    for (i, line) in lines.iter().enumerate() {
+        let near_change = changed
+            .iter()
+            .any(|c| i + context >= *c && i <= c + context);
+        if !near_change {
+            continue;
+        }
+        if last_printed.is_some_and(|last| i > last + 1) {
+            println!("{}", "...".dimmed());
+        }
+        match line {
+            DiffLine::Added(l) => println!("{}", format!("+ {l}").green()),
+            DiffLine::Removed(l) => println!("{}", format!("- {l}").red()),
+            DiffLine::Unchanged(l) => println!("  {l}"),
+        }
+        last_printed = Some(i);
+    }
+}
+
+/// For each line of `before`, the index of the same line in `after`, if it was kept.
+fn matching_lines(before: &str, after: &str) -> Vec<Option<usize>> {
+    let mut matches = Vec::new();
+    let mut j = 0;
+    for line in diff_lines(before, after) {
+        match line {
+            DiffLine::Added(_) => j += 1,
+            DiffLine::Removed(_) => matches.push(None),
+            DiffLine::Unchanged(_) => {
+                matches.push(Some(j));
+                j += 1;
+            }
+        }
+    }
+    matches
+}
+
+/// Merges two sets of changes made to the same text, in the same way as `diff3`.
+///
+/// Lines changed on only one side are taken from that side. Where both sides changed the same
+/// lines in different ways, both versions are kept between conflict markers. The merged text is
+/// returned with the number of conflicts.
+pub fn merge3(base: &str, ours: &str, theirs: &str) -> (String, usize) {
+    let base_lines: Vec<&str> = base.lines().collect();
+    let our_lines: Vec<&str> = ours.lines().collect();
+    let their_lines: Vec<&str> = theirs.lines().collect();
+    let ours_matches = matching_lines(base, ours);
+    let theirs_matches = matching_lines(base, theirs);
+
+    let mut merged: Vec<&str> = Vec::new();
+    let mut conflicts = 0;
+    let (mut i, mut j, mut k) = (0, 0, 0);
+    loop {
+        // The next line that neither side changed.
+        let stable =
+            (i..base_lines.len()).find_map(|b| Some((b, ours_matches[b]?, theirs_matches[b]?)));
+        let (b, o, t) = stable.unwrap_or((base_lines.len(), our_lines.len(), their_lines.len()));
+
+        let base_chunk = &base_lines[i..b];
+        let our_chunk = &our_lines[j..o];
+        let their_chunk = &their_lines[k..t];
+        if our_chunk == base_chunk || our_chunk == their_chunk {
+            merged.extend(their_chunk);
+        } else if their_chunk == base_chunk {
+            merged.extend(our_chunk);
+        } else {
+            conflicts += 1;
+            merged.push("<<<<<<< yours");
+            merged.extend(our_chunk);
+            merged.push("=======");
+            merged.extend(their_chunk);
+            merged.push(">>>>>>> theirs");
+        }
+
+        if stable.is_none() {
+            break;
+        }
+        merged.push(base_lines[b]);
+        (i, j, k) = (b + 1, o + 1, t + 1);
+    }
+    (merged.join("\n"), conflicts)
+}
+
+/// Whether a merged text still has conflict markers in it.
+pub fn has_conflict_markers(text: &str) -> bool {
+    text.lines()
+        .any(|l| l.starts_with("<<<<<<< ") || l == "=======" || l.starts_with(">>>>>>> "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_lines_should_find_added_and_removed_lines() {
+        let before = "id: 1\nname: ABC\ndescription: News\nregion: NYC";
+        let after = "id: 1\nname: ABC News\ndescription: News\nregion: NYC\nnotes: x";
+        assert_eq!(
+            diff_lines(before, after),
+            vec![
+                DiffLine::Unchanged("id: 1"),
+                DiffLine::Removed("name: ABC"),
+                DiffLine::Added("name: ABC News"),
+                DiffLine::Unchanged("description: News"),
+                DiffLine::Unchanged("region: NYC"),
+                DiffLine::Added("notes: x"),
+            ]
+        );
+    }
+
+    #[test]
+    fn merge3_should_combine_changes_to_different_lines() {
+        let base = "title: A\ndate: 2001-09-11\ndescription: x\nnotes: y";
+        let ours = "title: B\ndate: 2001-09-11\ndescription: x\nnotes: y";
+        let theirs = "title: A\ndate: 2001-09-11\ndescription: x\nnotes: z\nlinks: l";
+        assert_eq!(
+            merge3(base, ours, theirs),
+            (
+                "title: B\ndate: 2001-09-11\ndescription: x\nnotes: z\nlinks: l".to_string(),
+                0
+            )
+        );
+    }
+
+    #[test]
+    fn merge3_should_mark_conflicting_changes() {
+        let base = "title: A\ndescription: x";
+        let ours = "title: B\ndescription: x";
+        let theirs = "title: C\ndescription: x";
+        let (merged, conflicts) = merge3(base, ours, theirs);
+        assert_eq!(conflicts, 1);
+        assert_eq!(
+            merged,
+            "<<<<<<< yours\ntitle: B\n=======\ntitle: C\n>>>>>>> theirs\ndescription: x"
+        );
+        assert!(has_conflict_markers(&merged));
+        assert!(!has_conflict_markers(base));
+    }
+}
@@ -2,6 +2,12 @@ use crate::editing::forms::FormError;
 use color_eyre::Result;
 use std::any::Any;
 
+/// The markers written above the choices of a field, which aren't part of its value.
+pub const CHOOSE_ONE: &str = "## CHOOSE ONE ##";
+pub const CHOOSE_ONE_OR_DELETE_ALL: &str = "## CHOOSE ONE OR DELETE ALL ##";
+pub const CHOOSE_ONE_OR_NONE: &str = "## CHOOSE ONE OR NONE ##";
+pub const CHOICE_MARKERS: [&str; 3] = [CHOOSE_ONE, CHOOSE_ONE_OR_DELETE_ALL, CHOOSE_ONE_OR_NONE];
+
 pub trait FormField: Any {
     fn name(&self) -> &str;
     fn value(&self) -> String;
@@ -28,7 +34,7 @@ impl TextField {
             return Err(FormError::MalformedField(name.to_string()));
         }
         let val = input
-            .trim_start_matches(&format!("{name}: "))
+            .trim_start_matches(&format!("{name}:"))
             .trim()
             .to_string();
         if val.is_empty() {
@@ -66,6 +72,59 @@ impl FormField for TextField {
     }
 }
 
+pub struct OptionalTextField {
+    pub name: String,
+    pub value: String,
+}
+
+impl OptionalTextField {
+    pub fn new(name: &str, value: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    pub fn from_input_str(name: &str, input: &str) -> Result<Self, FormError> {
+        if !input.contains(name) {
+            return Err(FormError::MalformedField(name.to_string()));
+        }
+        let val = input
+            .trim_start_matches(&format!("{name}:"))
+            .trim()
+            .to_string();
+        Ok(OptionalTextField::new(name, &val))
+    }
+}
+
+impl FormField for OptionalTextField {
+    fn value(&self) -> String {
+        self.value.clone()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn as_string(&self) -> String {
+        let mut s = String::new();
+        s.push_str(&format!("{}:", self.name()));
+        let val = self.value();
+        if !val.is_empty() {
+            s.push_str(&format!(" {}", val));
+        }
+        s
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
 pub struct MultilineTextField {
     pub name: String,
     pub value: String,
@@ -188,9 +247,9 @@ impl FormField for OptionalChoiceListField {
         let mut s = String::new();
         s.push_str(&format!("{}:", self.name()));
         let val = self.value();
-        if val.is_empty() {
+        if val.is_empty() && !self.choices.is_empty() {
             s.push('\n');
-            s.push_str("## CHOOSE ONE OR DELETE ALL ##\n");
+            s.push_str(&format!("{CHOOSE_ONE_OR_DELETE_ALL}\n"));
             for choice in self.choices.iter() {
                 s.push_str(choice);
                 s.push('\n');
@@ -229,7 +288,7 @@ impl OptionalChoiceListField {
         }
 
         let name_stripped = input
-            .trim_start_matches(&format!("{}: ", name))
+            .trim_start_matches(&format!("{name}:"))
             .trim()
             .to_string();
         let values = if name_stripped.is_empty() {
@@ -264,7 +323,7 @@ impl ListField {
         }
 
         let name_stripped = input
-            .trim_start_matches(&format!("{}: ", name))
+            .trim_start_matches(&format!("{name}:"))
             .trim()
             .to_string();
         let values = if name_stripped.is_empty() {
@@ -417,7 +476,7 @@ impl FormField for MultilineListField {
 }
 
 impl MultilineListField {
-    fn new(name: &str, values: &[String]) -> Self {
+    pub fn new(name: &str, values: &[String]) -> Self {
         Self {
             name: name.to_string(),
             values: values.to_owned(),
@@ -520,7 +579,7 @@ impl FormField for ChoiceField {
         let val = self.value();
         if val.is_empty() {
             s.push('\n');
-            s.push_str("## CHOOSE ONE ##\n");
+            s.push_str(&format!("{CHOOSE_ONE}\n"));
             for choice in self.choices.iter() {
                 s.push_str(choice);
                 s.push('\n');
@@ -558,7 +617,7 @@ impl ChoiceField {
             return Err(FormError::MalformedField(name.to_string()));
         }
         let val = input
-            .trim_start_matches(&format!("{name}: "))
+            .trim_start_matches(&format!("{name}:"))
             .trim()
             .to_string();
         if val.is_empty() {
@@ -592,7 +651,7 @@ impl FormField for OptionalChoiceField {
             // The check here is to support edits, where the initial choice has already been made.
             // On edit commands, the choices don't get populated.
             if !self.choices.is_empty() {
-                s.push_str("## CHOOSE ONE OR NONE ##\n");
+                s.push_str(&format!("{CHOOSE_ONE_OR_NONE}\n"));
                 for choice in self.choices.iter() {
                     s.push_str(choice);
                     s.push('\n');
@@ -1,4 +1,5 @@
 use super::fields::{ChoiceField, FormField, OptionalChoiceField, OptionalChoiceListField};
+use super::schema::{escape_separators, ChoicesSource, FieldValue, FormSchema};
 use color_eyre::Result;
 use thiserror::Error;
 
@@ -6,23 +7,128 @@ use thiserror::Error;
 pub enum FormError {
     #[error("There are no choice fields named {0}")]
     ChoiceFieldNotFound(String),
+    #[error("The {0} field appears more than once")]
+    DuplicateField(String),
     #[error("Field {0} not found")]
     FieldNotFound(String),
     #[error("Field {0} is of incorrect type")]
     IncorrectType(String),
+    #[error("'{0}' is not one of the available choices")]
+    InvalidChoice(String),
+    #[error("Invalid timestamp: {0}")]
+    InvalidTimestamp(String),
     #[error("Invalid value for field {0}")]
     InvalidValue(String),
+    #[error("Line {line}, column {column}: {error}: '{text}'")]
+    Invalid {
+        column: usize,
+        error: Box<FormError>,
+        line: usize,
+        text: String,
+    },
     #[error("The {0} field is not in the expected form")]
     MalformedField(String),
-    #[error("The form string is not in the expected form")]
-    MalformedForm,
     #[error("The {0} field requires at least one value")]
     RequiredFieldEmpty(String),
+    #[error("'{0}' is not an existing person, but is close to {1}")]
+    SimilarPerson(String, String),
+    #[error(
+        "The text does not begin with the label of a field. Use \\--- for a separator within text."
+    )]
+    UnknownField(String),
+}
+
+impl FormError {
+    /// Moves the location of the error down by the given number of lines.
+    ///
+    /// This is for when lines are inserted above the text the form was parsed from.
+    pub fn shift_lines(self, count: usize) -> Self {
+        match self {
+            FormError::Invalid {
+                column,
+                error,
+                line,
+                text,
+            } => FormError::Invalid {
+                column,
+                error,
+                line: line + count,
+                text,
+            },
+            error => error,
+        }
+    }
+}
+
+/// The lines of a completed form that belong to a field, along with their line numbers.
+#[derive(Clone, Debug, Default)]
+pub struct FieldSection {
+    pub lines: Vec<(usize, String)>,
+    pub name: String,
+}
+
+impl FieldSection {
+    pub fn text(&self) -> String {
+        self.lines
+            .iter()
+            .map(|(_, l)| l.as_str())
+            .collect::<Vec<&str>>()
+            .join("\n")
+    }
+
+    /// Attaches the location of the given text within the field to an error.
+    ///
+    /// If the text can't be found, the error points at the label of the field.
+    pub fn locate(&self, text: &str, error: FormError) -> FormError {
+        let found = if text.is_empty() {
+            None
+        } else {
+            self.lines
+                .iter()
+                .find_map(|(n, l)| l.find(text).map(|i| (*n, l[..i].chars().count() + 1)))
+        };
+        match found {
+            Some((line, column)) => FormError::Invalid {
+                column,
+                error: Box::new(error),
+                line,
+                text: text.to_string(),
+            },
+            None => self.locate_value(error),
+        }
+    }
+
+    /// Attaches the location of the value of the field to an error.
+    pub fn locate_value(&self, error: FormError) -> FormError {
+        let Some((line, first)) = self.lines.first() else {
+            return error;
+        };
+        let value = first
+            .trim_start_matches(&format!("{}:", self.name))
+            .trim_start();
+        let (column, text) = if value.is_empty() {
+            (1, first.clone())
+        } else {
+            (
+                first.chars().count() - value.chars().count() + 1,
+                value.to_string(),
+            )
+        };
+        FormError::Invalid {
+            column,
+            error: Box::new(error),
+            line: *line,
+            text,
+        }
+    }
 }
 
 #[derive(Default)]
 pub struct Form {
     pub fields: Vec<Box<dyn FormField>>,
+    pub schema: Option<&'static FormSchema>,
+    /// The lines each field was parsed from. Empty for forms that weren't parsed from text.
+    pub sections: Vec<FieldSection>,
 }
 
 impl Form {
@@ -30,6 +136,15 @@ impl Form {
         self.fields.push(field);
     }
 
+    /// Attaches the location of some text within a field to an error found when interpreting the
+    /// value of the field.
+    pub fn locate(&self, field_name: &str, text: &str, error: FormError) -> FormError {
+        match self.sections.iter().find(|s| s.name == field_name) {
+            Some(section) => section.locate(text, error),
+            None => error,
+        }
+    }
+
     pub fn get_field(&self, name: &str) -> Result<&dyn FormField, FormError> {
         self.fields
             .iter()
@@ -62,6 +177,21 @@ impl Form {
             .ok_or_else(|| FormError::IncorrectType(name.to_string()))
     }
 
+    /// Replaces the value of a field, using its definition in the schema.
+    pub fn set_value(&mut self, name: &str, value: &FieldValue) -> Result<(), FormError> {
+        let def = self
+            .schema
+            .and_then(|s| s.fields.iter().find(|f| f.name == name))
+            .ok_or_else(|| FormError::FieldNotFound(name.to_string()))?;
+        let field = self
+            .fields
+            .iter_mut()
+            .find(|f| f.name() == name)
+            .ok_or_else(|| FormError::FieldNotFound(name.to_string()))?;
+        *field = def.create(value);
+        Ok(())
+    }
+
     pub fn add_choices(&mut self, field_name: &str, choices: Vec<String>) -> Result<(), FormError> {
         if let Ok(field) = self.get_field_as_mut::<OptionalChoiceListField>(field_name) {
             field.add_choices(choices.clone());
@@ -79,6 +209,21 @@ impl Form {
         Err(FormError::ChoiceFieldNotFound(field_name.to_string()))
     }
 
+    /// Adds the choices to every field in the form that takes its choices from the given source.
+    pub fn populate_choices(
+        &mut self,
+        source: ChoicesSource,
+        choices: Vec<String>,
+    ) -> Result<(), FormError> {
+        let Some(schema) = self.schema else {
+            return Ok(());
+        };
+        for def in schema.fields.iter().filter(|f| f.choices == Some(source)) {
+            self.add_choices(def.name, choices.clone())?;
+        }
+        Ok(())
+    }
+
     pub fn print(&self) {
         let s = self.as_string();
         println!("{s}");
@@ -87,7 +232,7 @@ impl Form {
     pub fn as_string(&self) -> String {
         let mut s = String::new();
         for i in 0..self.fields.len() {
-            s.push_str(&self.fields[i].as_string());
+            s.push_str(&escape_separators(&self.fields[i].as_string()));
             if i < self.fields.len() - 1 {
                 s.push_str("\n---\n");
             }
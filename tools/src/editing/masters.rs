@@ -1,264 +1,253 @@
 use super::fields::{
-    ListField, MultilineTextField, OptionalChoiceListField, OptionalListField,
-    OptionalMultilineListField, OptionalMultilineTextField, TextField,
+    ListField, OptionalChoiceListField, OptionalListField, OptionalMultilineListField,
 };
+use super::schema::{ChoicesSource, FieldDef, FieldKind, FieldValue, FormSchema};
 use super::{
     forms::{Form, FormError},
     get_people_from_input,
 };
 
-use color_eyre::{eyre::eyre, Result};
-use db::models::{Category, EventTimestamp, MasterVideo, NewsBroadcast, Person, PersonType};
+use crate::times::{check_timestamps, TimestampIssue};
+use chrono::Duration;
+use color_eyre::Result;
+use db::models::{
+    Category, EventTimestamp, EventType, MasterVideo, NewsBroadcast, NistNetworkMapping, NistVideo,
+    Person, PersonType,
+};
+use std::io::Write;
 use std::path::PathBuf;
 
+pub static MASTER_VIDEO_FORM: FormSchema = FormSchema {
+    fields: &[
+        FieldDef::optional("News Broadcasts", FieldKind::ChoiceList)
+            .with_choices(ChoicesSource::NewsBroadcasts),
+        FieldDef::required("Title", FieldKind::Text),
+        FieldDef::required("Categories", FieldKind::List).with_choices(ChoicesSource::Categories),
+        FieldDef::required("Date", FieldKind::Text),
+        FieldDef::required("Description", FieldKind::MultilineText),
+        FieldDef::optional("Links", FieldKind::List),
+        FieldDef::optional("Timestamps", FieldKind::MultilineList),
+        FieldDef::optional("NIST Videos", FieldKind::ChoiceList)
+            .with_choices(ChoicesSource::NistVideos),
+        FieldDef::optional("NIST Notes", FieldKind::MultilineText),
+        FieldDef::optional("Eyewitnesses", FieldKind::List)
+            .with_choices(ChoicesSource::People(PersonType::Eyewitness)),
+        FieldDef::optional("Fire", FieldKind::List)
+            .with_choices(ChoicesSource::People(PersonType::Fire)),
+        FieldDef::optional("Police", FieldKind::List)
+            .with_choices(ChoicesSource::People(PersonType::Police)),
+        FieldDef::optional("Port Authority", FieldKind::List)
+            .with_choices(ChoicesSource::People(PersonType::PortAuthority)),
+        FieldDef::optional("Reporters", FieldKind::List)
+            .with_choices(ChoicesSource::People(PersonType::Reporter)),
+        FieldDef::optional("Survivors", FieldKind::List)
+            .with_choices(ChoicesSource::People(PersonType::Survivor)),
+        FieldDef::optional("Victims", FieldKind::List)
+            .with_choices(ChoicesSource::People(PersonType::Victim)),
+        FieldDef::optional("Videographers", FieldKind::List)
+            .with_choices(ChoicesSource::People(PersonType::Videographer)),
+        FieldDef::optional("NIST Files", FieldKind::MultilineList),
+    ],
+};
+
+const PEOPLE_FIELDS: [(&str, PersonType); 8] = [
+    ("Eyewitnesses", PersonType::Eyewitness),
+    ("Fire", PersonType::Fire),
+    ("Police", PersonType::Police),
+    ("Port Authority", PersonType::PortAuthority),
+    ("Reporters", PersonType::Reporter),
+    ("Survivors", PersonType::Survivor),
+    ("Victims", PersonType::Victim),
+    ("Videographers", PersonType::Videographer),
+];
+
 impl Form {
     pub fn from_master_video_str(s: &str) -> Result<Self, FormError> {
-        let parts: Vec<_> = s.split("---\n").collect();
-        if parts.len() != 17 {
-            return Err(FormError::MalformedForm);
+        MASTER_VIDEO_FORM.parse(s)
+    }
+}
+
+impl From<&MasterVideo> for Form {
+    fn from(model: &MasterVideo) -> Self {
+        let mut values = vec![
+            (
+                "News Broadcasts",
+                model
+                    .news_broadcasts
+                    .iter()
+                    .map(|b| b.to_string())
+                    .collect::<Vec<String>>()
+                    .into(),
+            ),
+            ("Title", model.title.clone().into()),
+            (
+                "Categories",
+                model
+                    .categories
+                    .iter()
+                    .map(|c| c.to_string())
+                    .collect::<Vec<String>>()
+                    .into(),
+            ),
+            (
+                "Date",
+                model.date.map_or("".to_string(), |d| d.to_string()).into(),
+            ),
+            ("Description", model.description.clone().into()),
+            ("Links", model.links.clone().into()),
+            (
+                "Timestamps",
+                model
+                    .timestamps
+                    .iter()
+                    .map(|c| c.to_string())
+                    .collect::<Vec<String>>()
+                    .into(),
+            ),
+            (
+                "NIST Videos",
+                model
+                    .nist_videos
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<String>>()
+                    .into(),
+            ),
+            (
+                "NIST Notes",
+                model.nist_notes.clone().unwrap_or_default().into(),
+            ),
+            (
+                "NIST Files",
+                model
+                    .nist_files
+                    .iter()
+                    .map(|c| c.0.to_string_lossy().to_string())
+                    .collect::<Vec<String>>()
+                    .into(),
+            ),
+        ];
+        for (name, person_type) in PEOPLE_FIELDS.iter() {
+            values.push((
+                *name,
+                model
+                    .people
+                    .iter()
+                    .filter(|p| p.types.contains(person_type))
+                    .map(|p| p.name.clone())
+                    .collect::<Vec<String>>()
+                    .into(),
+            ));
         }
+        MASTER_VIDEO_FORM.create(values)
+    }
+}
+
+/// What to do when a name on a master video form isn't an existing person, but is close to the
+/// name of one.
+///
+/// This is usually a typo, which would otherwise add a duplicate of the person.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SimilarNames {
+    /// List the close matches and ask whether to add a new person.
+    Confirm,
+    /// Treat the name as an error.
+    Reject,
+}
 
-        let mut form = Form::default();
+/// Names are compared ignoring case, spacing and punctuation.
+fn normalise_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
 
-        form.add_field(Box::new(OptionalChoiceListField::from_input_str(
-            "News Broadcasts",
-            parts[0],
-        )?));
-        form.add_field(Box::new(TextField::from_input_str("Title", parts[1])?));
-        form.add_field(Box::new(ListField::from_input_str("Categories", parts[2])?));
-        form.add_field(Box::new(TextField::from_input_str("Date", parts[3])?));
-        form.add_field(Box::new(MultilineTextField::from_input_str(
-            "Description",
-            parts[4],
-        )?));
-        form.add_field(Box::new(OptionalListField::from_input_str(
-            "Links", parts[5],
-        )?));
-        form.add_field(Box::new(OptionalMultilineListField::from_input_str(
-            "Timestamps",
-            parts[6],
-        )?));
-        form.add_field(Box::new(OptionalMultilineTextField::from_input_str(
-            "NIST Notes",
-            parts[7],
-        )?));
-        form.add_field(Box::new(OptionalListField::from_input_str(
-            "Eyewitnesses",
-            parts[8],
-        )?));
-        form.add_field(Box::new(OptionalListField::from_input_str(
-            "Fire", parts[9],
-        )?));
-        form.add_field(Box::new(OptionalListField::from_input_str(
-            "Police", parts[10],
-        )?));
-        form.add_field(Box::new(OptionalListField::from_input_str(
-            "Port Authority",
-            parts[11],
-        )?));
-        form.add_field(Box::new(OptionalListField::from_input_str(
-            "Reporters",
-            parts[12],
-        )?));
-        form.add_field(Box::new(OptionalListField::from_input_str(
-            "Survivors",
-            parts[13],
-        )?));
-        form.add_field(Box::new(OptionalListField::from_input_str(
-            "Victims", parts[14],
-        )?));
-        form.add_field(Box::new(OptionalListField::from_input_str(
-            "Videographers",
-            parts[15],
-        )?));
-        form.add_field(Box::new(OptionalMultilineListField::from_input_str(
-            "NIST Files",
-            parts[16],
-        )?));
+/// Finds the people whose names are close to the given name, closest first.
+///
+/// Names that differ only in case, spacing or punctuation, or by a letter or two, are close.
+pub fn find_similar_people<'a>(name: &str, people: &'a [Person]) -> Vec<&'a Person> {
+    let name = normalise_name(name);
+    let mut similar: Vec<(f64, &Person)> = people
+        .iter()
+        .map(|p| {
+            let score = strsim::normalized_damerau_levenshtein(&name, &normalise_name(&p.name));
+            (score, p)
+        })
+        .filter(|(score, _)| *score >= 0.8)
+        .collect();
+    similar.sort_by(|a, b| b.0.total_cmp(&a.0));
+    similar.into_iter().map(|(_, p)| p).collect()
+}
 
-        Ok(form)
+fn confirm_new_person(name: &str, similar: &[&Person]) -> Result<bool> {
+    println!("'{name}' is not an existing person, but is close to:");
+    for person in similar.iter() {
+        match &person.description {
+            Some(description) => println!("  {}: {}", person.name, description),
+            None => println!("  {}", person.name),
+        }
     }
+    println!("Add '{name}' as a new person? [y/n]");
+    std::io::stdout().flush()?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_lowercase() == "y")
 }
 
-impl From<&MasterVideo> for Form {
-    fn from(model: &MasterVideo) -> Self {
-        let mut form = Form::default();
+fn locate_timestamp_issue(form: &Form, input: &str, issue: &TimestampIssue) -> FormError {
+    form.locate(
+        "Timestamps",
+        input,
+        FormError::InvalidTimestamp(issue.to_string()),
+    )
+}
 
-        form.add_field(Box::new(OptionalChoiceListField::new(
-            "News Broadcasts",
-            &model
-                .news_broadcasts
-                .iter()
-                .map(|b| b.to_string())
-                .collect::<Vec<String>>(),
-        )));
-        form.add_field(Box::new(TextField::new("Title", &model.title)));
-        form.add_field(Box::new(ListField::new(
-            "Categories",
-            &model
-                .categories
-                .iter()
-                .map(|c| c.to_string())
-                .collect::<Vec<String>>(),
-        )));
-        form.add_field(Box::new(TextField::new(
-            "Date",
-            &model.date.map_or("".to_string(), |d| d.to_string()),
-        )));
-        form.add_field(Box::new(MultilineTextField::new(
-            "Description",
-            &model.description,
-        )));
-        form.add_field(Box::new(OptionalListField::new(
-            "Links",
-            &model
-                .categories
-                .iter()
-                .map(|c| c.to_string())
-                .collect::<Vec<String>>(),
-        )));
-        form.add_field(Box::new(OptionalMultilineListField::new(
-            "Timestamps",
-            &model
-                .timestamps
-                .iter()
-                .map(|c| c.to_string())
-                .collect::<Vec<String>>(),
-        )));
-        form.add_field(Box::new(OptionalMultilineTextField::new(
-            "NIST Notes",
-            model.nist_notes.as_ref().unwrap_or(&"".to_string()),
-        )));
-        form.add_field(Box::new(ListField::new(
-            "Eyewitnesses",
-            &model
-                .people
-                .iter()
-                .filter_map(|p| {
-                    if p.types.contains(&PersonType::Eyewitness) {
-                        Some(p.name.clone())
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<String>>(),
-        )));
-        form.add_field(Box::new(ListField::new(
-            "Fire",
-            &model
-                .people
-                .iter()
-                .filter_map(|p| {
-                    if p.types.contains(&PersonType::Fire) {
-                        Some(p.name.clone())
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<String>>(),
-        )));
-        form.add_field(Box::new(ListField::new(
-            "Police",
-            &model
-                .people
-                .iter()
-                .filter_map(|p| {
-                    if p.types.contains(&PersonType::Police) {
-                        Some(p.name.clone())
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<String>>(),
-        )));
-        form.add_field(Box::new(ListField::new(
-            "Port Authority",
-            &model
-                .people
-                .iter()
-                .filter_map(|p| {
-                    if p.types.contains(&PersonType::PortAuthority) {
-                        Some(p.name.clone())
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<String>>(),
-        )));
-        form.add_field(Box::new(ListField::new(
-            "Reporters",
-            &model
-                .people
-                .iter()
-                .filter_map(|p| {
-                    if p.types.contains(&PersonType::Reporter) {
-                        Some(p.name.clone())
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<String>>(),
-        )));
-        form.add_field(Box::new(ListField::new(
-            "Survivors",
-            &model
-                .people
-                .iter()
-                .filter_map(|p| {
-                    if p.types.contains(&PersonType::Survivor) {
-                        Some(p.name.clone())
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<String>>(),
-        )));
-        form.add_field(Box::new(ListField::new(
-            "Victims",
-            &model
-                .people
-                .iter()
-                .filter_map(|p| {
-                    if p.types.contains(&PersonType::Victim) {
-                        Some(p.name.clone())
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<String>>(),
-        )));
-        form.add_field(Box::new(ListField::new(
-            "Videographers",
-            &model
-                .people
-                .iter()
-                .filter_map(|p| {
-                    if p.types.contains(&PersonType::Videographer) {
-                        Some(p.name.clone())
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<String>>(),
-        )));
-        form.add_field(Box::new(OptionalMultilineListField::new(
-            "NIST Files",
-            &model
-                .nist_files
-                .iter()
-                .map(|c| c.0.to_string_lossy().to_string())
-                .collect::<Vec<String>>(),
-        )));
+/// Checks the timestamps of a master video parsed from a form fall within the length of the video.
+///
+/// This is separate from parsing the form because the length comes from the videos of the master.
+pub fn check_video_length(form: &Form, master: &MasterVideo, video_length: Duration) -> Result<()> {
+    if let Some(issue) = check_timestamps(&master.timestamps, Some(video_length)).first() {
+        let input = master.timestamps[issue.index()].to_string();
+        return Err(locate_timestamp_issue(form, &input, issue).into());
+    }
+    Ok(())
+}
 
-        form
+/// Fills in a master video form for a new master made from a NIST video.
+///
+/// The news broadcasts are the ones on the same date as the NIST video, from the network or
+/// affiliate its network name is mapped to. Nothing is chosen if the name hasn't been mapped.
+pub fn prefill_from_nist_video(
+    form: &mut Form,
+    video: &NistVideo,
+    mappings: &[NistNetworkMapping],
+    news_broadcasts: &[NewsBroadcast],
+) -> Result<(), FormError> {
+    form.set_value("NIST Videos", &FieldValue::List(vec![video.to_string()]))?;
+    let network = video.network.as_deref().unwrap_or_default().trim();
+    let Some(mapping) = mappings.iter().find(|m| m.nist_network == network) else {
+        return Ok(());
+    };
+    let broadcasts: Vec<String> = news_broadcasts
+        .iter()
+        .filter(|b| Some(b.date) == video.broadcast_date && mapping.is_for(b))
+        .map(|b| b.to_string())
+        .collect();
+    if !broadcasts.is_empty() {
+        form.set_value("News Broadcasts", &FieldValue::List(broadcasts))?;
     }
+    Ok(())
 }
 
 pub fn master_video_from_form(
     id: i32,
     form: &Form,
     news_broadcasts: &[NewsBroadcast],
+    nist_videos: &[NistVideo],
     people: &[Person],
+    event_types: &[EventType],
+    similar_names: SimilarNames,
 ) -> Result<MasterVideo> {
     let broadcasts_input = form
         .get_field_as::<OptionalChoiceListField>("News Broadcasts")?
@@ -272,7 +261,13 @@ pub fn master_video_from_form(
             if let Some(broadcast) = news_broadcasts.iter().find(|b| b.to_string() == broadcast) {
                 broadcasts.push(broadcast.clone());
             } else {
-                return Err(eyre!("{} is not a valid broadcast", broadcast));
+                return Err(form
+                    .locate(
+                        "News Broadcasts",
+                        &broadcast,
+                        FormError::InvalidChoice(broadcast.clone()),
+                    )
+                    .into());
             }
         }
         broadcasts
@@ -281,10 +276,17 @@ pub fn master_video_from_form(
     let title = form.get_field("Title")?.value();
 
     let categories_input = form.get_field_as::<ListField>("Categories")?.values.clone();
-    let categories = categories_input
-        .iter()
-        .map(|c| Category::from(c.as_str()))
-        .collect();
+    let mut categories = Vec::new();
+    for category in categories_input.iter() {
+        let category = Category::try_from(category.as_str()).map_err(|_| {
+            form.locate(
+                "Categories",
+                category,
+                FormError::InvalidChoice(category.clone()),
+            )
+        })?;
+        categories.push(category);
+    }
 
     let date = form.get_field("Date")?.value();
     let description = form.get_field("Description")?.value();
@@ -297,10 +299,45 @@ pub fn master_video_from_form(
         .get_field_as::<OptionalMultilineListField>("Timestamps")?
         .values
         .clone();
-    let timestamps = timestamps_input
-        .iter()
-        .map(|t| EventTimestamp::try_from(t.as_str()).unwrap())
-        .collect::<Vec<EventTimestamp>>();
+    let mut timestamps = Vec::new();
+    for input in timestamps_input.iter() {
+        let timestamp = EventTimestamp::parse(input, event_types).map_err(|e| {
+            form.locate(
+                "Timestamps",
+                input,
+                FormError::InvalidTimestamp(e.to_string()),
+            )
+        })?;
+        timestamps.push(timestamp);
+    }
+    if let Some(issue) = check_timestamps(&timestamps, None).first() {
+        return Err(locate_timestamp_issue(form, &timestamps_input[issue.index()], issue).into());
+    }
+
+    // A NIST video is chosen by the ID before its title, because the titles can change when NIST's
+    // tables are imported again.
+    let nist_videos_input = form
+        .get_field_as::<OptionalChoiceListField>("NIST Videos")?
+        .values
+        .clone();
+    let mut master_nist_videos = Vec::new();
+    for input in nist_videos_input.iter() {
+        let video = input
+            .split(':')
+            .next()
+            .and_then(|id| id.trim().parse::<i32>().ok())
+            .and_then(|id| nist_videos.iter().find(|v| v.video_id == id))
+            .ok_or_else(|| {
+                form.locate(
+                    "NIST Videos",
+                    input,
+                    FormError::InvalidChoice(input.clone()),
+                )
+            })?;
+        if !master_nist_videos.contains(video) {
+            master_nist_videos.push(video.clone());
+        }
+    }
 
     let nist_notes = form.get_field("NIST Notes")?.value();
     let nist_notes = if nist_notes.is_empty() {
@@ -309,24 +346,42 @@ pub fn master_video_from_form(
         Some(nist_notes)
     };
 
-    let pairs = [
-        ("Eyewitnesses", PersonType::Eyewitness),
-        ("Fire", PersonType::Fire),
-        ("Police", PersonType::Police),
-        ("Port Authority", PersonType::PortAuthority),
-        ("Reporters", PersonType::Reporter),
-        ("Survivors", PersonType::Survivor),
-        ("Victims", PersonType::Victim),
-        ("Videographers", PersonType::Videographer),
-    ];
-
     let mut video_people: Vec<Person> = Vec::new();
-    for (prefix, person_type) in pairs.iter() {
+    for (prefix, person_type) in PEOPLE_FIELDS.iter() {
         let people_input = form
             .get_field_as::<OptionalListField>(prefix)?
             .values
             .clone();
-        let people_input = get_people_from_input(&people_input, people, person_type.clone());
+        let people_input = get_people_from_input(&people_input, people, *person_type);
+        for person in people_input.iter() {
+            // A new person only needs to be checked the first time their name appears.
+            if person.id != 0 || video_people.iter().any(|p| p.name == person.name) {
+                continue;
+            }
+            let similar = find_similar_people(&person.name, people);
+            if similar.is_empty() {
+                continue;
+            }
+            if similar_names == SimilarNames::Confirm && confirm_new_person(&person.name, &similar)?
+            {
+                continue;
+            }
+            let names = similar
+                .iter()
+                .map(|p| match &p.description {
+                    Some(description) => format!("{} ({description})", p.name),
+                    None => p.name.clone(),
+                })
+                .collect::<Vec<String>>()
+                .join("; ");
+            return Err(form
+                .locate(
+                    prefix,
+                    &person.name,
+                    FormError::SimilarPerson(person.name.clone(), names),
+                )
+                .into());
+        }
         for person in people_input.iter() {
             if !video_people.iter().any(|p| p.name == person.name) {
                 video_people.push(person.clone());
@@ -337,7 +392,7 @@ pub fn master_video_from_form(
         for person in video_people.iter_mut() {
             if let Some(p) = people_input.iter().find(|p| p.name == person.name) {
                 if !person.types.contains(&p.types[0]) {
-                    person.types.push(p.types[0].clone());
+                    person.types.push(p.types[0]);
                 }
             }
         }
@@ -354,13 +409,16 @@ pub fn master_video_from_form(
 
     let master = MasterVideo {
         categories,
-        date: Some(date.parse()?),
+        date: Some(date.parse().map_err(|_| {
+            form.locate("Date", &date, FormError::InvalidValue("Date".to_string()))
+        })?),
         description,
         id,
         links,
         news_broadcasts,
         nist_files,
         nist_notes,
+        nist_videos: master_nist_videos,
         people: video_people,
         timestamps,
         title,
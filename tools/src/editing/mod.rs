@@ -4,11 +4,24 @@ pub mod masters;
 pub mod news;
 pub mod nist_tapes;
 pub mod nist_videos;
+pub mod prompts;
+pub mod records;
+pub mod schema;
 #[cfg(test)]
 pub mod tests;
 pub mod videos;
 
+use crate::diff::{diff_lines, has_conflict_markers, merge3, print_diff};
+use crate::helpers::print_banner;
+use color_eyre::{eyre::eyre, Result};
+use colored::Colorize;
 use db::models::{Person, PersonType};
+use dialoguer::Editor;
+use forms::{Form, FormError};
+use records::RecordEntity;
+use schema::ChoicesSource;
+use std::future::Future;
+use std::io::Write;
 
 fn get_people_from_input(
     input: &Vec<String>,
@@ -33,9 +46,147 @@ fn get_people_from_input(
                 name: name.to_string(),
                 description,
                 historical_title,
-                types: vec![person_type.clone()],
+                types: vec![person_type],
             });
         }
     }
     result
 }
+
+/// Opens a form in an editor and parses the completed form with `parse`.
+///
+/// If the completed form can't be parsed, the editor is opened again with the user's input and
+/// the error added as a comment at the top. This repeats until the form parses. `None` is returned
+/// if the user aborts, by quitting without saving or by deleting everything in the form.
+pub fn edit_form<T>(form: &Form, parse: impl Fn(&str) -> Result<T>) -> Result<Option<T>> {
+    Ok(edit_text(form.as_string(), parse)?.map(|(_, result)| result))
+}
+
+/// Opens the text of a form in an editor, returning the completed form along with the result of
+/// parsing it. See `edit_form`.
+fn edit_text<T>(
+    mut text: String,
+    parse: impl Fn(&str) -> Result<T>,
+) -> Result<Option<(String, T)>> {
+    loop {
+        let Some(completed_form) = Editor::new().edit(&text)? else {
+            return Ok(None);
+        };
+        // Any lines at the top that begin with ## are from a previous error.
+        let completed_form = completed_form
+            .lines()
+            .skip_while(|l| l.starts_with("##"))
+            .collect::<Vec<&str>>()
+            .join("\n");
+        if completed_form.trim().is_empty() {
+            return Ok(None);
+        }
+        match parse(&completed_form) {
+            Ok(result) => return Ok(Some((completed_form, result))),
+            Err(e) => {
+                let message = match e.downcast::<FormError>() {
+                    Ok(e) => e.shift_lines(2).to_string(),
+                    Err(e) => e.to_string(),
+                };
+                text = format!(
+                    "## ERROR: {}\n## Correct the form and save to try again, or delete everything to abort.\n{}",
+                    message.replace('\n', " "),
+                    completed_form
+                );
+            }
+        }
+    }
+}
+
+/// Opens the form for an existing record in an editor, in the same way as `edit_form`, then saves
+/// the result with `save`, which is given the version of the record the form was based on.
+///
+/// If `choices` is provided, the fields with choices are prompted for before the editor is opened.
+/// See `prompts::prompt_for_choices`.
+///
+/// The save only succeeds if the record is still at that version. If someone else saved it while
+/// the form was open, the changes made on each side are displayed and the user can edit the form
+/// again with both sets of changes merged, overwrite the other changes with their own, or abort.
+/// `load` reads the form for the record as it is now.
+pub async fn edit_record_form<T, R, F, S>(
+    entity: RecordEntity,
+    id: i32,
+    mut form: Form,
+    choices: Option<&dyn Fn(ChoicesSource) -> Vec<String>>,
+    load: impl Fn() -> F,
+    parse: impl Fn(&str) -> Result<T>,
+    save: impl Fn(T, i32) -> S,
+) -> Result<Option<R>>
+where
+    T: Clone,
+    F: Future<Output = Result<Form>>,
+    S: Future<Output = Result<R>>,
+{
+    let name = crate::audit::entity_name(entity);
+    let mut version = db::get_version(&name, id).await?;
+    let mut base = form.as_string();
+    if let Some(choices) = choices {
+        prompts::prompt_for_choices(&mut form, choices)?;
+    }
+    let mut text = form.as_string();
+    loop {
+        let edited = edit_text(text, |cf| {
+            if has_conflict_markers(cf) {
+                return Err(eyre!(
+                    "Resolve the conflicts marked with <<<<<<< and >>>>>>> before saving"
+                ));
+            }
+            parse(cf)
+        })?;
+        let Some((completed_form, result)) = edited else {
+            return Ok(None);
+        };
+        loop {
+            match save(result.clone(), version).await {
+                Ok(saved) => return Ok(Some(saved)),
+                Err(e) if is_version_conflict(&e) => {}
+                Err(e) => return Err(e),
+            }
+            let current_version = db::get_version(&name, id).await?;
+            let current = load().await?.as_string();
+            print_banner("Conflict");
+            println!("This {name} was saved by someone else while it was being edited");
+            println!("{}", "Their changes:".bold());
+            print_diff(&diff_lines(&base, &current), 2);
+            println!("{}", "Your changes:".bold());
+            print_diff(&diff_lines(&base, &completed_form), 2);
+            println!(
+                "[e]dit again with both sets of changes, [o]verwrite their changes, or [a]bort?"
+            );
+            std::io::stdout().flush()?;
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input)?;
+            match input.trim().to_lowercase().as_str() {
+                "e" => {
+                    let (merged, conflicts) = merge3(&base, &completed_form, &current);
+                    text = if conflicts > 0 {
+                        format!(
+                            "## {conflicts} of your changes conflict with theirs. Resolve them and save, or delete everything to abort.\n{merged}"
+                        )
+                    } else {
+                        merged
+                    };
+                    base = current;
+                    version = current_version;
+                    break;
+                }
+                // The save is tried again against their version, which can still fail if the
+                // record has been saved again since.
+                "o" => version = current_version,
+                _ => return Ok(None),
+            }
+        }
+    }
+}
+
+fn is_version_conflict(e: &color_eyre::Report) -> bool {
+    matches!(
+        e.downcast_ref::<db::error::Error>(),
+        Some(db::error::Error::VersionConflict(..))
+    )
+}
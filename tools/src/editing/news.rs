@@ -1,76 +1,55 @@
-use super::fields::{ChoiceField, MultilineTextField, OptionalChoiceField, TextField};
 use super::forms::{Form, FormError};
+use super::schema::{ChoicesSource, FieldDef, FieldKind, FormSchema};
 
-use color_eyre::{eyre::eyre, Result};
+use color_eyre::Result;
 use db::models::{NewsAffiliate, NewsBroadcast, NewsNetwork};
 
+pub static NEWS_NETWORK_FORM: FormSchema = FormSchema {
+    fields: &[
+        FieldDef::required("Name", FieldKind::Text),
+        FieldDef::required("Description", FieldKind::MultilineText),
+    ],
+};
+
+pub static NEWS_AFFILIATE_FORM: FormSchema = FormSchema {
+    fields: &[
+        FieldDef::required("Network", FieldKind::Choice).with_choices(ChoicesSource::NewsNetworks),
+        FieldDef::required("Name", FieldKind::Text),
+        FieldDef::required("Description", FieldKind::MultilineText),
+        FieldDef::required("Region", FieldKind::Text),
+    ],
+};
+
+pub static NEWS_BROADCAST_FORM: FormSchema = FormSchema {
+    fields: &[
+        FieldDef::optional("Network", FieldKind::Choice).with_choices(ChoicesSource::NewsNetworks),
+        FieldDef::optional("Affiliate", FieldKind::Choice)
+            .with_choices(ChoicesSource::NewsAffiliates),
+        FieldDef::required("Date", FieldKind::Text),
+        FieldDef::required("Description", FieldKind::MultilineText),
+    ],
+};
+
 impl Form {
     pub fn from_news_network_str(s: &str) -> Result<Self, FormError> {
-        let parts: Vec<_> = s.split("---\n").collect();
-        if parts.len() != 2 {
-            return Err(FormError::MalformedForm);
-        }
-
-        let mut form = Form::default();
-        form.add_field(Box::new(TextField::from_input_str("Name", parts[0])?));
-        form.add_field(Box::new(MultilineTextField::from_input_str(
-            "Description",
-            parts[1],
-        )?));
-
-        Ok(form)
+        NEWS_NETWORK_FORM.parse(s)
     }
 
     pub fn from_news_affiliate_str(s: &str) -> Result<Self, FormError> {
-        let parts: Vec<_> = s.split("---\n").collect();
-        if parts.len() != 4 {
-            return Err(FormError::MalformedForm);
-        }
-
-        let mut form = Form::default();
-        form.add_field(Box::new(ChoiceField::from_input_str("Network", parts[0])?));
-        form.add_field(Box::new(TextField::from_input_str("Name", parts[1])?));
-        form.add_field(Box::new(MultilineTextField::from_input_str(
-            "Description",
-            parts[2],
-        )?));
-        form.add_field(Box::new(TextField::from_input_str("Region", parts[3])?));
-
-        Ok(form)
+        NEWS_AFFILIATE_FORM.parse(s)
     }
 
     pub fn from_news_broadcast_str(s: &str) -> Result<Self, FormError> {
-        let parts: Vec<_> = s.split("---\n").collect();
-        if parts.len() != 4 {
-            return Err(FormError::MalformedForm);
-        }
-
-        let mut form = Form::default();
-        form.add_field(Box::new(OptionalChoiceField::from_input_str(
-            "Network", parts[0],
-        )?));
-        form.add_field(Box::new(OptionalChoiceField::from_input_str(
-            "Affiliate",
-            parts[1],
-        )?));
-        form.add_field(Box::new(TextField::from_input_str("Date", parts[2])?));
-        form.add_field(Box::new(MultilineTextField::from_input_str(
-            "Description",
-            parts[3],
-        )?));
-        Ok(form)
+        NEWS_BROADCAST_FORM.parse(s)
     }
 }
 
 impl From<&NewsNetwork> for Form {
     fn from(model: &NewsNetwork) -> Self {
-        let mut form = Form::default();
-        form.add_field(Box::new(TextField::new("Name", &model.name)));
-        form.add_field(Box::new(MultilineTextField::new(
-            "Description",
-            &model.description,
-        )));
-        form
+        NEWS_NETWORK_FORM.create(vec![
+            ("Name", model.name.clone().into()),
+            ("Description", model.description.clone().into()),
+        ])
     }
 }
 
@@ -87,15 +66,12 @@ pub fn news_network_from_form(id: i32, form: &Form) -> Result<NewsNetwork> {
 
 impl From<&NewsAffiliate> for Form {
     fn from(model: &NewsAffiliate) -> Self {
-        let mut form = Form::default();
-        form.add_field(Box::new(TextField::new("Name", &model.name)));
-        form.add_field(Box::new(MultilineTextField::new(
-            "Description",
-            &model.description,
-        )));
-        form.add_field(Box::new(TextField::new("Region", &model.region)));
-        form.add_field(Box::new(TextField::new("Network", &model.network.name)));
-        form
+        NEWS_AFFILIATE_FORM.create(vec![
+            ("Network", model.network.name.clone().into()),
+            ("Name", model.name.clone().into()),
+            ("Description", model.description.clone().into()),
+            ("Region", model.region.clone().into()),
+        ])
     }
 }
 
@@ -111,7 +87,13 @@ pub fn news_affiliate_from_form(
     let network = networks
         .iter()
         .find(|m| m.name == network_name)
-        .ok_or_else(|| eyre!("{network_name} is not in the networks list"))?;
+        .ok_or_else(|| {
+            form.locate(
+                "Network",
+                &network_name,
+                FormError::InvalidChoice(network_name.clone()),
+            )
+        })?;
 
     let affiliate = NewsAffiliate {
         description,
@@ -125,27 +107,26 @@ pub fn news_affiliate_from_form(
 
 impl From<&NewsBroadcast> for Form {
     fn from(model: &NewsBroadcast) -> Self {
-        let mut form = Form::default();
-        form.add_field(Box::new(OptionalChoiceField::new(
-            "Network",
-            &model
-                .news_network
-                .as_ref()
-                .map_or("".to_string(), |n| n.name.clone()),
-        )));
-        form.add_field(Box::new(OptionalChoiceField::new(
-            "Affiliate",
-            &model
-                .news_affiliate
-                .as_ref()
-                .map_or("".to_string(), |n| n.name.clone()),
-        )));
-        form.add_field(Box::new(TextField::new("Date", &model.date.to_string())));
-        form.add_field(Box::new(MultilineTextField::new(
-            "Description",
-            &model.description,
-        )));
-        form
+        NEWS_BROADCAST_FORM.create(vec![
+            (
+                "Network",
+                model
+                    .news_network
+                    .as_ref()
+                    .map_or("".to_string(), |n| n.name.clone())
+                    .into(),
+            ),
+            (
+                "Affiliate",
+                model
+                    .news_affiliate
+                    .as_ref()
+                    .map_or("".to_string(), |n| n.name.clone())
+                    .into(),
+            ),
+            ("Date", model.date.to_string().into()),
+            ("Description", model.description.clone().into()),
+        ])
     }
 }
 
@@ -163,7 +144,13 @@ pub fn news_broadcast_from_form(
             networks
                 .iter()
                 .find(|m| m.name == network_name)
-                .ok_or_else(|| eyre!("{network_name} is not in the networks list"))?
+                .ok_or_else(|| {
+                    form.locate(
+                        "Network",
+                        &network_name,
+                        FormError::InvalidChoice(network_name.clone()),
+                    )
+                })?
                 .clone(),
         )
     };
@@ -176,7 +163,13 @@ pub fn news_broadcast_from_form(
             affiliates
                 .iter()
                 .find(|m| m.name == affiliate_name)
-                .ok_or_else(|| eyre!("{affiliate_name} is not in the affiliates list"))?
+                .ok_or_else(|| {
+                    form.locate(
+                        "Affiliate",
+                        &affiliate_name,
+                        FormError::InvalidChoice(affiliate_name.clone()),
+                    )
+                })?
                 .clone(),
         )
     };
@@ -184,7 +177,9 @@ pub fn news_broadcast_from_form(
     let date = form.get_field("Date")?.value();
     let description = form.get_field("Description")?.value();
     let broadcast = NewsBroadcast {
-        date: date.parse()?,
+        date: date
+            .parse()
+            .map_err(|_| form.locate("Date", &date, FormError::InvalidValue("Date".to_string())))?,
         description,
         id,
         news_affiliate,
@@ -1,33 +1,32 @@
 use super::fields::OptionalMultilineListField;
 use super::forms::{Form, FormError};
+use super::schema::{FieldDef, FieldKind, FormSchema};
 
 use color_eyre::Result;
 use db::models::NistTape;
 use std::path::PathBuf;
 
+pub static NIST_TAPE_FORM: FormSchema = FormSchema {
+    fields: &[FieldDef::optional("NIST Files", FieldKind::MultilineList)],
+};
+
 impl Form {
     pub fn from_nist_tape_str(s: &str) -> Result<Self, FormError> {
-        let mut form = Form::default();
-        form.add_field(Box::new(OptionalMultilineListField::from_input_str(
-            "NIST Files",
-            s,
-        )?));
-        Ok(form)
+        NIST_TAPE_FORM.parse(s)
     }
 }
 
 impl From<&NistTape> for Form {
     fn from(model: &NistTape) -> Self {
-        let mut form = Form::default();
-        form.add_field(Box::new(OptionalMultilineListField::new(
+        NIST_TAPE_FORM.create(vec![(
             "NIST Files",
-            &model
+            model
                 .release_files
                 .iter()
                 .map(|c| c.0.to_string_lossy().to_string())
-                .collect::<Vec<String>>(),
-        )));
-        form
+                .collect::<Vec<String>>()
+                .into(),
+        )])
     }
 }
 
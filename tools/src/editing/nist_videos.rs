@@ -1,36 +1,31 @@
 use super::fields::{BooleanField, OptionalMultilineTextField};
 use super::forms::{Form, FormError};
+use super::schema::{FieldDef, FieldKind, FormSchema};
 use color_eyre::Result;
 use db::models::NistVideo;
 
+pub static NIST_VIDEO_FORM: FormSchema = FormSchema {
+    fields: &[
+        FieldDef::required("Missing?", FieldKind::Boolean),
+        FieldDef::optional("Additional Notes", FieldKind::MultilineText),
+    ],
+};
+
 impl Form {
     pub fn from_nist_video_str(s: &str) -> Result<Self, FormError> {
-        let parts: Vec<_> = s.split("---\n").collect();
-        if parts.len() != 2 {
-            return Err(FormError::MalformedForm);
-        }
-
-        let mut form = Form::default();
-        form.add_field(Box::new(BooleanField::from_input_str(
-            "Missing?", parts[0],
-        )?));
-        form.add_field(Box::new(OptionalMultilineTextField::from_input_str(
-            "Additional Notes",
-            parts[1],
-        )?));
-        Ok(form)
+        NIST_VIDEO_FORM.parse(s)
     }
 }
 
 impl From<&NistVideo> for Form {
     fn from(model: &NistVideo) -> Self {
-        let mut form = Form::default();
-        form.add_field(Box::new(BooleanField::new("Missing?", model.is_missing)));
-        form.add_field(Box::new(OptionalMultilineTextField::new(
-            "Additional Notes",
-            model.additional_notes.as_ref().unwrap_or(&"".to_string()),
-        )));
-        form
+        NIST_VIDEO_FORM.create(vec![
+            ("Missing?", model.is_missing.into()),
+            (
+                "Additional Notes",
+                model.additional_notes.clone().unwrap_or_default().into(),
+            ),
+        ])
     }
 }
 
@@ -0,0 +1,93 @@
+use super::forms::Form;
+use super::schema::{ChoicesSource, FieldKind, FieldValue};
+use color_eyre::Result;
+use skim::prelude::*;
+use std::io::Cursor;
+
+/// Prompts for the value of each field in a form that has choices, using fuzzy selection.
+///
+/// This is an alternative to picking choices from the text of the form, which becomes unwieldy
+/// when there are hundreds of them. `choices` provides the choices for each source; fields whose
+/// source has no choices are skipped. Fields that take a list start with their current values
+/// selected. Pressing escape leaves a field as it is. The free-text fields are then completed in
+/// the editor as usual, where the chosen values can also be reviewed.
+pub fn prompt_for_choices(
+    form: &mut Form,
+    choices: impl Fn(ChoicesSource) -> Vec<String>,
+) -> Result<()> {
+    let Some(schema) = form.schema else {
+        return Ok(());
+    };
+    for def in schema.fields.iter() {
+        let Some(source) = def.choices else {
+            continue;
+        };
+        let options = choices(source);
+        if options.is_empty() {
+            continue;
+        }
+        let is_list = matches!(def.kind, FieldKind::ChoiceList | FieldKind::List);
+        let current = form.get_field(def.name)?.value();
+        let current: Vec<String> = if is_list {
+            current
+                .split(';')
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+                .collect()
+        } else {
+            vec![current]
+        };
+
+        let Some(selected) = select(def.name, &options, &current, is_list, "leave unchanged")?
+        else {
+            continue;
+        };
+        let value = if is_list {
+            FieldValue::List(selected)
+        } else {
+            FieldValue::Text(selected.into_iter().next().unwrap_or_default())
+        };
+        form.set_value(def.name, &value)?;
+    }
+    Ok(())
+}
+
+/// Runs a fuzzy selection over the options, returning `None` if the user pressed escape.
+///
+/// The header tells the user that pressing escape will `on_escape`, e.g. "leave unchanged".
+pub fn select(
+    name: &str,
+    options: &[String],
+    current: &[String],
+    multi: bool,
+    on_escape: &str,
+) -> Result<Option<Vec<String>>> {
+    let prompt = format!("{name}> ");
+    let header = if multi {
+        format!("TAB to select or deselect, ENTER to accept, ESC to {on_escape}")
+    } else {
+        format!("ENTER to select, ESC to {on_escape}")
+    };
+    let selector = DefaultSkimSelector::default().preset(current.iter().cloned());
+    let skim_options = SkimOptionsBuilder::default()
+        .height(Some("70%"))
+        .multi(multi)
+        .prompt(Some(&prompt))
+        .header(Some(&header))
+        .selector(Some(Rc::new(selector)))
+        .build()?;
+    let items = SkimItemReader::default().of_bufread(Cursor::new(options.join("\n")));
+    let Some(output) = Skim::run_with(&skim_options, Some(items)) else {
+        return Ok(None);
+    };
+    if output.is_abort {
+        return Ok(None);
+    }
+    Ok(Some(
+        output
+            .selected_items
+            .iter()
+            .map(|item| item.output().to_string())
+            .collect(),
+    ))
+}
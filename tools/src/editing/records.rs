@@ -0,0 +1,251 @@
+use super::forms::{Form, FormError};
+use super::masters::MASTER_VIDEO_FORM;
+use super::news::{NEWS_AFFILIATE_FORM, NEWS_BROADCAST_FORM, NEWS_NETWORK_FORM};
+use super::nist_tapes::NIST_TAPE_FORM;
+use super::nist_videos::NIST_VIDEO_FORM;
+use super::schema::{FieldKind, FieldValue, FormSchema};
+use super::videos::VIDEO_FORM;
+use clap::ValueEnum;
+use color_eyre::{eyre::eyre, Result};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// Structured document formats that records can be imported from and exported to, as an
+/// alternative to the text forms.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum RecordFormat {
+    Toml,
+    Yaml,
+}
+
+impl RecordFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Some(RecordFormat::Toml),
+            Some("yaml") | Some("yml") => Some(RecordFormat::Yaml),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &str {
+        match self {
+            RecordFormat::Toml => "toml",
+            RecordFormat::Yaml => "yaml",
+        }
+    }
+}
+
+/// The kinds of record that can be imported and exported.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum RecordEntity {
+    /// News affiliate
+    Affiliate,
+    /// News broadcast
+    Broadcast,
+    /// Master video
+    Master,
+    /// News network
+    Network,
+    /// Allocation of release files to a NIST tape
+    NistTape,
+    /// Missing status and additional notes for a NIST video
+    NistVideo,
+    /// Video
+    Video,
+}
+
+impl RecordEntity {
+    pub fn schema(&self) -> &'static FormSchema {
+        match self {
+            RecordEntity::Affiliate => &NEWS_AFFILIATE_FORM,
+            RecordEntity::Broadcast => &NEWS_BROADCAST_FORM,
+            RecordEntity::Master => &MASTER_VIDEO_FORM,
+            RecordEntity::Network => &NEWS_NETWORK_FORM,
+            RecordEntity::NistTape => &NIST_TAPE_FORM,
+            RecordEntity::NistVideo => &NIST_VIDEO_FORM,
+            RecordEntity::Video => &VIDEO_FORM,
+        }
+    }
+}
+
+/// The key used for a field in a structured record, e.g., `NIST Notes` becomes `nist_notes`.
+pub fn field_key(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("_")
+}
+
+/// Converts a form into a structured record, with the ID of the record as the first key.
+///
+/// An ID of 0 is left out, as the record is new.
+pub fn record_from_form(id: i32, form: &Form) -> Value {
+    let mut record = Map::new();
+    if id != 0 {
+        record.insert("id".to_string(), Value::from(id));
+    }
+    let Some(schema) = form.schema else {
+        return Value::Object(record);
+    };
+    for (def, field) in schema.fields.iter().zip(form.fields.iter()) {
+        let value = field.value();
+        let value = match def.kind {
+            FieldKind::Boolean => Value::Bool(value == "Yes"),
+            FieldKind::ChoiceList | FieldKind::List => Value::Array(
+                value
+                    .split(';')
+                    .map(|v| v.trim())
+                    .filter(|v| !v.is_empty())
+                    .map(Value::from)
+                    .collect(),
+            ),
+            FieldKind::MultilineList => Value::Array(
+                value
+                    .lines()
+                    .filter(|v| !v.trim().is_empty())
+                    .map(Value::from)
+                    .collect(),
+            ),
+            FieldKind::Choice | FieldKind::MultilineText | FieldKind::Text => Value::from(value),
+        };
+        record.insert(field_key(def.name), value);
+    }
+    Value::Object(record)
+}
+
+/// Converts a structured record into a form for the given schema, returning the ID of the record
+/// along with the form. The ID is 0 if the record doesn't have one.
+///
+/// The form is validated in the same way as a text form, so the record can then be handled by the
+/// same functions.
+pub fn form_from_record(schema: &'static FormSchema, record: &Value) -> Result<(i32, Form)> {
+    let record = record
+        .as_object()
+        .ok_or_else(|| eyre!("Each record must be a table of fields"))?;
+    let mut id = 0;
+    let mut values = Vec::new();
+    for (key, value) in record.iter() {
+        if key == "id" {
+            id = value
+                .as_i64()
+                .ok_or_else(|| eyre!("The id of a record must be a number"))?
+                as i32;
+            continue;
+        }
+        let def = schema
+            .fields
+            .iter()
+            .find(|f| field_key(f.name) == *key)
+            .ok_or_else(|| FormError::UnknownField(key.clone()))?;
+        let value = match def.kind {
+            FieldKind::Boolean => match value {
+                Value::Bool(b) => FieldValue::Boolean(*b),
+                Value::String(s) if s.to_lowercase() == "yes" || s.to_lowercase() == "no" => {
+                    FieldValue::Boolean(s.to_lowercase() == "yes")
+                }
+                _ => return Err(FormError::MalformedField(def.name.to_string()).into()),
+            },
+            FieldKind::ChoiceList | FieldKind::List | FieldKind::MultilineList => match value {
+                Value::Array(items) => {
+                    let mut list = Vec::new();
+                    for item in items.iter() {
+                        list.push(
+                            value_to_string(item)
+                                .ok_or_else(|| FormError::MalformedField(def.name.to_string()))?,
+                        );
+                    }
+                    FieldValue::List(list)
+                }
+                Value::String(s) if def.kind == FieldKind::MultilineList => {
+                    FieldValue::List(s.lines().map(|l| l.to_string()).collect())
+                }
+                _ => FieldValue::List(vec![value_to_string(value)
+                    .ok_or_else(|| FormError::MalformedField(def.name.to_string()))?]),
+            },
+            FieldKind::Choice | FieldKind::MultilineText | FieldKind::Text => FieldValue::Text(
+                value_to_string(value)
+                    .ok_or_else(|| FormError::MalformedField(def.name.to_string()))?
+                    .trim()
+                    .to_string(),
+            ),
+        };
+        values.push((def.name, value));
+    }
+
+    let form = schema.create(values);
+    for (def, field) in schema.fields.iter().zip(form.fields.iter()) {
+        if def.required && field.value().is_empty() {
+            return Err(FormError::RequiredFieldEmpty(def.name.to_string()).into());
+        }
+    }
+    Ok((id, form))
+}
+
+fn value_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::String(s) => Some(s.clone()),
+        // TOML dates and times are represented as a table with a single string.
+        Value::Object(map) if map.len() == 1 => map.values().next().and_then(value_to_string),
+        _ => None,
+    }
+}
+
+/// Reads the records in a document.
+///
+/// A document can hold a single record or a batch of them. In YAML, a batch is either a sequence
+/// of records or several documents in the same file. In TOML, a batch is an array of tables named
+/// `records`.
+pub fn parse_records(s: &str, format: RecordFormat) -> Result<Vec<Value>> {
+    let mut documents = Vec::new();
+    match format {
+        RecordFormat::Toml => documents.push(toml::from_str::<Value>(s)?),
+        RecordFormat::Yaml => {
+            for document in serde_yaml::Deserializer::from_str(s) {
+                let value = Value::deserialize(document)?;
+                if !value.is_null() {
+                    documents.push(value);
+                }
+            }
+        }
+    }
+
+    let mut records = Vec::new();
+    for document in documents {
+        match document {
+            Value::Array(items) => records.extend(items),
+            Value::Object(mut map) if map.len() == 1 && map.contains_key("records") => {
+                match map.remove("records") {
+                    Some(Value::Array(items)) => records.extend(items),
+                    _ => return Err(eyre!("The records key must hold an array of records")),
+                }
+            }
+            document => records.push(document),
+        }
+    }
+    Ok(records)
+}
+
+/// Writes records to a document. More than one record is written as a batch.
+pub fn write_records(mut records: Vec<Value>, format: RecordFormat) -> Result<String> {
+    let document = if records.len() == 1 {
+        records.remove(0)
+    } else {
+        match format {
+            RecordFormat::Toml => {
+                let mut map = Map::new();
+                map.insert("records".to_string(), Value::Array(records));
+                Value::Object(map)
+            }
+            RecordFormat::Yaml => Value::Array(records),
+        }
+    };
+    let output = match format {
+        RecordFormat::Toml => toml::to_string(&document)?,
+        RecordFormat::Yaml => serde_yaml::to_string(&document)?,
+    };
+    Ok(output)
+}
@@ -0,0 +1,324 @@
+use super::fields::{
+    BooleanField, ChoiceField, FormField, ListField, MultilineListField, MultilineTextField,
+    OptionalChoiceField, OptionalChoiceListField, OptionalListField, OptionalMultilineListField,
+    OptionalMultilineTextField, OptionalTextField, TextField, CHOICE_MARKERS,
+};
+use super::forms::{FieldSection, Form, FormError};
+use db::models::PersonType;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FieldKind {
+    Boolean,
+    Choice,
+    ChoiceList,
+    List,
+    MultilineList,
+    MultilineText,
+    Text,
+}
+
+/// The records or values a field offers as its choices.
+///
+/// Choice fields must have one of their choices as their value. The categories and people of a
+/// master video are lists, but their values can also be picked from the choices when prompting for
+/// them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChoicesSource {
+    Categories,
+    MasterVideos,
+    NewsAffiliates,
+    NewsBroadcasts,
+    NewsNetworks,
+    NistVideos,
+    People(PersonType),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldValue {
+    Boolean(bool),
+    List(Vec<String>),
+    Text(String),
+}
+
+impl FieldValue {
+    fn as_bool(&self) -> bool {
+        match self {
+            FieldValue::Boolean(value) => *value,
+            FieldValue::List(values) => !values.is_empty(),
+            FieldValue::Text(value) => value.to_lowercase() == "yes",
+        }
+    }
+
+    fn as_list(&self) -> Vec<String> {
+        match self {
+            FieldValue::List(values) => values.clone(),
+            FieldValue::Text(value) if value.is_empty() => Vec::new(),
+            _ => vec![self.as_text()],
+        }
+    }
+
+    fn as_text(&self) -> String {
+        match self {
+            FieldValue::Boolean(value) => if *value { "Yes" } else { "No" }.to_string(),
+            FieldValue::List(values) => values.join("\n"),
+            FieldValue::Text(value) => value.clone(),
+        }
+    }
+}
+
+impl From<bool> for FieldValue {
+    fn from(value: bool) -> Self {
+        FieldValue::Boolean(value)
+    }
+}
+
+impl From<&str> for FieldValue {
+    fn from(value: &str) -> Self {
+        FieldValue::Text(value.to_string())
+    }
+}
+
+impl From<String> for FieldValue {
+    fn from(value: String) -> Self {
+        FieldValue::Text(value)
+    }
+}
+
+impl From<Vec<String>> for FieldValue {
+    fn from(values: Vec<String>) -> Self {
+        FieldValue::List(values)
+    }
+}
+
+#[derive(Debug)]
+pub struct FieldDef {
+    pub choices: Option<ChoicesSource>,
+    pub kind: FieldKind,
+    pub name: &'static str,
+    pub required: bool,
+}
+
+impl FieldDef {
+    pub const fn required(name: &'static str, kind: FieldKind) -> Self {
+        Self {
+            choices: None,
+            kind,
+            name,
+            required: true,
+        }
+    }
+
+    pub const fn optional(name: &'static str, kind: FieldKind) -> Self {
+        Self {
+            choices: None,
+            kind,
+            name,
+            required: false,
+        }
+    }
+
+    pub const fn with_choices(mut self, source: ChoicesSource) -> Self {
+        self.choices = Some(source);
+        self
+    }
+
+    pub(super) fn create(&self, value: &FieldValue) -> Box<dyn FormField> {
+        let name = self.name;
+        match (self.kind, self.required) {
+            (FieldKind::Boolean, _) => Box::new(BooleanField::new(name, value.as_bool())),
+            (FieldKind::Choice, true) => Box::new(ChoiceField::new(name, &value.as_text())),
+            (FieldKind::Choice, false) => {
+                Box::new(OptionalChoiceField::new(name, &value.as_text()))
+            }
+            (FieldKind::ChoiceList, _) => {
+                Box::new(OptionalChoiceListField::new(name, &value.as_list()))
+            }
+            (FieldKind::List, true) => Box::new(ListField::new(name, &value.as_list())),
+            (FieldKind::List, false) => Box::new(OptionalListField::new(name, &value.as_list())),
+            (FieldKind::MultilineList, true) => {
+                Box::new(MultilineListField::new(name, &value.as_list()))
+            }
+            (FieldKind::MultilineList, false) => {
+                Box::new(OptionalMultilineListField::new(name, &value.as_list()))
+            }
+            (FieldKind::MultilineText, true) => {
+                Box::new(MultilineTextField::new(name, &value.as_text()))
+            }
+            (FieldKind::MultilineText, false) => {
+                Box::new(OptionalMultilineTextField::new(name, &value.as_text()))
+            }
+            (FieldKind::Text, true) => Box::new(TextField::new(name, &value.as_text())),
+            (FieldKind::Text, false) => Box::new(OptionalTextField::new(name, &value.as_text())),
+        }
+    }
+
+    fn parse(&self, input: &str) -> Result<Box<dyn FormField>, FormError> {
+        let name = self.name;
+        let field: Box<dyn FormField> = match (self.kind, self.required) {
+            (FieldKind::Boolean, _) => Box::new(BooleanField::from_input_str(name, input)?),
+            (FieldKind::Choice, true) => Box::new(ChoiceField::from_input_str(name, input)?),
+            (FieldKind::Choice, false) => {
+                Box::new(OptionalChoiceField::from_input_str(name, input)?)
+            }
+            (FieldKind::ChoiceList, _) => {
+                Box::new(OptionalChoiceListField::from_input_str(name, input)?)
+            }
+            (FieldKind::List, true) => Box::new(ListField::from_input_str(name, input)?),
+            (FieldKind::List, false) => Box::new(OptionalListField::from_input_str(name, input)?),
+            (FieldKind::MultilineList, true) => {
+                Box::new(MultilineListField::from_input_str(name, input)?)
+            }
+            (FieldKind::MultilineList, false) => {
+                Box::new(OptionalMultilineListField::from_input_str(name, input)?)
+            }
+            (FieldKind::MultilineText, true) => {
+                Box::new(MultilineTextField::from_input_str(name, input)?)
+            }
+            (FieldKind::MultilineText, false) => {
+                Box::new(OptionalMultilineTextField::from_input_str(name, input)?)
+            }
+            (FieldKind::Text, true) => Box::new(TextField::from_input_str(name, input)?),
+            (FieldKind::Text, false) => Box::new(OptionalTextField::from_input_str(name, input)?),
+        };
+        if self.required && field.value().is_empty() {
+            return Err(FormError::RequiredFieldEmpty(name.to_string()));
+        }
+        Ok(field)
+    }
+}
+
+/// Describes the fields of a form, in the order they appear.
+///
+/// The schema is used both to render a form for a record and to parse the completed form. Fields
+/// are separated by a line containing only `---`. A line of text that should itself be `---` is
+/// written as `\---`.
+#[derive(Debug)]
+pub struct FormSchema {
+    pub fields: &'static [FieldDef],
+}
+
+impl FormSchema {
+    /// Creates a form with the given values. Fields without a value are left empty.
+    pub fn create(&'static self, values: Vec<(&str, FieldValue)>) -> Form {
+        let mut form = Form {
+            schema: Some(self),
+            ..Default::default()
+        };
+        let empty = FieldValue::Text(String::new());
+        for def in self.fields.iter() {
+            let value = values
+                .iter()
+                .find(|(name, _)| *name == def.name)
+                .map(|(_, value)| value)
+                .unwrap_or(&empty);
+            form.add_field(def.create(value));
+        }
+        form
+    }
+
+    /// Parses a completed form.
+    ///
+    /// Fields are identified by their label, so they can appear in any order. Optional fields can
+    /// be omitted entirely. Errors record the line and column where the problem was found.
+    pub fn parse(&'static self, s: &str) -> Result<Form, FormError> {
+        let mut sections: Vec<Option<FieldSection>> = Vec::new();
+        sections.resize_with(self.fields.len(), || None);
+        for section in split_sections(s) {
+            let Some((line, first)) = section.first() else {
+                continue;
+            };
+            let index = self
+                .fields
+                .iter()
+                .position(|f| first.starts_with(&format!("{}:", f.name)))
+                .ok_or_else(|| FormError::Invalid {
+                    column: 1,
+                    error: Box::new(FormError::UnknownField(first.clone())),
+                    line: *line,
+                    text: first.clone(),
+                })?;
+            let def = &self.fields[index];
+            if sections[index].is_some() {
+                return Err(FormError::Invalid {
+                    column: 1,
+                    error: Box::new(FormError::DuplicateField(def.name.to_string())),
+                    line: *line,
+                    text: first.clone(),
+                });
+            }
+            sections[index] = Some(FieldSection {
+                lines: section,
+                name: def.name.to_string(),
+            });
+        }
+
+        let mut form = Form {
+            schema: Some(self),
+            ..Default::default()
+        };
+        for (def, section) in self.fields.iter().zip(sections) {
+            let section = match section {
+                Some(section) => section,
+                None if def.required => {
+                    return Err(FormError::FieldNotFound(def.name.to_string()));
+                }
+                None => FieldSection {
+                    lines: Vec::new(),
+                    name: def.name.to_string(),
+                },
+            };
+            let input = if section.lines.is_empty() {
+                format!("{}:", def.name)
+            } else {
+                section.text()
+            };
+            let field = def.parse(&input).map_err(|e| section.locate_value(e))?;
+            form.add_field(field);
+            form.sections.push(section);
+        }
+        Ok(form)
+    }
+}
+
+/// Splits a form into the lines for each field, unescaping any separators within the text. Each
+/// line is paired with its line number.
+///
+/// The markers above the choices of a field are dropped, as are blank lines before the label of a
+/// field. Any other text is kept, even if it looks like an instruction.
+fn split_sections(s: &str) -> Vec<Vec<(usize, String)>> {
+    let mut sections = vec![Vec::new()];
+    for (i, line) in s.lines().enumerate() {
+        if CHOICE_MARKERS.contains(&line.trim_end()) {
+            continue;
+        }
+        let line = match line.trim_end() {
+            "---" => {
+                sections.push(Vec::new());
+                continue;
+            }
+            "\\---" => &line[1..],
+            _ => line,
+        };
+        if let Some(section) = sections.last_mut() {
+            if section.is_empty() && line.trim().is_empty() {
+                continue;
+            }
+            section.push((i + 1, line.to_string()));
+        }
+    }
+    sections
+}
+
+/// Escapes any lines in the rendered text of a field that would be taken as a separator.
+pub fn escape_separators(s: &str) -> String {
+    s.split('\n')
+        .map(|line| {
+            if line.trim_end() == "---" {
+                format!("\\{line}")
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
@@ -1,7 +1,32 @@
 use super::*;
-use crate::editing::{forms::Form, masters::master_video_from_form};
+use crate::editing::{
+    forms::{Form, FormError},
+    masters::{find_similar_people, master_video_from_form, prefill_from_nist_video, SimilarNames},
+    news::{news_affiliate_from_form, news_network_from_form, NEWS_NETWORK_FORM},
+    records::{form_from_record, parse_records, record_from_form, write_records, RecordFormat},
+    schema::FieldValue,
+};
 use chrono::NaiveDate;
-use db::models::{Category, MasterVideo, NewsAffiliate, NewsBroadcast, NewsNetwork, Video};
+use db::models::{
+    Category, EventType, MasterVideo, NewsAffiliate, NewsBroadcast, NewsNetwork,
+    NistNetworkMapping, NistVideo, Video,
+};
+
+fn event_types() -> Vec<EventType> {
+    [
+        "normal",
+        "report",
+        "wtc1-collapse",
+        "wtc2-collapse",
+        "wtc2-impact",
+    ]
+    .iter()
+    .map(|slug| EventType {
+        slug: slug.to_string(),
+        ..Default::default()
+    })
+    .collect()
+}
 
 #[test]
 fn form_as_string_from_default_master_video_should_have_expected_fields() {
@@ -117,7 +142,16 @@ fn master_video_from_form_should_parse_a_master_video() {
     ];
 
     let form = Form::from_master_video_str(&form_input).unwrap();
-    let master_video = master_video_from_form(0, &form, &news_broadcasts, &people).unwrap();
+    let master_video = master_video_from_form(
+        0,
+        &form,
+        &news_broadcasts,
+        &[],
+        &people,
+        &event_types(),
+        SimilarNames::Reject,
+    )
+    .unwrap();
     assert_eq!(master_video.news_broadcasts[0].id, 1);
     assert_eq!(
         master_video.title,
@@ -287,7 +321,16 @@ fn master_video_from_form_should_parse_a_form_where_an_existing_person_has_a_typ
     ];
 
     let form = Form::from_master_video_str(&form_input).unwrap();
-    let master_video = master_video_from_form(0, &form, &news_broadcasts, &people).unwrap();
+    let master_video = master_video_from_form(
+        0,
+        &form,
+        &news_broadcasts,
+        &[],
+        &people,
+        &event_types(),
+        SimilarNames::Reject,
+    )
+    .unwrap();
     let person_with_multiple_types = master_video
         .people
         .iter()
@@ -369,7 +412,16 @@ fn master_video_from_form_should_parse_master_video_where_form_has_empty_fields(
     ];
 
     let form = Form::from_master_video_str(&form_input).unwrap();
-    let master_video = master_video_from_form(0, &form, &news_broadcasts, &people).unwrap();
+    let master_video = master_video_from_form(
+        0,
+        &form,
+        &news_broadcasts,
+        &[],
+        &people,
+        &event_types(),
+        SimilarNames::Reject,
+    )
+    .unwrap();
 
     assert!(master_video.links.is_empty());
     assert!(master_video.timestamps.is_empty());
@@ -446,10 +498,510 @@ fn form_as_string_from_default_video_should_have_expected_fields() {
     ];
 
     let master_form = Form::from_master_video_str(&master_video_form).unwrap();
-    let master_video = master_video_from_form(0, &master_form, &news_broadcasts, &people).unwrap();
+    let master_video = master_video_from_form(
+        0,
+        &master_form,
+        &news_broadcasts,
+        &[],
+        &people,
+        &event_types(),
+        SimilarNames::Reject,
+    )
+    .unwrap();
 
     let mut form = Form::from(&Video::default());
     form.add_choices("Master", vec![master_video.title])
         .unwrap();
     assert_eq!(form.as_string(), video_form.trim());
 }
+
+#[test]
+fn form_from_str_should_parse_fields_by_label_in_any_order() {
+    let form_input = "Date: 2001-09-11\n---\nCategories: news\n---\nDescription:\nAmateur footage\n---\nTitle: WTC1 Collapse";
+    let form = Form::from_master_video_str(form_input).unwrap();
+    let master_video = master_video_from_form(
+        0,
+        &form,
+        &[],
+        &[],
+        &[],
+        &event_types(),
+        SimilarNames::Reject,
+    )
+    .unwrap();
+    assert_eq!(master_video.title, "WTC1 Collapse");
+    assert_eq!(master_video.description, "Amateur footage");
+    assert!(master_video.timestamps.is_empty());
+    assert!(master_video.people.is_empty());
+
+    let result = Form::from_master_video_str("Date: 2001-09-11\n---\nCategories: news");
+    assert!(matches!(result, Err(FormError::FieldNotFound(name)) if name == "Title"));
+    let result = Form::from_master_video_str(&format!("{form_input}\n---\nTitle: Duplicate"));
+    assert!(matches!(
+        result,
+        Err(FormError::Invalid { error, line: 10, .. })
+            if matches!(*error, FormError::DuplicateField(ref name) if name == "Title")
+    ));
+}
+
+#[test]
+fn form_from_str_should_allow_escaped_separators_in_multiline_text() {
+    let master_video = MasterVideo {
+        title: "WTC1 Collapse".to_string(),
+        categories: vec![Category::News],
+        date: NaiveDate::from_ymd_opt(2001, 9, 11),
+        description: "Part one\n---\nPart two".to_string(),
+        ..Default::default()
+    };
+    let form = Form::from(&master_video);
+    let form_string = form.as_string();
+    assert!(form_string.contains("Part one\n\\---\nPart two"));
+
+    let form = Form::from_master_video_str(&form_string).unwrap();
+    let parsed = master_video_from_form(
+        0,
+        &form,
+        &[],
+        &[],
+        &[],
+        &event_types(),
+        SimilarNames::Reject,
+    )
+    .unwrap();
+    assert_eq!(parsed.description, "Part one\n---\nPart two");
+
+    let unescaped = form_string.replace("\\---", "---");
+    let result = Form::from_master_video_str(&unescaped);
+    assert!(matches!(
+        result,
+        Err(FormError::Invalid { error, text, .. })
+            if matches!(*error, FormError::UnknownField(_)) && text == "Part two"
+    ));
+}
+
+#[test]
+fn form_from_str_should_keep_text_that_is_not_a_generated_marker() {
+    let master_video = MasterVideo {
+        title: "WTC1 Collapse".to_string(),
+        categories: vec![Category::News],
+        date: NaiveDate::from_ymd_opt(2001, 9, 11),
+        description: "## Notes ##\nPart one\n---  \nPart two".to_string(),
+        ..Default::default()
+    };
+    let form_string = Form::from(&master_video).as_string();
+    assert!(form_string.contains("Part one\n\\---  \nPart two"));
+
+    let form = Form::from_master_video_str(&form_string).unwrap();
+    let parsed = master_video_from_form(
+        0,
+        &form,
+        &[],
+        &[],
+        &[],
+        &event_types(),
+        SimilarNames::Reject,
+    )
+    .unwrap();
+    assert_eq!(parsed.description, master_video.description);
+}
+
+#[test]
+fn form_from_news_affiliate_should_parse_back_into_the_same_affiliate() {
+    let network = NewsNetwork {
+        id: 1,
+        name: "ABC News".to_string(),
+        description: "National ABC News coverage".to_string(),
+    };
+    let affiliate = NewsAffiliate {
+        id: 1,
+        name: "WABC-TV".to_string(),
+        description: "New York local coverage".to_string(),
+        region: "NYC".to_string(),
+        network: network.clone(),
+    };
+    let form = Form::from(&affiliate);
+    let form = Form::from_news_affiliate_str(&form.as_string()).unwrap();
+    let parsed = news_affiliate_from_form(1, &form, &[network]).unwrap();
+    assert_eq!(parsed, affiliate);
+}
+
+#[test]
+fn form_errors_should_give_the_line_and_column_of_the_problem() {
+    let form_input = std::fs::read_to_string("../resources/master_form_completed")
+        .expect("Failed to read test form")
+        .replace("News Broadcasts: WABC-TV (2001-09-11)", "News Broadcasts:");
+
+    let invalid_date = form_input.replace("Date: 2001-09-11", "Date: 2001-09-31");
+    let form = Form::from_master_video_str(&invalid_date).unwrap();
+    let error = master_video_from_form(
+        0,
+        &form,
+        &[],
+        &[],
+        &[],
+        &event_types(),
+        SimilarNames::Reject,
+    )
+    .err()
+    .unwrap()
+    .downcast::<FormError>()
+    .unwrap();
+    assert_eq!(
+        error.to_string(),
+        "Line 7, column 7: Invalid value for field Date: '2001-09-31'"
+    );
+
+    let invalid_timestamp = form_input.replace("00:35:10: John", "00:35: John");
+    let form = Form::from_master_video_str(&invalid_timestamp).unwrap();
+    let error = master_video_from_form(
+        0,
+        &form,
+        &[],
+        &[],
+        &[],
+        &event_types(),
+        SimilarNames::Reject,
+    )
+    .err()
+    .unwrap()
+    .downcast::<FormError>()
+    .unwrap();
+    assert!(matches!(
+        error.shift_lines(2),
+        FormError::Invalid {
+            column: 1,
+            line: 20,
+            ..
+        }
+    ));
+
+    let out_of_order = form_input.replace("00:44:49: John", "00:34:49: John");
+    let form = Form::from_master_video_str(&out_of_order).unwrap();
+    let error = master_video_from_form(
+        0,
+        &form,
+        &[],
+        &[],
+        &[],
+        &event_types(),
+        SimilarNames::Reject,
+    )
+    .err()
+    .unwrap()
+    .downcast::<FormError>()
+    .unwrap();
+    assert_eq!(
+        error.to_string(),
+        "Line 19, column 1: Invalid timestamp: 00:34:49 is earlier than the timestamp before it \
+         at 00:35:10: '00:34:49: John DelGiorno delivers his second report from NewsCopter7 at \
+         ~0926. [report]'"
+    );
+
+    let invalid_boolean = "Missing?: Maybe\n---\nAdditional Notes:";
+    let error = Form::from_nist_video_str(invalid_boolean).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "Line 1, column 11: The Missing? field is not in the expected form: 'Maybe'"
+    );
+}
+
+#[test]
+fn master_video_from_form_should_link_nist_videos_by_their_ids() {
+    let nist_videos = vec![
+        NistVideo {
+            video_id: 13,
+            video_title: "WABC 9/11 Broadcast".to_string(),
+            ..Default::default()
+        },
+        NistVideo {
+            video_id: 14,
+            video_title: "WABC 9/11 Broadcast (Silverstein copy)".to_string(),
+            ..Default::default()
+        },
+    ];
+    let form_input = std::fs::read_to_string("../resources/master_form_completed")
+        .expect("Failed to read test form")
+        .replace("News Broadcasts: WABC-TV (2001-09-11)", "News Broadcasts:")
+        .replace(
+            "NIST Videos:\n",
+            "NIST Videos: 14: an older title; 13: WABC 9/11 Broadcast; 14\n",
+        );
+    let form = Form::from_master_video_str(&form_input).unwrap();
+    let master_video = master_video_from_form(
+        0,
+        &form,
+        &[],
+        &nist_videos,
+        &[],
+        &event_types(),
+        SimilarNames::Reject,
+    )
+    .unwrap();
+    let ids: Vec<i32> = master_video
+        .nist_videos
+        .iter()
+        .map(|v| v.video_id)
+        .collect();
+    assert_eq!(ids, vec![14, 13]);
+
+    let unknown_video = form_input.replace("13: WABC", "15: WABC");
+    let form = Form::from_master_video_str(&unknown_video).unwrap();
+    let error = master_video_from_form(
+        0,
+        &form,
+        &[],
+        &nist_videos,
+        &[],
+        &event_types(),
+        SimilarNames::Reject,
+    )
+    .err()
+    .unwrap()
+    .downcast::<FormError>()
+    .unwrap();
+    assert!(error.to_string().contains("'15: WABC 9/11 Broadcast'"));
+}
+
+#[test]
+fn prefill_from_nist_video_should_choose_the_broadcasts_of_the_mapped_network() {
+    let news_network = NewsNetwork {
+        id: 1,
+        name: "ABC News".to_string(),
+        description: String::new(),
+    };
+    let affiliate = NewsAffiliate {
+        id: 1,
+        name: "WABC-TV".to_string(),
+        description: String::new(),
+        region: "NYC".to_string(),
+        network: news_network.clone(),
+    };
+    let broadcast = |id: i32, day: u32, network: bool| NewsBroadcast {
+        id,
+        date: NaiveDate::from_ymd_opt(2001, 9, day).unwrap(),
+        description: String::new(),
+        news_network: network.then(|| news_network.clone()),
+        news_affiliate: (!network).then(|| affiliate.clone()),
+    };
+    let news_broadcasts = vec![
+        broadcast(1, 11, false),
+        broadcast(2, 11, true),
+        broadcast(3, 12, true),
+    ];
+    let mappings = vec![NistNetworkMapping {
+        nist_network: "ABC".to_string(),
+        news_network: Some(news_network.clone()),
+        ..Default::default()
+    }];
+    let video = NistVideo {
+        video_id: 13,
+        video_title: "ABC 9/11 Broadcast".to_string(),
+        network: Some("ABC ".to_string()),
+        broadcast_date: NaiveDate::from_ymd_opt(2001, 9, 11),
+        ..Default::default()
+    };
+
+    let mut form = Form::from(&MasterVideo::default());
+    prefill_from_nist_video(&mut form, &video, &mappings, &news_broadcasts).unwrap();
+    assert_eq!(
+        form.get_field("News Broadcasts").unwrap().value(),
+        "ABC News (2001-09-11)"
+    );
+    assert_eq!(
+        form.get_field("NIST Videos").unwrap().value(),
+        "13: ABC 9/11 Broadcast"
+    );
+
+    let mut form = Form::from(&MasterVideo::default());
+    prefill_from_nist_video(&mut form, &video, &[], &news_broadcasts).unwrap();
+    assert_eq!(form.get_field("News Broadcasts").unwrap().value(), "");
+}
+
+#[test]
+fn records_should_round_trip_a_master_video_form_through_yaml_and_toml() {
+    let form_input = std::fs::read_to_string("../resources/master_form_completed")
+        .expect("Failed to read test form");
+    let form = Form::from_master_video_str(&form_input).unwrap();
+
+    for format in [RecordFormat::Yaml, RecordFormat::Toml] {
+        let record = record_from_form(5, &form);
+        let document = write_records(vec![record], format).unwrap();
+        let records = parse_records(&document, format).unwrap();
+        assert_eq!(records.len(), 1);
+        let (id, parsed) = form_from_record(form.schema.unwrap(), &records[0]).unwrap();
+        assert_eq!(id, 5);
+        assert_eq!(parsed.as_string(), form.as_string());
+    }
+}
+
+#[test]
+fn parse_records_should_read_batches_of_records() {
+    let yaml_sequence = "\
+- name: ABC News
+  description: National ABC News coverage
+- id: 2
+  name: CBS News
+  description: National CBS News coverage
+";
+    let yaml_documents = "\
+name: ABC News
+description: National ABC News coverage
+---
+id: 2
+name: CBS News
+description: National CBS News coverage
+";
+    let toml_batch = "\
+[[records]]
+name = \"ABC News\"
+description = \"National ABC News coverage\"
+
+[[records]]
+id = 2
+name = \"CBS News\"
+description = \"National CBS News coverage\"
+";
+    for (document, format) in [
+        (yaml_sequence, RecordFormat::Yaml),
+        (yaml_documents, RecordFormat::Yaml),
+        (toml_batch, RecordFormat::Toml),
+    ] {
+        let records = parse_records(document, format).unwrap();
+        let networks = records
+            .iter()
+            .map(|r| {
+                let (id, form) = form_from_record(&NEWS_NETWORK_FORM, r).unwrap();
+                news_network_from_form(id, &form).unwrap()
+            })
+            .collect::<Vec<NewsNetwork>>();
+        assert_eq!(
+            networks,
+            vec![
+                NewsNetwork {
+                    id: 0,
+                    name: "ABC News".to_string(),
+                    description: "National ABC News coverage".to_string(),
+                },
+                NewsNetwork {
+                    id: 2,
+                    name: "CBS News".to_string(),
+                    description: "National CBS News coverage".to_string(),
+                },
+            ]
+        );
+    }
+
+    let unknown_field =
+        parse_records("name: ABC News\nwebsite: abc.com", RecordFormat::Yaml).unwrap();
+    let error = form_from_record(&NEWS_NETWORK_FORM, &unknown_field[0])
+        .err()
+        .unwrap()
+        .downcast::<FormError>()
+        .unwrap();
+    assert!(matches!(error, FormError::UnknownField(key) if key == "website"));
+}
+
+#[test]
+fn set_value_should_replace_the_value_of_a_field_chosen_by_prompt() {
+    let mut form = Form::from(&MasterVideo::default());
+    let values = [
+        (
+            "Categories",
+            FieldValue::List(vec!["news".to_string(), "documentary".to_string()]),
+        ),
+        ("Date", FieldValue::Text("2001-09-11".to_string())),
+        ("Description", FieldValue::Text("NBC coverage".to_string())),
+        (
+            "Reporters",
+            FieldValue::List(vec!["Pat Dawson".to_string()]),
+        ),
+        ("Title", FieldValue::Text("WTC2 Collapse".to_string())),
+    ];
+    for (name, value) in values.iter() {
+        form.set_value(name, value).unwrap();
+    }
+
+    let form = Form::from_master_video_str(&form.as_string()).unwrap();
+    let master_video = master_video_from_form(
+        0,
+        &form,
+        &[],
+        &[],
+        &[],
+        &event_types(),
+        SimilarNames::Reject,
+    )
+    .unwrap();
+    assert_eq!(master_video.title, "WTC2 Collapse");
+    assert_eq!(
+        master_video.categories,
+        vec![Category::News, Category::Documentary]
+    );
+    assert_eq!(master_video.people.len(), 1);
+    assert_eq!(master_video.people[0].name, "Pat Dawson");
+
+    let mut form = Form::from(&MasterVideo::default());
+    assert!(matches!(
+        form.set_value("Website", &FieldValue::Text(String::new())),
+        Err(FormError::FieldNotFound(_))
+    ));
+}
+
+#[test]
+fn master_video_from_form_should_reject_a_new_person_close_to_an_existing_person() {
+    let people = vec![
+        Person {
+            description: Some("WABC-TV helicopter reporter".to_string()),
+            historical_title: None,
+            id: 1,
+            name: "John DelGiorno".to_string(),
+            types: vec![PersonType::Reporter],
+        },
+        Person {
+            description: None,
+            historical_title: None,
+            id: 2,
+            name: "Lori Stokes".to_string(),
+            types: vec![PersonType::Reporter],
+        },
+    ];
+    assert_eq!(find_similar_people("John Del Giorno", &people)[0].id, 1);
+    assert_eq!(find_similar_people("lori stoke", &people)[0].id, 2);
+    assert!(find_similar_people("Pat Dawson", &people).is_empty());
+
+    let mut form = Form::from(&MasterVideo::default());
+    let values = [
+        ("Date", FieldValue::Text("2001-09-11".to_string())),
+        ("Categories", FieldValue::List(vec!["news".to_string()])),
+        ("Description", FieldValue::Text("WABC coverage".to_string())),
+        (
+            "Reporters",
+            FieldValue::List(vec![
+                "Lori Stokes".to_string(),
+                "John Del Giorno".to_string(),
+            ]),
+        ),
+        ("Title", FieldValue::Text("WABC-TV".to_string())),
+    ];
+    for (name, value) in values.iter() {
+        form.set_value(name, value).unwrap();
+    }
+    let form = Form::from_master_video_str(&form.as_string()).unwrap();
+    let error = master_video_from_form(
+        0,
+        &form,
+        &[],
+        &[],
+        &people,
+        &event_types(),
+        SimilarNames::Reject,
+    )
+    .err()
+    .unwrap()
+    .downcast::<FormError>()
+    .unwrap();
+    assert!(error.to_string().ends_with(
+        "'John Del Giorno' is not an existing person, but is close to \
+         John DelGiorno (WABC-TV helicopter reporter): 'John Del Giorno'"
+    ));
+}
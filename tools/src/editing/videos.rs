@@ -1,55 +1,45 @@
-use super::fields::{
-    BooleanField, ChoiceField, OptionalMultilineListField, OptionalMultilineTextField, TextField,
-};
 use super::forms::{Form, FormError};
+use super::schema::{ChoicesSource, FieldDef, FieldKind, FormSchema};
 use color_eyre::{eyre::eyre, Result};
 use db::helpers::{duration_to_string, interval_to_duration, parse_duration};
 use db::models::{MasterVideo, Video};
 use sqlx::postgres::types::PgInterval;
 
+pub static VIDEO_FORM: FormSchema = FormSchema {
+    fields: &[
+        FieldDef::required("Master", FieldKind::Choice).with_choices(ChoicesSource::MasterVideos),
+        FieldDef::required("Title", FieldKind::Text),
+        FieldDef::required("Channel", FieldKind::Text),
+        FieldDef::optional("Description", FieldKind::MultilineText),
+        FieldDef::required("Link", FieldKind::Text),
+        FieldDef::required("Duration", FieldKind::Text),
+        FieldDef::required("Primary", FieldKind::Boolean),
+    ],
+};
+
 impl Form {
     pub fn from_video_str(s: &str) -> Result<Self, FormError> {
-        let parts: Vec<_> = s.split("---\n").collect();
-        if parts.len() != 7 {
-            return Err(FormError::MalformedForm);
-        }
-
-        let mut form = Form::default();
-
-        form.add_field(Box::new(ChoiceField::from_input_str("Master", parts[0])?));
-        form.add_field(Box::new(TextField::from_input_str("Title", parts[1])?));
-        form.add_field(Box::new(TextField::from_input_str("Channel", parts[2])?));
-        form.add_field(Box::new(OptionalMultilineListField::from_input_str(
-            "Description",
-            parts[3],
-        )?));
-        form.add_field(Box::new(TextField::from_input_str("Link", parts[4])?));
-        form.add_field(Box::new(TextField::from_input_str("Duration", parts[5])?));
-        form.add_field(Box::new(BooleanField::from_input_str("Primary", parts[6])?));
-
-        Ok(form)
+        VIDEO_FORM.parse(s)
     }
 }
 
 impl From<&Video> for Form {
     fn from(video: &Video) -> Self {
-        let mut form = Form::default();
-
-        form.add_field(Box::new(ChoiceField::new("Master", &video.master.title)));
-        form.add_field(Box::new(TextField::new("Title", &video.title)));
-        form.add_field(Box::new(TextField::new("Channel", &video.channel_username)));
-        form.add_field(Box::new(OptionalMultilineTextField::new(
-            "Description",
-            video.description.as_ref().unwrap_or(&"".to_string()),
-        )));
-        form.add_field(Box::new(TextField::new("Link", &video.title)));
-        form.add_field(Box::new(TextField::new(
-            "Duration",
-            &duration_to_string(&interval_to_duration(&video.duration)),
-        )));
-        form.add_field(Box::new(BooleanField::new("Primary", video.is_primary)));
-
-        form
+        VIDEO_FORM.create(vec![
+            ("Master", video.master.title.clone().into()),
+            ("Title", video.title.clone().into()),
+            ("Channel", video.channel_username.clone().into()),
+            (
+                "Description",
+                video.description.clone().unwrap_or_default().into(),
+            ),
+            ("Link", video.link.clone().into()),
+            (
+                "Duration",
+                duration_to_string(&interval_to_duration(&video.duration)).into(),
+            ),
+            ("Primary", video.is_primary.into()),
+        ])
     }
 }
 
@@ -58,7 +48,13 @@ pub fn video_from_form(id: i32, form: &Form, masters: &[MasterVideo]) -> Result<
     let master = masters
         .iter()
         .find(|m| m.title == master_title)
-        .ok_or_else(|| eyre!("{master_title} is not in the master list"))?;
+        .ok_or_else(|| {
+            form.locate(
+                "Master",
+                &master_title,
+                FormError::InvalidChoice(master_title.clone()),
+            )
+        })?;
 
     let title = form.get_field("Title")?.value();
     let channel_username = form.get_field("Channel")?.value();
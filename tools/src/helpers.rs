@@ -1,6 +1,8 @@
-use chrono::Duration;
+use chrono::{Duration, NaiveTime};
+use clap::ValueEnum;
 use sqlx::postgres::types::PgInterval;
 use std::path::{Path, PathBuf};
+use url::Url;
 
 pub fn interval_to_duration(interval: &PgInterval) -> Duration {
     let total_microseconds = interval.microseconds;
@@ -68,3 +70,34 @@ pub fn print_banner(text: &str) {
     println!("║ {:^width$} ║", text, width = text_width);
     println!("╚{}╝", top_bottom);
 }
+
+#[derive(Clone, Debug, ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+/// Parses a time of day in the `HHMM` form used throughout the timestamps, e.g., `0903`.
+pub fn parse_time_of_day(s: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(s, "%H%M").map_err(|_| format!("'{s}' is not a time in HHMM form"))
+}
+
+/// Gets the ID of a YouTube video from a link to it, such as `https://youtu.be/<id>` or
+/// `https://www.youtube.com/watch?v=<id>&t=10s`.
+///
+/// The same video can be linked in several ways, so videos are compared by this ID rather than by
+/// their links. Returns `None` for anything that isn't a link to a YouTube video.
+pub fn youtube_id_from_link(link: &str) -> Option<String> {
+    let url = Url::parse(link).ok()?;
+    let host = url.host_str()?;
+    let id = if host == "youtu.be" {
+        url.path().trim_start_matches('/').to_string()
+    } else if host == "youtube.com" || host.ends_with(".youtube.com") {
+        url.query_pairs()
+            .find(|(key, _)| key == "v")
+            .map(|(_, value)| value.to_string())?
+    } else {
+        return None;
+    };
+    (!id.is_empty()).then_some(id)
+}
@@ -0,0 +1,196 @@
+use db::models::NistTape;
+use std::collections::{HashMap, HashSet};
+
+/// The copies of NIST's tapes, resolved from the tape each one was derived from.
+///
+/// A tape with a `derived_from` of 0 is an original. Any other value is the ID of the tape it was
+/// copied from, so the tapes form a set of trees, one for each original.
+pub struct Lineage<'a> {
+    tapes: &'a [NistTape],
+    by_id: HashMap<i32, &'a NistTape>,
+    children: HashMap<i32, Vec<&'a NistTape>>,
+    /// The first tape matched to release files in each tree, keyed by the ID of its root.
+    allocated: HashMap<i32, Option<&'a NistTape>>,
+}
+
+impl<'a> Lineage<'a> {
+    pub fn new(tapes: &'a [NistTape]) -> Self {
+        let by_id: HashMap<i32, &NistTape> = tapes.iter().map(|t| (t.tape_id, t)).collect();
+        let mut children: HashMap<i32, Vec<&NistTape>> = HashMap::new();
+        for tape in tapes.iter() {
+            if tape.derived_from != 0 && tape.derived_from != tape.tape_id {
+                children.entry(tape.derived_from).or_default().push(tape);
+            }
+        }
+        for copies in children.values_mut() {
+            copies.sort_by_key(|t| t.tape_id);
+        }
+        let mut lineage = Self {
+            tapes,
+            by_id,
+            children,
+            allocated: HashMap::new(),
+        };
+        let mut allocated = HashMap::new();
+        for tape in tapes.iter() {
+            let root = lineage.root(tape);
+            allocated.entry(root.tape_id).or_insert_with(|| {
+                lineage
+                    .members(tape)
+                    .into_iter()
+                    .map(|(_, m)| m)
+                    .find(|m| !m.release_files.is_empty())
+            });
+        }
+        lineage.allocated = allocated;
+        lineage
+    }
+
+    pub fn get(&self, tape_id: i32) -> Option<&'a NistTape> {
+        self.by_id.get(&tape_id).copied()
+    }
+
+    pub fn parent(&self, tape: &NistTape) -> Option<&'a NistTape> {
+        if tape.derived_from == 0 || tape.derived_from == tape.tape_id {
+            return None;
+        }
+        self.get(tape.derived_from)
+    }
+
+    /// The tapes copied directly from the given tape, in order of their IDs.
+    pub fn children(&self, tape: &NistTape) -> Vec<&'a NistTape> {
+        self.children
+            .get(&tape.tape_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The tapes the given tape was copied from, starting with its parent.
+    ///
+    /// The chain stops at a tape that has already been seen, so a cycle in NIST's data can't make
+    /// it loop forever.
+    pub fn ancestors(&self, tape: &NistTape) -> Vec<&'a NistTape> {
+        let mut ancestors: Vec<&NistTape> = Vec::new();
+        let mut current = self.parent(tape);
+        while let Some(parent) = current {
+            if parent.tape_id == tape.tape_id
+                || ancestors.iter().any(|a| a.tape_id == parent.tape_id)
+            {
+                break;
+            }
+            ancestors.push(parent);
+            current = self.parent(parent);
+        }
+        ancestors
+    }
+
+    /// The tape at the top of the tree the given tape belongs to.
+    pub fn root(&self, tape: &'a NistTape) -> &'a NistTape {
+        self.ancestors(tape).last().copied().unwrap_or(tape)
+    }
+
+    /// Every tape in the same tree as the given tape, starting from its root, with the depth of
+    /// each one below the root.
+    pub fn members(&self, tape: &'a NistTape) -> Vec<(usize, &'a NistTape)> {
+        let mut members = Vec::new();
+        let mut seen = HashSet::new();
+        let mut stack = vec![(0, self.root(tape))];
+        while let Some((depth, current)) = stack.pop() {
+            if !seen.insert(current.tape_id) {
+                continue;
+            }
+            members.push((depth, current));
+            for child in self.children(current).into_iter().rev() {
+                stack.push((depth + 1, child));
+            }
+        }
+        members
+    }
+
+    /// The tapes derived from a tape that doesn't exist.
+    pub fn orphans(&self) -> Vec<&'a NistTape> {
+        self.tapes
+            .iter()
+            .filter(|t| t.derived_from != 0 && self.get(t.derived_from).is_none())
+            .collect()
+    }
+
+    /// A tape in the same tree that has been matched to release files, if the given tape hasn't
+    /// been matched itself.
+    ///
+    /// The copies of a tape have the same content, so when one of them is in a release the others
+    /// are covered by it.
+    pub fn covered_by(&self, tape: &'a NistTape) -> Option<&'a NistTape> {
+        if !tape.release_files.is_empty() {
+            return None;
+        }
+        let root = self.root(tape);
+        match self.allocated.get(&root.tape_id) {
+            Some(allocated) => *allocated,
+            None => self
+                .members(tape)
+                .into_iter()
+                .map(|(_, m)| m)
+                .find(|m| !m.release_files.is_empty()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn tape(tape_id: i32, derived_from: i32) -> NistTape {
+        NistTape {
+            tape_id,
+            derived_from,
+            ..Default::default()
+        }
+    }
+
+    fn ids(tapes: &[&NistTape]) -> Vec<i32> {
+        tapes.iter().map(|t| t.tape_id).collect()
+    }
+
+    #[test]
+    fn lineage_should_resolve_ancestors_and_members() {
+        let tapes = vec![tape(1, 0), tape(2, 1), tape(3, 1), tape(4, 2), tape(5, 0)];
+        let lineage = Lineage::new(&tapes);
+        assert_eq!(ids(&lineage.ancestors(&tapes[3])), vec![2, 1]);
+        assert_eq!(lineage.root(&tapes[3]).tape_id, 1);
+        assert_eq!(ids(&lineage.children(&tapes[0])), vec![2, 3]);
+        let members: Vec<(usize, i32)> = lineage
+            .members(&tapes[2])
+            .into_iter()
+            .map(|(depth, t)| (depth, t.tape_id))
+            .collect();
+        assert_eq!(members, vec![(0, 1), (1, 2), (2, 4), (1, 3)]);
+        assert_eq!(lineage.members(&tapes[4]).len(), 1);
+    }
+
+    #[test]
+    fn lineage_should_stop_at_a_cycle() {
+        let tapes = vec![tape(1, 2), tape(2, 1)];
+        let lineage = Lineage::new(&tapes);
+        assert_eq!(ids(&lineage.ancestors(&tapes[0])), vec![2]);
+        assert_eq!(lineage.members(&tapes[0]).len(), 2);
+    }
+
+    #[test]
+    fn orphans_should_find_tapes_derived_from_missing_tapes() {
+        let tapes = vec![tape(1, 0), tape(2, 1), tape(3, 9)];
+        assert_eq!(ids(&Lineage::new(&tapes).orphans()), vec![3]);
+    }
+
+    #[test]
+    fn covered_by_should_find_an_allocated_tape_in_the_same_tree() {
+        let mut tapes = vec![tape(1, 0), tape(2, 1), tape(3, 1), tape(4, 0)];
+        tapes[1].release_files = vec![(PathBuf::from("a.mpg"), 1)];
+        let lineage = Lineage::new(&tapes);
+        assert_eq!(lineage.covered_by(&tapes[0]).map(|t| t.tape_id), Some(2));
+        assert_eq!(lineage.covered_by(&tapes[2]).map(|t| t.tape_id), Some(2));
+        assert!(lineage.covered_by(&tapes[1]).is_none());
+        assert!(lineage.covered_by(&tapes[3]).is_none());
+    }
+}
@@ -1,10 +1,22 @@
+pub mod audit;
+pub mod chapters;
 pub mod cmd;
+pub mod dataset;
+pub mod diff;
 pub mod editing;
 pub mod helpers;
+pub mod lineage;
 pub mod releases;
 pub mod static_data;
+pub mod times;
 
+use crate::chapters::ChapterFormat;
+use crate::cmd::nist_status::StatusGroup;
+use crate::editing::records::{RecordEntity, RecordFormat};
+use crate::helpers::{parse_time_of_day, ExportFormat};
 use crate::releases::*;
+use crate::times::{parse_anchor, parse_offset, Anchor};
+use chrono::{Duration, NaiveTime};
 use clap::{Parser, Subcommand};
 use color_eyre::Result;
 use std::path::PathBuf;
@@ -20,6 +32,30 @@ struct Opt {
 enum Commands {
     #[clap(subcommand)]
     Cumulus(CumulusSubcommands),
+    #[clap(subcommand)]
+    Dataset(DatasetSubcommands),
+    #[clap(subcommand, name = "event-types")]
+    EventTypes(EventTypesSubcommands),
+    /// Show the changes made to a record over time, or restore an earlier revision.
+    ///
+    /// Every save of a catalogue record is written to an audit log, with snapshots of the record
+    /// before and after the change, the user who made it and when. The user is taken from the
+    /// `AW_USER` environment variable, or `USER` if it isn't set.
+    #[clap(name = "history")]
+    History {
+        /// The kind of record.
+        #[arg(long, value_enum)]
+        entity: RecordEntity,
+        /// The ID of the record.
+        #[arg(long)]
+        id: u32,
+        /// The number of a revision to restore the record to.
+        ///
+        /// The difference between the current record and the revision is displayed before it's
+        /// restored. Restoring a revision is itself recorded as a change.
+        #[arg(long)]
+        restore: Option<u32>,
+    },
     #[clap(subcommand, name = "masters")]
     MasterVideos(MasterVideosSubcommands),
     #[clap(subcommand)]
@@ -27,9 +63,59 @@ enum Commands {
     #[clap(subcommand)]
     Nist(NistSubcommands),
     #[clap(subcommand)]
+    Records(RecordsSubcommands),
+    #[clap(subcommand)]
     Releases(ReleasesSubcommands),
+    /// Full-text search across the whole catalogue.
+    ///
+    /// Master videos, event timestamps, NIST videos and tapes, news broadcasts and people are all
+    /// searched. The query supports web search syntax: quoted phrases, `or`, and `-` to exclude a
+    /// term.
+    #[clap(name = "search")]
+    Search {
+        /// The search query.
+        query: String,
+        /// The maximum number of results to display.
+        #[arg(long, default_value_t = 50)]
+        limit: u32,
+    },
+    /// Display a chronological timeline of events across all master videos.
+    ///
+    /// Every timestamp with a time of day is merged into a single view, so it's possible to see what
+    /// each camera was showing at a given minute. Timestamps without a time of day are excluded.
+    ///
+    /// The timeline is printed by default. Use --format and/or --out-path to export it as CSV or
+    /// JSON. If only --out-path is used, the format is inferred from the file extension.
+    #[clap(name = "timeline")]
+    Timeline {
+        /// Only include events up to this time of day, in HHMM form.
+        #[arg(long, value_parser = parse_time_of_day)]
+        end_time: Option<NaiveTime>,
+        /// Only include events of this type, e.g., wtc2-impact.
+        ///
+        /// The argument can be used more than once.
+        #[arg(long)]
+        event_type: Vec<String>,
+        /// Export the timeline in this format.
+        #[arg(long, value_enum)]
+        format: Option<ExportFormat>,
+        /// Only include master videos with a broadcast from this network or affiliate.
+        #[arg(long)]
+        network: Option<String>,
+        /// Path to an output file for the export.
+        #[arg(long)]
+        out_path: Option<PathBuf>,
+        /// Only include master videos featuring this person.
+        #[arg(long)]
+        person: Option<String>,
+        /// Only include events from this time of day, in HHMM form.
+        #[arg(long, value_parser = parse_time_of_day)]
+        start_time: Option<NaiveTime>,
+    },
     #[clap(subcommand)]
     Videos(VideosSubcommands),
+    #[clap(subcommand, name = "youtube")]
+    YouTube(YouTubeSubcommands),
 }
 
 /// Tools for working with the Cumulus exports
@@ -64,6 +150,93 @@ enum CumulusSubcommands {
     },
 }
 
+/// Keep the curated catalogue as a directory of files that can be versioned.
+///
+/// The dataset includes event types, master videos with their timestamps, people, videos, news
+/// networks, affiliates and broadcasts, NIST tape allocations and notes on NIST videos. Each record
+/// is a YAML file in a directory for its kind, named by its ID, and records refer to each other by
+/// ID. Event types are named and referred to by their slug.
+#[derive(Subcommand, Debug)]
+enum DatasetSubcommands {
+    /// Export the catalogue to a directory.
+    ///
+    /// The output is the same for the same data, so changes can be reviewed as diffs. Any existing
+    /// record files in the directory are replaced.
+    #[clap(name = "export")]
+    Export {
+        /// Path to the directory. It will be created if it doesn't exist.
+        #[arg(long)]
+        dir: PathBuf,
+    },
+    /// Import a dataset directory, adding or updating each record with the ID it has in the
+    /// dataset.
+    ///
+    /// Every reference between records is checked before anything is saved, as are references to
+    /// the NIST tapes, videos and release files, which must already be in the database. This
+    /// makes it possible to rebuild the catalogue on an empty database once the NIST data and the
+    /// releases have been imported.
+    #[clap(name = "import")]
+    Import {
+        /// Path to the directory.
+        #[arg(long)]
+        dir: PathBuf,
+    },
+}
+
+/// Manage the types of event that timestamps can mark.
+///
+/// Each type is identified by its slug, which is written in square brackets at the end of each
+/// timestamp. Types with a canonical time are key events, which are used to check and derive the
+/// times of day of timestamps.
+#[derive(Subcommand, Debug)]
+enum EventTypesSubcommands {
+    /// Add an event type
+    #[clap(name = "add")]
+    Add {
+        /// The identifier of the type, e.g., wtc7-collapse
+        #[arg(long)]
+        slug: String,
+        /// A short name for the type, e.g., WTC7 collapse
+        #[arg(long)]
+        label: String,
+        /// A description of the events of this type
+        #[arg(long)]
+        description: Option<String>,
+        /// The time of day at which the event occurred, in HH:MM:SS form, e.g., 17:20:33
+        #[arg(long)]
+        canonical_time: Option<NaiveTime>,
+    },
+    /// Change the label, description or canonical time of an event type
+    #[clap(name = "edit")]
+    Edit {
+        /// The identifier of the type
+        #[arg(long)]
+        slug: String,
+        /// A short name for the type
+        #[arg(long)]
+        label: Option<String>,
+        /// A description of the events of this type
+        #[arg(long)]
+        description: Option<String>,
+        /// The time of day at which the event occurred, in HH:MM:SS form
+        #[arg(long, conflicts_with = "no_canonical_time")]
+        canonical_time: Option<NaiveTime>,
+        /// Remove the canonical time, so the type is no longer a key event
+        #[arg(long)]
+        no_canonical_time: bool,
+    },
+    /// List the event types
+    #[clap(name = "ls")]
+    Ls {},
+    /// Remove an event type that isn't used by any timestamps
+    #[clap(name = "rm")]
+    Rm {
+        /// The identifier of the type
+        #[arg(long)]
+        slug: String,
+    },
+}
+
 /// Manage master videos
 #[derive(Subcommand, Debug)]
 enum MasterVideosSubcommands {
@@ -76,12 +249,66 @@ enum MasterVideosSubcommands {
         /// Path to a file containing a completed master video form.
         #[arg(long)]
         path: Option<PathBuf>,
+        /// Choose the values of fields with choices using fuzzy selection, rather than from lists
+        /// in the form.
+        ///
+        /// The editor is then opened to complete the rest of the form.
+        #[arg(long, conflicts_with = "path")]
+        prompt: bool,
+        /// The ID of a NIST video the master is made from.
+        ///
+        /// The form starts with the NIST video chosen, along with the news broadcasts on the same
+        /// date from the network its network name is mapped to.
+        #[arg(long, conflicts_with = "path")]
+        nist_video_id: Option<i32>,
+    },
+    /// Check the key events of master videos against the times the events happened.
+    ///
+    /// Key events are the impacts, the attack on the Pentagon and the collapses. Each one is
+    /// compared with its canonical time, using the time of day of the timestamp, or the time
+    /// inferred from the other timestamps. The offsets between the key events are also checked to
+    /// find videos that have been edited or have events tagged incorrectly.
+    #[clap(name = "audit-times")]
+    AuditTimes {
+        /// The ID of a master video to check. All master videos are checked if not provided.
+        #[arg(long)]
+        id: Option<u32>,
+        /// The number of minutes a key event can differ from its canonical time
+        #[arg(long, default_value_t = 2)]
+        tolerance: u32,
     },
     /// Edit a master video using an interactive editor.
     #[clap(name = "edit")]
     Edit {
         #[arg(long)]
         id: u32,
+        /// Choose the values of fields with choices using fuzzy selection, rather than from lists
+        /// in the form.
+        ///
+        /// The editor is then opened to complete the rest of the form.
+        #[arg(long)]
+        prompt: bool,
+    },
+    /// Export the timestamps of a master video as chapters or subtitles.
+    ///
+    /// The chapters can be muxed into the video files, or pasted into a YouTube description. The
+    /// subtitles show the time of day along with the description of each timestamp.
+    #[clap(name = "export-chapters")]
+    ExportChapters {
+        /// The ID of the master video
+        #[arg(long)]
+        id: u32,
+        /// The format to export to
+        #[arg(long, value_enum)]
+        format: ChapterFormat,
+        /// Path of the file to write to. The output is written to stdout if not provided.
+        #[arg(long)]
+        out_path: Option<PathBuf>,
+        /// The length of the video, e.g., 01:58:32, used to end the final chapter.
+        ///
+        /// Without it, the final chapter lasts for ten seconds.
+        #[arg(long)]
+        video_length: Option<String>,
     },
     /// List all the master videos
     #[clap(name = "ls")]
@@ -92,6 +319,116 @@ enum MasterVideosSubcommands {
         #[arg(long)]
         id: u32,
     },
+    /// Derive the time of day for the timestamps of a master video from anchor points.
+    ///
+    /// Timestamps that already have a time of day are used as anchors. Further anchors can be
+    /// supplied on the command line, or taken from key events, such as the impacts and collapses,
+    /// whose times are known. Every other timestamp gets its time of day from the nearest anchor
+    /// before it.
+    ///
+    /// Anchors that are later than the previous anchor predicts are treated as a break in the
+    /// footage. Those that are earlier are reported as inconsistent and left alone.
+    #[clap(name = "sync-times")]
+    SyncTimes {
+        /// The ID of the master video
+        #[arg(long)]
+        id: u32,
+        /// An offset into the video and its time of day, e.g., 00:20:00=0903.
+        ///
+        /// Can be used multiple times.
+        #[arg(long = "anchor", value_parser = parse_anchor)]
+        anchors: Vec<Anchor>,
+        /// Show the derived times without saving them
+        #[arg(long)]
+        dry_run: bool,
+        /// Use the first occurrence of each key event as an anchor
+        #[arg(long)]
+        key_events: bool,
+        /// The number of minutes an anchor can differ from the predicted time before it is
+        /// considered a break in the footage or inconsistent
+        #[arg(long, default_value_t = 2)]
+        tolerance: u32,
+    },
+    #[clap(subcommand)]
+    Timestamps(MasterVideosTimestampsSubcommands),
+}
+
+/// Manage the timestamps of a master video.
+///
+/// The timestamps are kept in order of their offsets. Changes are rejected if a timestamp ends
+/// before it starts, or falls after the end of the longest video of the master.
+#[derive(Subcommand, Debug)]
+enum MasterVideosTimestampsSubcommands {
+    /// Add a timestamp to a master video
+    #[clap(name = "add")]
+    Add {
+        /// The ID of the master video
+        #[arg(long)]
+        id: u32,
+        /// The offset of the event into the video, e.g., 00:20:00
+        #[arg(long, value_parser = parse_offset)]
+        offset: Duration,
+        /// The offset at which the event ends, for events with a duration
+        #[arg(long, value_parser = parse_offset)]
+        end: Option<Duration>,
+        /// The slug of the type of event, e.g., normal, report or wtc2-impact
+        #[arg(long, default_value = "normal")]
+        event_type: String,
+        /// The time of day of the event, e.g., 0903
+        #[arg(long, value_parser = parse_time_of_day)]
+        time_of_day: Option<NaiveTime>,
+        /// A description of the event
+        #[arg(long)]
+        description: String,
+    },
+    /// Change the fields of a timestamp. Fields that are not provided are left as they are.
+    #[clap(name = "edit")]
+    Edit {
+        /// The ID of the master video
+        #[arg(long)]
+        id: u32,
+        /// The ID of the timestamp
+        #[arg(long)]
+        timestamp_id: u32,
+        /// The offset of the event into the video, e.g., 00:20:00
+        #[arg(long, value_parser = parse_offset)]
+        offset: Option<Duration>,
+        /// The offset at which the event ends
+        #[arg(long, value_parser = parse_offset, conflicts_with = "no_end")]
+        end: Option<Duration>,
+        /// Remove the end of the event
+        #[arg(long)]
+        no_end: bool,
+        /// The slug of the type of event
+        #[arg(long)]
+        event_type: Option<String>,
+        /// The time of day of the event, e.g., 0903
+        #[arg(long, value_parser = parse_time_of_day, conflicts_with = "no_time_of_day")]
+        time_of_day: Option<NaiveTime>,
+        /// Remove the time of day of the event
+        #[arg(long)]
+        no_time_of_day: bool,
+        /// A description of the event
+        #[arg(long)]
+        description: Option<String>,
+    },
+    /// List the timestamps of a master video, along with any problems with their offsets
+    #[clap(name = "ls")]
+    Ls {
+        /// The ID of the master video
+        #[arg(long)]
+        id: u32,
+    },
+    /// Remove a timestamp from a master video
+    #[clap(name = "rm")]
+    Rm {
+        /// The ID of the master video
+        #[arg(long)]
+        id: u32,
+        /// The ID of the timestamp
+        #[arg(long)]
+        timestamp_id: u32,
+    },
 }
 
 /// Manage news broadcasts, networks and affiliates.
@@ -143,6 +480,12 @@ enum NewsAffiliatesSubcommands {
         /// Path to a file containing a populated news affiliate template.
         #[arg(long)]
         path: Option<PathBuf>,
+        /// Choose the values of fields with choices using fuzzy selection, rather than from lists
+        /// in the form.
+        ///
+        /// The editor is then opened to complete the rest of the form.
+        #[arg(long, conflicts_with = "path")]
+        prompt: bool,
     },
     /// Edit a news affiliate
     #[clap(name = "edit")]
@@ -150,6 +493,12 @@ enum NewsAffiliatesSubcommands {
         /// The ID of the affiliate to edit
         #[arg(long)]
         id: u32,
+        /// Choose the values of fields with choices using fuzzy selection, rather than from lists
+        /// in the form.
+        ///
+        /// The editor is then opened to complete the rest of the form.
+        #[arg(long)]
+        prompt: bool,
     },
     /// List all news affiliates
     #[clap(name = "ls")]
@@ -172,6 +521,12 @@ enum NewsBroadcastsSubcommands {
         /// Path to a file containing a populated broadcast form.
         #[arg(long)]
         path: Option<PathBuf>,
+        /// Choose the values of fields with choices using fuzzy selection, rather than from lists
+        /// in the form.
+        ///
+        /// The editor is then opened to complete the rest of the form.
+        #[arg(long, conflicts_with = "path")]
+        prompt: bool,
     },
     /// Edit a news broadcast
     #[clap(name = "edit")]
@@ -179,6 +534,12 @@ enum NewsBroadcastsSubcommands {
         /// The ID of the broadcast to edit
         #[arg(long)]
         id: u32,
+        /// Choose the values of fields with choices using fuzzy selection, rather than from lists
+        /// in the form.
+        ///
+        /// The editor is then opened to complete the rest of the form.
+        #[arg(long)]
+        prompt: bool,
     },
     /// List all news broadcasts
     #[clap(name = "ls")]
@@ -197,6 +558,27 @@ enum NewsBroadcastsSubcommands {
 enum NistSubcommands {
     #[clap(subcommand)]
     Import(NistImportSubcommands),
+    /// Show how far the NIST videos have got through the archive.
+    ///
+    /// For each video, this works out whether it is missing, has tapes, has tapes allocated to
+    /// release files, has a master video, and has a master with an online video. The totals are
+    /// printed overall and by network, subject and broadcast date.
+    ///
+    /// Use --format and/or --out-path to export the status of each video as CSV or JSON, or the
+    /// totals for one group if --by is also used. If only --out-path is used, the format is inferred
+    /// from the file extension.
+    #[clap(name = "status")]
+    Status {
+        /// Only show or export the totals for this group.
+        #[arg(long, value_enum)]
+        by: Option<StatusGroup>,
+        /// Export the status in this format.
+        #[arg(long, value_enum)]
+        format: Option<ExportFormat>,
+        /// Path to an output file for the export.
+        #[arg(long)]
+        out_path: Option<PathBuf>,
+    },
     #[clap(subcommand)]
     Tapes(NistTapesSubcommands),
     #[clap(subcommand)]
@@ -206,35 +588,104 @@ enum NistSubcommands {
 /// Import CSV exports of NIST's Access database tables into the Postgres database.
 #[derive(Subcommand, Debug)]
 enum NistImportSubcommands {
-    /// Import the document database numbers from static data.
+    /// Import identifiers for NIST's tapes or videos from a table in a FOIA response.
+    ///
+    /// Each row of the CSV maps a tape or video ID to an identifier, such as a document database
+    /// number. The identifiers are recorded with the response they came from, so a later response
+    /// with different values doesn't replace the earlier ones. The most recent value is the one
+    /// displayed.
+    #[clap(name = "mapping")]
+    Mapping {
+        /// Path to the CSV file
+        #[arg(long)]
+        csv: PathBuf,
+        /// The column with the identifiers
+        #[arg(long)]
+        column: String,
+        /// The kind of identifier, using lowercase letters, numbers and underscores.
+        ///
+        /// Defaults to the name of the column in that form, e.g., "Document Database Number"
+        /// becomes document_database_number, which is the kind used for the numbers in NIST's
+        /// document database.
+        #[arg(long)]
+        kind: Option<String>,
+        /// The kind of record the IDs refer to
+        #[arg(long, value_enum, default_value = "nist-tape")]
+        entity: RecordEntity,
+        /// The FOIA response the table came from, e.g., "FOIA 09-42 release 14"
+        #[arg(long)]
+        foia_response: String,
+        /// The column with the tape or video IDs.
+        ///
+        /// Defaults to tape_id for tapes and video_id for videos.
+        #[arg(long)]
+        id_column: Option<String>,
+    },
+    /// Import the tables directly from NIST's Access database.
     ///
-    /// The document database number applies to the tapes table, so the tapes need to be imported
-    /// first.
-    #[clap(name = "document-numbers")]
-    DocumentNumbers {},
+    /// The database is read with mdbtools, which must be installed. The Videos and Tapes tables are
+    /// imported as they would be from CSV exports, and any other tables are stored as they are.
+    /// Rows that can't be converted are reported at the end rather than stopping the import.
+    #[clap(name = "mdb")]
+    Mdb {
+        /// Path to the .mdb file
+        #[arg(long)]
+        path: PathBuf,
+        /// Show how the tables differ from the database without importing them
+        #[arg(long)]
+        dry_run: bool,
+    },
     /// Import a CSV export of the NIST Tapes table from their Access database.
     ///
-    /// The videos table must be imported before the tapes table.
+    /// The videos table must be imported before the tapes table. Tapes that were already imported
+    /// are updated, keeping their document database numbers and release files. Tapes that are no
+    /// longer in the export are left in place.
     #[clap(name = "tapes")]
     Tapes {
         /// Path to the CSV export
         #[arg(long)]
         path: PathBuf,
+        /// Show how the export differs from the database without importing it
+        #[arg(long)]
+        dry_run: bool,
     },
     /// Import a CSV export of the NIST Videos table from their Access database.
     ///
-    /// The videos table must be imported before the tapes table.
+    /// The videos table must be imported before the tapes table. Videos that were already imported
+    /// are updated, keeping whether they are missing and their additional notes. Videos that are
+    /// no longer in the export are left in place.
     #[clap(name = "videos")]
     Videos {
         /// Path to the CSV export
         #[arg(long)]
         path: PathBuf,
+        /// Show how the export differs from the database without importing it
+        #[arg(long)]
+        dry_run: bool,
     },
 }
 
 /// Manage tapes from NIST's database.
 #[derive(Subcommand, Debug)]
 enum NistTapesSubcommands {
+    /// Allocate release files to tapes in bulk from a CSV file.
+    ///
+    /// The CSV has a tape_id column and a path column. The path can be a release file or a
+    /// directory, which allocates every release file below it. A tape can appear on more than one
+    /// row. Every row is checked before anything is saved, and the tapes are then saved together,
+    /// so either all of them are allocated or none are.
+    #[clap(name = "allocate")]
+    Allocate {
+        /// Path to the CSV file
+        #[arg(long)]
+        csv: PathBuf,
+        /// Show the changes without saving them.
+        #[arg(long)]
+        dry_run: bool,
+        /// Replace the files already allocated to each tape in the CSV, rather than adding to them.
+        #[arg(long)]
+        replace: bool,
+    },
     /// Edit a tape to associate it with released files.
     #[clap(name = "edit")]
     Edit {
@@ -242,6 +693,16 @@ enum NistTapesSubcommands {
         #[arg(long)]
         id: Option<u32>,
     },
+    /// Print the tree of copies a tape belongs to.
+    ///
+    /// The tree starts from the original tape. Tapes that haven't been matched to release files
+    /// are shown as covered when another tape in the tree has been.
+    #[clap(name = "lineage")]
+    Lineage {
+        /// The ID of the tape.
+        #[arg(long)]
+        id: u32,
+    },
     /// List the tapes.
     ///
     /// By default, the duplicate tapes will be filtered.
@@ -267,6 +728,9 @@ enum NistTapesSubcommands {
         #[arg(long)]
         wrap_length: Option<usize>,
     },
+    /// List the tapes derived from a tape that doesn't exist.
+    #[clap(name = "orphans")]
+    Orphans {},
     /// Print a full tape record.
     #[clap(name = "print")]
     Print {
@@ -291,6 +755,72 @@ enum NistVideosSubcommands {
     /// By default, the duplicate tapes will be filtered.
     #[clap(name = "ls")]
     Ls {},
+    /// List the videos under the news networks and affiliates their network names are mapped to.
+    ///
+    /// Network names that haven't been mapped are listed at the end.
+    #[clap(name = "by-network")]
+    ByNetwork {},
+    /// Map the network names in NIST's videos table to news networks or affiliates.
+    ///
+    /// Each name that hasn't been mapped is presented in turn, to be chosen from the networks and
+    /// affiliates using fuzzy selection. Pressing escape skips a name. Choose "No match" for a name
+    /// that doesn't refer to a single network, so it isn't presented again.
+    #[clap(name = "map-networks")]
+    MapNetworks {
+        /// Also present the names that have already been mapped.
+        #[arg(long)]
+        remap: bool,
+    },
+    /// List the videos that no master video has been made from.
+    #[clap(name = "no-master")]
+    NoMaster {
+        /// Exclude videos marked as missing.
+        #[arg(long)]
+        exclude_missing: bool,
+    },
+}
+
+/// Import and export records as YAML or TOML documents.
+///
+/// The records have the same fields as the forms used for adding and editing, with the labels
+/// written in snake case, e.g., `nist_notes`. Lists are written as arrays. A file can hold a
+/// single record or a batch: a sequence of records, or several documents, in YAML; an array of
+/// tables named `records` in TOML.
+#[derive(Subcommand, Debug)]
+enum RecordsSubcommands {
+    /// Export records to a YAML or TOML document.
+    #[clap(name = "export")]
+    Export {
+        /// The kind of record to export.
+        #[arg(long, value_enum)]
+        entity: RecordEntity,
+        /// The format of the document.
+        ///
+        /// If not provided, the format is inferred from the extension of the output path, and YAML
+        /// is used otherwise.
+        #[arg(long, value_enum)]
+        format: Option<RecordFormat>,
+        /// The ID of a single record to export. All records are exported if not provided.
+        #[arg(long)]
+        id: Option<u32>,
+        /// Path to an output file. The document is written to stdout if not provided.
+        #[arg(long)]
+        out_path: Option<PathBuf>,
+    },
+    /// Add or update records from a YAML or TOML document.
+    ///
+    /// Records with an `id` update the existing record, while those without one are added. NIST
+    /// tapes and videos can only be updated. All the records in the file are validated before any
+    /// of them are saved.
+    #[clap(name = "import")]
+    Import {
+        /// The kind of record in the file.
+        #[arg(long, value_enum)]
+        entity: RecordEntity,
+        /// Path to the file. The format is determined by its extension: yaml, yml or toml.
+        #[arg(long)]
+        path: PathBuf,
+    },
 }
 
 /// Manage 911datasets.org releases
@@ -385,6 +915,7 @@ enum VideosSubcommands {
     /// * When the --path argument is used, a completed form can be supplied.
     /// * When the --youtube-id argument is used, the video will be created based on the entry for
     ///   that video in the SQLite database. The --master-id argument must be used in conjunction.
+    ///   The video is not added if its link has already been added.
     #[clap(name = "add")]
     Add {
         /// The ID of the master video.
@@ -395,6 +926,12 @@ enum VideosSubcommands {
         /// Path to a file containing a populated video template.
         #[arg(long)]
         path: Option<PathBuf>,
+        /// Choose the values of fields with choices using fuzzy selection, rather than from lists
+        /// in the form.
+        ///
+        /// The editor is then opened to complete the rest of the form.
+        #[arg(long, conflicts_with_all = ["path", "youtube_id"])]
+        prompt: bool,
         /// The ID of a YouTube video in the SQLite database.
         ///
         /// If used, the --master-id argument must also be supplied to relate the video to a
@@ -417,6 +954,12 @@ enum VideosSubcommands {
     Edit {
         #[arg(long)]
         id: u32,
+        /// Choose the values of fields with choices using fuzzy selection, rather than from lists
+        /// in the form.
+        ///
+        /// The editor is then opened to complete the rest of the form.
+        #[arg(long)]
+        prompt: bool,
     },
     /// Exports the video list for a given range of releases
     #[clap(name = "export")]
@@ -438,6 +981,37 @@ enum VideosSubcommands {
         #[arg(long)]
         out_path: PathBuf,
     },
+    /// Import several videos from the YouTube archive for a master video.
+    ///
+    /// The videos are either listed in a file, or chosen from a channel in the archive using fuzzy
+    /// selection. Videos whose links have already been added are skipped.
+    #[clap(name = "import-youtube")]
+    ImportYoutube {
+        /// The ID or title of a channel in the archive to choose the videos from.
+        #[arg(
+            long,
+            conflicts_with = "ids_file",
+            required_unless_present = "ids_file"
+        )]
+        channel: Option<String>,
+        /// Show the videos that would be imported without saving them.
+        #[arg(long)]
+        dry_run: bool,
+        /// Path to a file with a YouTube ID or link on each line.
+        ///
+        /// Blank lines and lines starting with '#' are ignored.
+        #[arg(long)]
+        ids_file: Option<PathBuf>,
+        /// The ID of the master video.
+        #[arg(long)]
+        master_id: u32,
+        /// The YouTube ID of the video that should be the primary video for the master.
+        ///
+        /// It must be one of the videos being imported. Any existing primary video for the master
+        /// is no longer marked as primary.
+        #[arg(long)]
+        primary_id: Option<String>,
+    },
     /// List all videos
     #[clap(name = "ls")]
     Ls {},
@@ -450,6 +1024,36 @@ enum VideosSubcommands {
     },
 }
 
+/// Browse the SQLite archive of YouTube mirrors.
+#[derive(Subcommand, Debug)]
+enum YouTubeSubcommands {
+    /// List the channels in the archive, or the videos from one channel.
+    ///
+    /// Videos that have already been imported are marked.
+    #[clap(name = "ls")]
+    Ls {
+        /// The ID or title of a channel to list the videos from, newest first.
+        #[arg(long)]
+        channel: Option<String>,
+    },
+    /// Print the details of a video in the archive.
+    #[clap(name = "print")]
+    Print {
+        /// The YouTube ID of the video.
+        #[arg(long)]
+        id: String,
+    },
+    /// Search the titles of the videos in the archive, ignoring case.
+    ///
+    /// Videos that have already been imported are marked.
+    #[clap(name = "search")]
+    Search {
+        /// The text to search for. A video whose ID matches is also found.
+        #[arg(long)]
+        term: String,
+    },
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     color_eyre::install()?;
@@ -472,24 +1076,140 @@ async fn main() -> Result<()> {
                 cumulus_export_path,
             } => cmd::cumulus::ls_fields(&cumulus_export_path).await,
         },
+        Commands::Dataset(dataset_command) => match dataset_command {
+            DatasetSubcommands::Export { dir } => cmd::dataset::export(&dir).await,
+            DatasetSubcommands::Import { dir } => cmd::dataset::import(&dir).await,
+        },
+        Commands::EventTypes(event_types_command) => match event_types_command {
+            EventTypesSubcommands::Add {
+                slug,
+                label,
+                description,
+                canonical_time,
+            } => cmd::event_types::add(slug, label, description, canonical_time).await,
+            EventTypesSubcommands::Edit {
+                slug,
+                label,
+                description,
+                canonical_time,
+                no_canonical_time,
+            } => {
+                let canonical_time = if no_canonical_time {
+                    Some(None)
+                } else {
+                    canonical_time.map(Some)
+                };
+                cmd::event_types::edit(slug, label, description, canonical_time).await
+            }
+            EventTypesSubcommands::Ls {} => cmd::event_types::ls().await,
+            EventTypesSubcommands::Rm { slug } => cmd::event_types::rm(slug).await,
+        },
+        Commands::History {
+            entity,
+            id,
+            restore,
+        } => cmd::history::history(entity, id, restore).await,
         Commands::MasterVideos(master_videos_command) => match master_videos_command {
-            MasterVideosSubcommands::Add { path } => cmd::master_videos::add(path).await,
-            MasterVideosSubcommands::Edit { id } => cmd::master_videos::edit(id).await,
+            MasterVideosSubcommands::Add {
+                path,
+                prompt,
+                nist_video_id,
+            } => cmd::master_videos::add(path, prompt, nist_video_id).await,
+            MasterVideosSubcommands::AuditTimes { id, tolerance } => {
+                cmd::master_videos::audit_times(id, tolerance).await
+            }
+            MasterVideosSubcommands::Edit { id, prompt } => {
+                cmd::master_videos::edit(id, prompt).await
+            }
+            MasterVideosSubcommands::ExportChapters {
+                id,
+                format,
+                out_path,
+                video_length,
+            } => {
+                cmd::master_videos::export_chapters(id, format, out_path.as_deref(), video_length)
+                    .await
+            }
             MasterVideosSubcommands::Ls {} => cmd::master_videos::ls().await,
             MasterVideosSubcommands::Print { id } => cmd::master_videos::print(id).await,
+            MasterVideosSubcommands::SyncTimes {
+                id,
+                anchors,
+                dry_run,
+                key_events,
+                tolerance,
+            } => cmd::master_videos::sync_times(id, anchors, key_events, tolerance, dry_run).await,
+            MasterVideosSubcommands::Timestamps(timestamps_command) => match timestamps_command {
+                MasterVideosTimestampsSubcommands::Add {
+                    id,
+                    offset,
+                    end,
+                    event_type,
+                    time_of_day,
+                    description,
+                } => {
+                    cmd::master_videos::timestamps_add(
+                        id,
+                        offset,
+                        end,
+                        event_type,
+                        time_of_day,
+                        description,
+                    )
+                    .await
+                }
+                MasterVideosTimestampsSubcommands::Edit {
+                    id,
+                    timestamp_id,
+                    offset,
+                    end,
+                    no_end,
+                    event_type,
+                    time_of_day,
+                    no_time_of_day,
+                    description,
+                } => {
+                    let changes = cmd::master_videos::TimestampChanges {
+                        description,
+                        end: if no_end { Some(None) } else { end.map(Some) },
+                        event_type,
+                        offset,
+                        time_of_day: if no_time_of_day {
+                            Some(None)
+                        } else {
+                            time_of_day.map(Some)
+                        },
+                    };
+                    cmd::master_videos::timestamps_edit(id, timestamp_id, changes).await
+                }
+                MasterVideosTimestampsSubcommands::Ls { id } => {
+                    cmd::master_videos::timestamps_ls(id).await
+                }
+                MasterVideosTimestampsSubcommands::Rm { id, timestamp_id } => {
+                    cmd::master_videos::timestamps_rm(id, timestamp_id).await
+                }
+            },
         },
         Commands::News(news_command) => match news_command {
             NewsSubcommands::Affiliates(affiliates_command) => match affiliates_command {
-                NewsAffiliatesSubcommands::Add { path } => cmd::news_affiliates::add(path).await,
-                NewsAffiliatesSubcommands::Edit { id } => cmd::news_affiliates::edit(id).await,
+                NewsAffiliatesSubcommands::Add { path, prompt } => {
+                    cmd::news_affiliates::add(path, prompt).await
+                }
+                NewsAffiliatesSubcommands::Edit { id, prompt } => {
+                    cmd::news_affiliates::edit(id, prompt).await
+                }
                 NewsAffiliatesSubcommands::Ls {} => cmd::news_affiliates::ls().await,
                 NewsAffiliatesSubcommands::Print { id } => cmd::news_affiliates::print(id).await,
             },
             NewsSubcommands::Broadcasts(broadcasts_command) => match broadcasts_command {
-                NewsBroadcastsSubcommands::Add { path } => cmd::news_broadcasts::add(path).await,
-                NewsBroadcastsSubcommands::Edit { id } => cmd::news_broadcasts::edit(id).await,
+                NewsBroadcastsSubcommands::Add { path, prompt } => {
+                    cmd::news_broadcasts::add(path, prompt).await
+                }
+                NewsBroadcastsSubcommands::Edit { id, prompt } => {
+                    cmd::news_broadcasts::edit(id, prompt).await
+                }
                 NewsBroadcastsSubcommands::Ls {} => cmd::news_broadcasts::ls().await,
-                NewsBroadcastsSubcommands::Print { id } => cmd::news_broadcasts::edit(id).await,
+                NewsBroadcastsSubcommands::Print { id } => cmd::news_broadcasts::print(id).await,
             },
             NewsSubcommands::Networks(networks_command) => match networks_command {
                 NewsNetworksSubcommands::Add { path } => cmd::news_networks::add(path).await,
@@ -500,14 +1220,47 @@ async fn main() -> Result<()> {
         },
         Commands::Nist(nist_command) => match nist_command {
             NistSubcommands::Import(import_command) => match import_command {
-                NistImportSubcommands::DocumentNumbers {} => {
-                    cmd::nist_import::document_numbers().await
+                NistImportSubcommands::Mapping {
+                    csv,
+                    column,
+                    kind,
+                    entity,
+                    foia_response,
+                    id_column,
+                } => {
+                    cmd::nist_import::mapping(
+                        &csv,
+                        &column,
+                        kind,
+                        entity,
+                        id_column,
+                        &foia_response,
+                    )
+                    .await
+                }
+                NistImportSubcommands::Mdb { path, dry_run } => {
+                    cmd::nist_import::mdb(&path, dry_run).await
+                }
+                NistImportSubcommands::Tapes { path, dry_run } => {
+                    cmd::nist_import::tapes(&path, dry_run).await
+                }
+                NistImportSubcommands::Videos { path, dry_run } => {
+                    cmd::nist_import::videos(&path, dry_run).await
                 }
-                NistImportSubcommands::Tapes { path } => cmd::nist_import::tapes(&path).await,
-                NistImportSubcommands::Videos { path } => cmd::nist_import::videos(&path).await,
             },
+            NistSubcommands::Status {
+                by,
+                format,
+                out_path,
+            } => cmd::nist_status::status(by, format, out_path.as_deref()).await,
             NistSubcommands::Tapes(tapes_command) => match tapes_command {
+                NistTapesSubcommands::Allocate {
+                    csv,
+                    dry_run,
+                    replace,
+                } => cmd::nist_tapes::allocate(&csv, replace, dry_run).await,
                 NistTapesSubcommands::Edit { id } => cmd::nist_tapes::edit(id).await,
+                NistTapesSubcommands::Lineage { id } => cmd::nist_tapes::lineage(id).await,
                 NistTapesSubcommands::Ls {
                     exclude_missing,
                     find,
@@ -524,13 +1277,32 @@ async fn main() -> Result<()> {
                     )
                     .await
                 }
+                NistTapesSubcommands::Orphans {} => cmd::nist_tapes::orphans().await,
                 NistTapesSubcommands::Print { id } => cmd::nist_tapes::print(id).await,
             },
             NistSubcommands::Videos(videos_command) => match videos_command {
                 NistVideosSubcommands::Edit { id } => cmd::nist_videos::edit(id).await,
+                NistVideosSubcommands::ByNetwork {} => cmd::nist_videos::by_network().await,
                 NistVideosSubcommands::Ls {} => cmd::nist_videos::ls().await,
+                NistVideosSubcommands::MapNetworks { remap } => {
+                    cmd::nist_videos::map_networks(remap).await
+                }
+                NistVideosSubcommands::NoMaster { exclude_missing } => {
+                    cmd::nist_videos::no_master(exclude_missing).await
+                }
             },
         },
+        Commands::Records(records_command) => match records_command {
+            RecordsSubcommands::Export {
+                entity,
+                format,
+                id,
+                out_path,
+            } => cmd::records::export(entity, id, format, out_path.as_deref()).await,
+            RecordsSubcommands::Import { entity, path } => {
+                cmd::records::import(entity, &path).await
+            }
+        },
         Commands::Releases(releases_command) => match releases_command {
             ReleasesSubcommands::DownloadTorrents { path } => {
                 cmd::releases::download_torrents(&path).await
@@ -553,17 +1325,37 @@ async fn main() -> Result<()> {
                 }
             },
         },
+        Commands::Search { query, limit } => cmd::search::search(&query, limit).await,
+        Commands::Timeline {
+            end_time,
+            event_type,
+            format,
+            network,
+            out_path,
+            person,
+            start_time,
+        } => {
+            let filter = cmd::timeline::TimelineFilter {
+                end_time,
+                event_types: event_type,
+                network,
+                person,
+                start_time,
+            };
+            cmd::timeline::timeline(filter, format, out_path.as_deref()).await
+        }
         Commands::Videos(videos_command) => match videos_command {
             VideosSubcommands::Add {
                 master_id,
                 path,
+                prompt,
                 youtube_id,
-            } => cmd::videos::add(master_id, path, youtube_id).await,
+            } => cmd::videos::add(master_id, path, prompt, youtube_id).await,
             VideosSubcommands::Convert {
                 cumulus_export_path,
                 out_path,
             } => cmd::videos::convert(&cumulus_export_path, &out_path).await,
-            VideosSubcommands::Edit { id } => cmd::videos::edit(id).await,
+            VideosSubcommands::Edit { id, prompt } => cmd::videos::edit(id, prompt).await,
             VideosSubcommands::Export {
                 end_release_id,
                 out_path,
@@ -572,8 +1364,22 @@ async fn main() -> Result<()> {
             VideosSubcommands::ExportMaster { out_path } => {
                 cmd::videos::export_master(&out_path).await
             }
+            VideosSubcommands::ImportYoutube {
+                channel,
+                dry_run,
+                ids_file,
+                master_id,
+                primary_id,
+            } => {
+                cmd::videos::import_youtube(master_id, ids_file, channel, primary_id, dry_run).await
+            }
             VideosSubcommands::Ls {} => cmd::videos::ls().await,
             VideosSubcommands::Print { id } => cmd::videos::print(id).await,
         },
+        Commands::YouTube(youtube_command) => match youtube_command {
+            YouTubeSubcommands::Ls { channel } => cmd::youtube::ls(channel).await,
+            YouTubeSubcommands::Print { id } => cmd::youtube::print(&id).await,
+            YouTubeSubcommands::Search { term } => cmd::youtube::search(&term).await,
+        },
     }
 }
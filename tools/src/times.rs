@@ -0,0 +1,681 @@
+use chrono::{Duration, NaiveTime};
+use color_eyre::{eyre::eyre, Result};
+use db::helpers::{duration_to_string, interval_to_duration, parse_duration};
+use db::models::{EventTimestamp, EventType};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AnchorSource {
+    /// A key event whose time of day is known, e.g., the impact on the south tower at 09:03.
+    KeyEvent(EventType),
+    /// An offset and time of day supplied by the user.
+    Manual,
+    /// A timestamp that already has a time of day.
+    TimeOfDay,
+}
+
+/// A point in a video where the time of day is known.
+#[derive(Clone, Debug)]
+pub struct Anchor {
+    pub offset: Duration,
+    pub source: AnchorSource,
+    pub time_of_day: NaiveTime,
+    timestamp_index: Option<usize>,
+}
+
+impl Anchor {
+    pub fn manual(offset: Duration, time_of_day: NaiveTime) -> Self {
+        Self {
+            offset,
+            source: AnchorSource::Manual,
+            time_of_day,
+            timestamp_index: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SyncStatus {
+    /// The time of day of the timestamp was used as an anchor.
+    Anchor,
+    /// The time of day was derived from the anchor at the given offset.
+    Derived { anchor_offset: Duration },
+    /// The time of day of the timestamp disagrees with the anchors before it. The expected time is
+    /// the one the previous anchor would give.
+    Inconsistent { expected: NaiveTime },
+}
+
+#[derive(Clone, Debug)]
+pub struct SyncedTimestamp {
+    pub id: i32,
+    pub description: String,
+    pub event_type: EventType,
+    pub offset: Duration,
+    pub previous: Option<NaiveTime>,
+    pub status: SyncStatus,
+    pub time_of_day: Option<NaiveTime>,
+}
+
+impl SyncedTimestamp {
+    pub fn is_changed(&self) -> bool {
+        self.time_of_day != self.previous
+    }
+}
+
+/// A break in the footage, detected by an anchor being later than the previous anchor predicts.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gap {
+    pub length: Duration,
+    pub offset: Duration,
+}
+
+#[derive(Debug, Default)]
+pub struct SyncResult {
+    pub gaps: Vec<Gap>,
+    pub timestamps: Vec<SyncedTimestamp>,
+}
+
+/// Parses an anchor in the form `OFFSET=HHMM`, e.g., `00:20:00=0903`.
+pub fn parse_anchor(s: &str) -> Result<Anchor, String> {
+    let (offset, time) = s
+        .split_once('=')
+        .ok_or_else(|| format!("'{s}' is not an anchor in OFFSET=HHMM form"))?;
+    let offset = parse_duration(offset);
+    if offset.is_zero() && !offset_is_zero(s) {
+        return Err(format!("'{s}' does not have a valid offset"));
+    }
+    let time_of_day = crate::helpers::parse_time_of_day(time)?;
+    Ok(Anchor::manual(offset, time_of_day))
+}
+
+/// Parses an offset into a video in the `HH:MM:SS` form, e.g., `00:20:00`.
+pub fn parse_offset(s: &str) -> Result<Duration, String> {
+    let parts: Vec<&str> = s.split(':').collect();
+    if parts.len() != 3
+        || parts
+            .iter()
+            .any(|p| p.is_empty() || !p.chars().all(|c| c.is_ascii_digit()))
+    {
+        return Err(format!("'{s}' is not an offset in HH:MM:SS form"));
+    }
+    Ok(parse_duration(s))
+}
+
+fn offset_is_zero(s: &str) -> bool {
+    s.split('=')
+        .next()
+        .is_some_and(|o| !o.is_empty() && o.chars().all(|c| c == '0' || c == ':'))
+}
+
+/// Derives the time of day for the timestamps of a video from a set of anchors.
+///
+/// Anchors come from three places: those supplied by the user, timestamps that already have a time
+/// of day, and, if `use_key_events` is set, the first occurrence of each key event that doesn't
+/// have one. Each anchor is checked against the one before it. Footage is rarely continuous, so
+/// an anchor that is later than predicted is taken as the start of a new segment and recorded as a
+/// gap. An anchor that is earlier than predicted can't be explained by a break in the footage, and
+/// is likely a replay or a mistake, so it is flagged as inconsistent and not used. Anchors supplied
+/// by the user are always accepted.
+///
+/// Every other timestamp gets its time of day from the nearest anchor before it; timestamps before
+/// the first anchor are derived from that anchor.
+pub fn sync_times(
+    timestamps: &[EventTimestamp],
+    manual_anchors: &[Anchor],
+    use_key_events: bool,
+    tolerance: Duration,
+) -> Result<SyncResult> {
+    let mut candidates: Vec<Anchor> = manual_anchors.to_vec();
+    let mut seen_key_events = Vec::new();
+    for (i, timestamp) in timestamps.iter().enumerate() {
+        let offset = interval_to_duration(&timestamp.timestamp);
+        if let Some(time_of_day) = timestamp.time_of_day {
+            candidates.push(Anchor {
+                offset,
+                source: AnchorSource::TimeOfDay,
+                time_of_day,
+                timestamp_index: Some(i),
+            });
+        }
+    }
+    if use_key_events {
+        let mut by_offset: Vec<(usize, &EventTimestamp)> = timestamps.iter().enumerate().collect();
+        by_offset.sort_by_key(|(_, t)| interval_to_duration(&t.timestamp));
+        for (i, timestamp) in by_offset {
+            if timestamp.time_of_day.is_some() || seen_key_events.contains(&timestamp.event_type) {
+                continue;
+            }
+            if let Some(time_of_day) = timestamp.event_type.canonical_time {
+                seen_key_events.push(timestamp.event_type.clone());
+                candidates.push(Anchor {
+                    offset: interval_to_duration(&timestamp.timestamp),
+                    source: AnchorSource::KeyEvent(timestamp.event_type.clone()),
+                    time_of_day,
+                    timestamp_index: Some(i),
+                });
+            }
+        }
+    }
+    if candidates.is_empty() {
+        return Err(eyre!(
+            "There are no anchors to derive the time of day from. Supply an anchor or add a time of day to at least one timestamp."
+        ));
+    }
+    candidates.sort_by_key(|a| a.offset);
+
+    let mut result = SyncResult::default();
+    let mut accepted: Vec<Anchor> = Vec::new();
+    let mut rejected: Vec<(usize, NaiveTime)> = Vec::new();
+    for candidate in candidates {
+        if let Some(previous) = accepted.last() {
+            let expected = previous.time_of_day + (candidate.offset - previous.offset);
+            let difference = candidate.time_of_day - expected;
+            if difference < -tolerance && candidate.source != AnchorSource::Manual {
+                if let Some(i) = candidate.timestamp_index {
+                    rejected.push((i, expected));
+                }
+                continue;
+            }
+            if difference > tolerance {
+                result.gaps.push(Gap {
+                    length: difference,
+                    offset: candidate.offset,
+                });
+            }
+        }
+        accepted.push(candidate);
+    }
+
+    for (i, timestamp) in timestamps.iter().enumerate() {
+        let offset = interval_to_duration(&timestamp.timestamp);
+        let (status, time_of_day) =
+            if let Some(anchor) = accepted.iter().find(|a| a.timestamp_index == Some(i)) {
+                (SyncStatus::Anchor, Some(anchor.time_of_day))
+            } else if let Some((_, expected)) = rejected.iter().find(|(r, _)| *r == i) {
+                (
+                    SyncStatus::Inconsistent {
+                        expected: *expected,
+                    },
+                    timestamp.time_of_day,
+                )
+            } else {
+                let anchor = accepted
+                    .iter()
+                    .rev()
+                    .find(|a| a.offset <= offset)
+                    .unwrap_or(&accepted[0]);
+                (
+                    SyncStatus::Derived {
+                        anchor_offset: anchor.offset,
+                    },
+                    Some(anchor.time_of_day + (offset - anchor.offset)),
+                )
+            };
+        result.timestamps.push(SyncedTimestamp {
+            id: timestamp.id,
+            description: timestamp.description.clone(),
+            event_type: timestamp.event_type.clone(),
+            offset,
+            previous: timestamp.time_of_day,
+            status,
+            time_of_day,
+        });
+    }
+    result.timestamps.sort_by_key(|t| t.offset);
+    Ok(result)
+}
+
+/// A problem found when comparing the key events of a video with their canonical times.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AuditIssue {
+    /// The offsets between two key events are shorter than the time between the events, so
+    /// footage is missing between them.
+    Edited {
+        from: EventType,
+        missing: Duration,
+        to: EventType,
+    },
+    /// The offsets between two key events are longer than the time between the events, which can't
+    /// happen in live footage, so one of the events is likely tagged incorrectly.
+    Overrun {
+        excess: Duration,
+        from: EventType,
+        to: EventType,
+    },
+    /// The time of day of a key event doesn't match the time the event happened.
+    WrongTime {
+        canonical: NaiveTime,
+        event_type: EventType,
+        inferred: bool,
+        offset: Duration,
+        time_of_day: NaiveTime,
+    },
+}
+
+impl std::fmt::Display for AuditIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuditIssue::Edited { from, missing, to } => write!(
+                f,
+                "{} missing between {from} and {to}; the footage has been edited",
+                duration_to_string(missing)
+            ),
+            AuditIssue::Overrun { excess, from, to } => write!(
+                f,
+                "{} too long between {from} and {to}; one of them may be tagged incorrectly",
+                duration_to_string(excess)
+            ),
+            AuditIssue::WrongTime {
+                canonical,
+                event_type,
+                inferred,
+                offset,
+                time_of_day,
+            } => write!(
+                f,
+                "{event_type} at {} is at {}{} rather than {}",
+                duration_to_string(offset),
+                time_of_day.format("%H%M"),
+                if *inferred { " (inferred)" } else { "" },
+                canonical.format("%H%M")
+            ),
+        }
+    }
+}
+
+/// Checks the key events of a video against the times the events are known to have happened.
+///
+/// Each key event is compared with its canonical time, using its own time of day, or the time
+/// derived from the other timestamps if it doesn't have one. The offsets between the first
+/// occurrence of each key event are then compared with the time between the events.
+pub fn audit_times(timestamps: &[EventTimestamp], tolerance: Duration) -> Vec<AuditIssue> {
+    let inferred = if timestamps.iter().any(|t| t.time_of_day.is_some()) {
+        sync_times(timestamps, &[], false, tolerance)
+            .map(|r| r.timestamps)
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
+    let mut key_events: Vec<(Duration, &EventTimestamp, NaiveTime)> = timestamps
+        .iter()
+        .filter_map(|t| {
+            t.event_type
+                .canonical_time
+                .map(|c| (interval_to_duration(&t.timestamp), t, c))
+        })
+        .collect();
+    key_events.sort_by_key(|(offset, _, _)| *offset);
+
+    let mut issues = Vec::new();
+    for (offset, timestamp, canonical) in key_events.iter() {
+        let (time_of_day, is_inferred) = match timestamp.time_of_day {
+            Some(time_of_day) => (Some(time_of_day), false),
+            None => (
+                inferred
+                    .iter()
+                    .find(|t| t.id == timestamp.id && t.offset == *offset)
+                    .and_then(|t| t.time_of_day),
+                true,
+            ),
+        };
+        if let Some(time_of_day) = time_of_day {
+            if (time_of_day - *canonical).abs() > tolerance {
+                issues.push(AuditIssue::WrongTime {
+                    canonical: *canonical,
+                    event_type: timestamp.event_type.clone(),
+                    inferred: is_inferred,
+                    offset: *offset,
+                    time_of_day,
+                });
+            }
+        }
+    }
+
+    let mut firsts: Vec<&(Duration, &EventTimestamp, NaiveTime)> = Vec::new();
+    for key_event in key_events.iter() {
+        if !firsts
+            .iter()
+            .any(|f| f.1.event_type == key_event.1.event_type)
+        {
+            firsts.push(key_event);
+        }
+    }
+    for pair in firsts.windows(2) {
+        let (from_offset, from, from_time) = pair[0];
+        let (to_offset, to, to_time) = pair[1];
+        let difference = (*to_offset - *from_offset) - (*to_time - *from_time);
+        if difference < -tolerance {
+            issues.push(AuditIssue::Edited {
+                from: from.event_type.clone(),
+                missing: -difference,
+                to: to.event_type.clone(),
+            });
+        } else if difference > tolerance {
+            issues.push(AuditIssue::Overrun {
+                excess: difference,
+                from: from.event_type.clone(),
+                to: to.event_type.clone(),
+            });
+        }
+    }
+    issues
+}
+
+/// A problem with the offsets of the timestamps of a video.
+///
+/// Each issue has the index of the timestamp it concerns.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TimestampIssue {
+    /// The timestamp ends at or before the point it starts.
+    EndsBeforeStart {
+        end: Duration,
+        index: usize,
+        offset: Duration,
+    },
+    /// The timestamp is earlier than the one before it.
+    OutOfOrder {
+        index: usize,
+        offset: Duration,
+        previous: Duration,
+    },
+    /// The timestamp starts or ends after the end of the video.
+    PastEnd {
+        index: usize,
+        length: Duration,
+        offset: Duration,
+    },
+}
+
+impl TimestampIssue {
+    pub fn index(&self) -> usize {
+        match self {
+            TimestampIssue::EndsBeforeStart { index, .. } => *index,
+            TimestampIssue::OutOfOrder { index, .. } => *index,
+            TimestampIssue::PastEnd { index, .. } => *index,
+        }
+    }
+}
+
+impl std::fmt::Display for TimestampIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimestampIssue::EndsBeforeStart { end, offset, .. } => write!(
+                f,
+                "the end at {} is not after the start at {}",
+                duration_to_string(end),
+                duration_to_string(offset)
+            ),
+            TimestampIssue::OutOfOrder {
+                offset, previous, ..
+            } => write!(
+                f,
+                "{} is earlier than the timestamp before it at {}",
+                duration_to_string(offset),
+                duration_to_string(previous)
+            ),
+            TimestampIssue::PastEnd { length, offset, .. } => write!(
+                f,
+                "{} is after the end of the video at {}",
+                duration_to_string(offset),
+                duration_to_string(length)
+            ),
+        }
+    }
+}
+
+/// Checks that the timestamps of a video are in order and fall within the video.
+///
+/// The length of the video is only known when it has a video with a duration, so the timestamps
+/// are only checked against it when it's provided.
+pub fn check_timestamps(
+    timestamps: &[EventTimestamp],
+    video_length: Option<Duration>,
+) -> Vec<TimestampIssue> {
+    let mut issues = Vec::new();
+    let mut previous: Option<Duration> = None;
+    for (index, timestamp) in timestamps.iter().enumerate() {
+        let offset = interval_to_duration(&timestamp.timestamp);
+        let end = timestamp.end_timestamp.as_ref().map(interval_to_duration);
+        if let Some(previous) = previous.filter(|p| offset < *p) {
+            issues.push(TimestampIssue::OutOfOrder {
+                index,
+                offset,
+                previous,
+            });
+        }
+        if let Some(end) = end.filter(|e| *e <= offset) {
+            issues.push(TimestampIssue::EndsBeforeStart { end, index, offset });
+        }
+        if let Some(length) = video_length {
+            let last = end.unwrap_or(offset).max(offset);
+            if last > length {
+                issues.push(TimestampIssue::PastEnd {
+                    index,
+                    length,
+                    offset: last,
+                });
+            }
+        }
+        previous = Some(offset);
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::postgres::types::PgInterval;
+
+    /// An event type, with the canonical time of the key events.
+    fn event_type(slug: &str) -> EventType {
+        let canonical_time = match slug {
+            "wtc1-impact" => NaiveTime::from_hms_opt(8, 46, 40),
+            "wtc2-impact" => NaiveTime::from_hms_opt(9, 3, 2),
+            "pentagon-attack" => NaiveTime::from_hms_opt(9, 37, 46),
+            "wtc2-collapse" => NaiveTime::from_hms_opt(9, 58, 59),
+            "wtc1-collapse" => NaiveTime::from_hms_opt(10, 28, 22),
+            _ => None,
+        };
+        EventType {
+            slug: slug.to_string(),
+            canonical_time,
+            ..Default::default()
+        }
+    }
+
+    fn timestamp(
+        id: i32,
+        offset: &str,
+        time_of_day: Option<NaiveTime>,
+        slug: &str,
+    ) -> EventTimestamp {
+        EventTimestamp {
+            id,
+            description: format!("Event at {offset}"),
+            timestamp: PgInterval::try_from(parse_duration(offset)).unwrap(),
+            end_timestamp: None,
+            event_type: event_type(slug),
+            time_of_day,
+        }
+    }
+
+    fn time(h: u32, m: u32, s: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, s).unwrap()
+    }
+
+    #[test]
+    fn sync_times_should_derive_times_from_a_time_of_day_anchor() {
+        let timestamps = vec![
+            timestamp(1, "00:00:30", None, "normal"),
+            timestamp(2, "00:10:00", Some(time(9, 0, 0)), "normal"),
+            timestamp(3, "00:13:30", None, "normal"),
+        ];
+        let result = sync_times(&timestamps, &[], false, Duration::minutes(2)).unwrap();
+        assert!(result.gaps.is_empty());
+        assert_eq!(result.timestamps[0].time_of_day, Some(time(8, 50, 30)));
+        assert_eq!(result.timestamps[1].status, SyncStatus::Anchor);
+        assert!(!result.timestamps[1].is_changed());
+        assert_eq!(result.timestamps[2].time_of_day, Some(time(9, 3, 30)));
+        assert_eq!(
+            result.timestamps[2].status,
+            SyncStatus::Derived {
+                anchor_offset: Duration::minutes(10)
+            }
+        );
+    }
+
+    #[test]
+    fn sync_times_should_use_key_events_and_detect_gaps() {
+        let timestamps = vec![
+            timestamp(1, "00:20:00", None, "wtc2-impact"),
+            timestamp(2, "00:25:00", None, "normal"),
+            timestamp(3, "00:40:00", None, "wtc2-collapse"),
+            timestamp(4, "00:45:00", None, "normal"),
+        ];
+        let result = sync_times(&timestamps, &[], false, Duration::minutes(2));
+        assert!(result.is_err());
+
+        let result = sync_times(&timestamps, &[], true, Duration::minutes(2)).unwrap();
+        assert_eq!(result.timestamps[0].time_of_day, Some(time(9, 3, 2)));
+        assert_eq!(result.timestamps[1].time_of_day, Some(time(9, 8, 2)));
+        assert_eq!(result.timestamps[2].time_of_day, Some(time(9, 58, 59)));
+        assert_eq!(result.timestamps[3].time_of_day, Some(time(10, 3, 59)));
+        assert_eq!(result.gaps.len(), 1);
+        assert_eq!(result.gaps[0].offset, Duration::minutes(40));
+        assert_eq!(result.gaps[0].length, Duration::seconds(2157));
+    }
+
+    #[test]
+    fn sync_times_should_flag_anchors_earlier_than_predicted() {
+        let timestamps = vec![
+            timestamp(1, "00:20:00", None, "wtc2-impact"),
+            timestamp(2, "00:40:00", Some(time(9, 10, 0)), "normal"),
+            timestamp(3, "00:45:00", None, "normal"),
+        ];
+        let anchors = vec![parse_anchor("00:00:00=0843").unwrap()];
+        let result = sync_times(&timestamps, &anchors, false, Duration::minutes(2)).unwrap();
+        assert_eq!(result.timestamps[0].time_of_day, Some(time(9, 3, 0)));
+        assert_eq!(
+            result.timestamps[1].status,
+            SyncStatus::Inconsistent {
+                expected: time(9, 23, 0)
+            }
+        );
+        assert!(!result.timestamps[1].is_changed());
+        assert_eq!(result.timestamps[2].time_of_day, Some(time(9, 28, 0)));
+    }
+
+    #[test]
+    fn parse_offset_should_only_accept_offsets_with_hours_minutes_and_seconds() {
+        assert_eq!(parse_offset("00:20:05").unwrap(), Duration::seconds(1205));
+        assert_eq!(parse_offset("00:00:00").unwrap(), Duration::zero());
+        assert!(parse_offset("20:05").is_err());
+        assert!(parse_offset("00:2a:05").is_err());
+    }
+
+    #[test]
+    fn parse_anchor_should_reject_invalid_input() {
+        assert!(parse_anchor("00:20:00").is_err());
+        assert!(parse_anchor("abc=0903").is_err());
+        assert!(parse_anchor("00:20:00=9am").is_err());
+        let anchor = parse_anchor("20m=0903").unwrap();
+        assert_eq!(anchor.offset, Duration::minutes(20));
+        assert_eq!(anchor.time_of_day, time(9, 3, 0));
+    }
+
+    #[test]
+    fn audit_times_should_accept_consistent_key_events() {
+        let timestamps = vec![
+            timestamp(1, "00:20:00", Some(time(9, 3, 0)), "wtc2-impact"),
+            timestamp(2, "01:16:10", None, "wtc2-collapse"),
+            timestamp(3, "01:45:31", Some(time(10, 28, 0)), "wtc1-collapse"),
+        ];
+        assert!(audit_times(&timestamps, Duration::minutes(2)).is_empty());
+    }
+
+    #[test]
+    fn audit_times_should_flag_edits_and_wrong_tags() {
+        let timestamps = vec![
+            timestamp(1, "00:20:00", None, "wtc2-impact"),
+            timestamp(2, "00:30:00", None, "wtc2-collapse"),
+            timestamp(3, "01:30:00", None, "wtc1-collapse"),
+        ];
+        let issues = audit_times(&timestamps, Duration::minutes(2));
+        assert_eq!(issues.len(), 2);
+        assert!(matches!(
+            &issues[0],
+            AuditIssue::Edited { from, to, .. }
+                if from.slug == "wtc2-impact" && to.slug == "wtc2-collapse"
+        ));
+        assert!(matches!(
+            &issues[1],
+            AuditIssue::Overrun { from, to, .. }
+                if from.slug == "wtc2-collapse" && to.slug == "wtc1-collapse"
+        ));
+    }
+
+    #[test]
+    fn audit_times_should_flag_times_that_do_not_match_the_event() {
+        let timestamps = vec![
+            timestamp(1, "00:00:00", Some(time(9, 30, 0)), "normal"),
+            timestamp(2, "00:10:00", None, "pentagon-attack"),
+            timestamp(3, "00:20:00", Some(time(9, 40, 0)), "wtc1-impact"),
+        ];
+        let issues = audit_times(&timestamps, Duration::minutes(2));
+        assert_eq!(issues.len(), 3);
+        assert_eq!(
+            issues[0],
+            AuditIssue::WrongTime {
+                canonical: time(9, 37, 46),
+                event_type: event_type("pentagon-attack"),
+                inferred: true,
+                offset: Duration::minutes(10),
+                time_of_day: time(9, 40, 0),
+            }
+        );
+        assert!(matches!(
+            issues[1],
+            AuditIssue::WrongTime {
+                inferred: false,
+                ..
+            }
+        ));
+        assert!(matches!(issues[2], AuditIssue::Overrun { .. }));
+    }
+
+    #[test]
+    fn check_timestamps_should_flag_timestamps_out_of_order_or_past_the_end() {
+        let mut timestamps = vec![
+            timestamp(1, "00:00:00", None, "normal"),
+            timestamp(2, "00:20:00", None, "wtc2-impact"),
+            timestamp(3, "00:10:00", None, "report"),
+            timestamp(4, "00:30:00", None, "report"),
+        ];
+        timestamps[2].end_timestamp = PgInterval::try_from(Duration::minutes(5)).ok();
+        timestamps[3].end_timestamp = PgInterval::try_from(Duration::minutes(45)).ok();
+
+        assert_eq!(
+            check_timestamps(&timestamps, Some(Duration::minutes(40))),
+            vec![
+                TimestampIssue::OutOfOrder {
+                    index: 2,
+                    offset: Duration::minutes(10),
+                    previous: Duration::minutes(20),
+                },
+                TimestampIssue::EndsBeforeStart {
+                    end: Duration::minutes(5),
+                    index: 2,
+                    offset: Duration::minutes(10),
+                },
+                TimestampIssue::PastEnd {
+                    index: 3,
+                    length: Duration::minutes(40),
+                    offset: Duration::minutes(45),
+                },
+            ]
+        );
+
+        timestamps.remove(2);
+        assert!(check_timestamps(&timestamps, None).is_empty());
+    }
+}